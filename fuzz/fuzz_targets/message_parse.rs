@@ -0,0 +1,18 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate rust_ofp;
+
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_message::OfpMessage;
+use rust_ofp::openflow0x01::message::Message;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < OfpHeader::size() {
+        return;
+    }
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&data[..OfpHeader::size()]);
+    let header = OfpHeader::parse(raw);
+    let _ = Message::parse(&header, &data[OfpHeader::size()..]);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate rust_ofp;
+
+use rust_ofp::packet::Packet;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Packet::parse(data);
+});
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
-use std::net::TcpStream;
-use rust_ofp::ofp_controller::openflow0x01::OF0x01Controller;
+use std::io;
+use rust_ofp::ofp_controller::openflow0x01::{OF0x01Controller, Switches};
 use rust_ofp::openflow0x01::{Action, PacketIn, PacketOut, Pattern, PseudoPort, SwitchFeatures};
 use rust_ofp::openflow0x01::message::{add_flow, parse_payload};
+use rust_ofp::packet::Packet;
 
 /// Implements L2 learning switch functionality. Switches forward packets to the
 /// learning controller, which will examine the packet and learn the source-port
@@ -14,27 +15,34 @@ use rust_ofp::openflow0x01::message::{add_flow, parse_payload};
 /// distinct components.
 ///
 ///  - A _Learning Module_ that builds a map from host MAC addresses to the
-///    switch port on which they are connected.
+///    switch port on which they are connected, separately for each switch.
 ///
 ///  - A _Routing Module_ that performs traffic routing. If the switch receives
 ///    a packet which the learning module has learned of the destination location,
 ///    it forwards the packet directly on the associated port. If the location of
 ///    the destination is unknown, it floods the packet out all ports.
 pub struct LearningSwitch {
-    known_hosts: HashMap<u64, u16>,
+    known_hosts: HashMap<(u64, u64), u16>,
 }
 
 impl LearningSwitch {
-    fn learning_packet_in(&mut self, pkt: &PacketIn) {
-        let pk = parse_payload(&pkt.input_payload);
-        self.known_hosts.insert(pk.dl_src, pkt.port);
+    fn next_xid(sw: u64, switches: &Switches) -> u32 {
+        switches.next_xid(sw).unwrap_or(0)
     }
 
-    fn routing_packet_in(&mut self, sw: u64, pkt: PacketIn, stream: &mut TcpStream) {
-        let pk = parse_payload(&pkt.input_payload);
+    fn learning_packet_in(&mut self, sw: u64, pk: &Packet, pkt: &PacketIn) {
+        self.known_hosts.insert((sw, pk.dl_src), pkt.port);
+    }
+
+    fn routing_packet_in(&mut self,
+                         sw: u64,
+                         pk: Packet,
+                         pkt: PacketIn,
+                         switches: &Switches)
+                         -> io::Result<()> {
         let pkt_dst = pk.dl_dst;
         let pkt_src = pk.dl_src;
-        let out_port = self.known_hosts.get(&pkt_dst);
+        let out_port = self.known_hosts.get(&(sw, pkt_dst));
         match out_port {
             Some(p) => {
                 let src_port = pkt.port;
@@ -46,16 +54,18 @@ impl LearningSwitch {
                 dst_src_match.dl_src = Some(pkt_dst);
                 println!("Installing rule for host {:?} to {:?}.", pkt_src, pkt_dst);
                 let actions = vec![Action::Output(PseudoPort::PhysicalPort(*p))];
-                Self::send_flow_mod(sw, 0, add_flow(10, src_dst_match, actions), stream);
+                let xid = Self::next_xid(sw, switches);
+                Self::send_flow_mod(sw, xid, add_flow(10, src_dst_match, actions), switches)?;
                 println!("Installing rule for host {:?} to {:?}.", pkt_dst, pkt_src);
                 let actions = vec![Action::Output(PseudoPort::PhysicalPort(src_port))];
-                Self::send_flow_mod(sw, 0, add_flow(10, dst_src_match, actions), stream);
+                let xid = Self::next_xid(sw, switches);
+                Self::send_flow_mod(sw, xid, add_flow(10, dst_src_match, actions), switches)?;
                 let pkt_out = PacketOut {
                     output_payload: pkt.input_payload,
                     port_id: None,
                     apply_actions: vec![Action::Output(PseudoPort::PhysicalPort(*p))],
                 };
-                Self::send_packet_out(sw, 0, pkt_out, stream)
+                Self::send_packet_out(sw, Self::next_xid(sw, switches), pkt_out, switches)
             }
             None => {
                 println!("Flooding to {:?}", pkt_dst);
@@ -64,7 +74,7 @@ impl LearningSwitch {
                     port_id: None,
                     apply_actions: vec![Action::Output(PseudoPort::AllPorts)],
                 };
-                Self::send_packet_out(sw, 0, pkt_out, stream)
+                Self::send_packet_out(sw, Self::next_xid(sw, switches), pkt_out, switches)
             }
         }
     }
@@ -75,12 +85,21 @@ impl OF0x01Controller for LearningSwitch {
         LearningSwitch { known_hosts: HashMap::new() }
     }
 
-    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &mut TcpStream) {}
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
 
-    fn switch_disconnected(&mut self, _: u64) {}
+    fn switch_disconnected(&mut self, sw: u64) {
+        self.known_hosts.retain(|&(host_sw, _), _| host_sw != sw)
+    }
 
-    fn packet_in(&mut self, sw: u64, _: u32, pkt: PacketIn, stream: &mut TcpStream) {
-        self.learning_packet_in(&pkt);
-        self.routing_packet_in(sw, pkt, stream);
+    fn packet_in(&mut self, sw: u64, _: u32, pkt: PacketIn, switches: &Switches) {
+        match parse_payload(&pkt.input_payload) {
+            Ok(pk) => {
+                self.learning_packet_in(sw, &pk, &pkt);
+                if let Err(e) = self.routing_packet_in(sw, pk, pkt, switches) {
+                    println!("Failed to route packet on switch {}: {}", sw, e)
+                }
+            }
+            Err(e) => println!("Dropping unparsable packet: {}", e),
+        }
     }
 }
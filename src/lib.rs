@@ -2,15 +2,22 @@
 #![crate_type = "lib"]
 
 extern crate byteorder;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 
 pub mod learning_switch;
 
 mod bits;
+pub mod marshal_error;
+pub mod ofp_codec;
 pub mod ofp_controller;
 pub mod ofp_header;
+pub mod ofp_hello;
 pub mod ofp_message;
 pub mod openflow0x01;
+pub mod openflow0x04;
 pub mod packet;
+pub mod parse_error;
 
 mod rust_ofp {
     pub use super::*;
@@ -0,0 +1,23 @@
+use std::error;
+use std::fmt;
+
+/// Marshal Error
+///
+/// Reasons a message could not be marshaled into a buffer to send on the network.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MarshalError {
+    /// A field named by the first argument held a value that is not allowed in the message.
+    InvalidValue(&'static str, u64),
+}
+
+impl fmt::Display for MarshalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MarshalError::InvalidValue(field, v) => {
+                write!(f, "value {} not allowed for {}", v, field)
+            }
+        }
+    }
+}
+
+impl error::Error for MarshalError {}
@@ -0,0 +1,85 @@
+use std::io;
+use std::io::Read;
+
+use ofp_header::OfpHeader;
+use parse_error::ParseError;
+
+/// Split the first complete OpenFlow frame off the front of `buf`.
+///
+/// Returns the frame's header and body (the bytes following the header), or `None` if `buf`
+/// does not yet hold a complete frame. Fails if the header's `length` is smaller than the
+/// header itself, as no later frame in the stream can then be located.
+pub fn decode_frame(buf: &mut Vec<u8>) -> Result<Option<(OfpHeader, Vec<u8>)>, ParseError> {
+    if buf.len() < OfpHeader::size() {
+        return Ok(None);
+    }
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&buf[..OfpHeader::size()]);
+    let header = OfpHeader::parse(raw);
+    let len = header.length();
+    if len < OfpHeader::size() {
+        return Err(ParseError::BadLength("ofp_header", len));
+    }
+    if buf.len() < len {
+        return Ok(None);
+    }
+    let body = buf[OfpHeader::size()..len].to_vec();
+    buf.drain(..len);
+    Ok(Some((header, body)))
+}
+
+/// OpenFlow Message Reader
+///
+/// Reads whole OpenFlow frames from a byte stream, buffering partial reads and any bytes of
+/// subsequent frames until they are complete.
+pub struct MessageReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> MessageReader<R> {
+    /// Create a `MessageReader` reading from `inner`.
+    pub fn new(inner: R) -> MessageReader<R> {
+        MessageReader {
+            inner: inner,
+            buf: vec![],
+        }
+    }
+
+    /// Return a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the underlying reader. Reading from it directly will
+    /// desynchronise the `MessageReader`.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Block until the next complete frame arrives, returning its header and body. Returns
+    /// `None` if the stream ends cleanly between frames.
+    pub fn read_frame(&mut self) -> io::Result<Option<(OfpHeader, Vec<u8>)>> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match decode_frame(&mut self.buf) {
+                Ok(Some(frame)) => return Ok(Some(frame)),
+                Ok(None) => (),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+            let num_bytes = match self.inner.read(&mut chunk) {
+                Ok(num_bytes) => num_bytes,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if num_bytes == 0 {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                          "stream closed in the middle of a message"));
+            }
+            self.buf.extend_from_slice(&chunk[..num_bytes]);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::io;
 use std::net::TcpStream;
 use rust_ofp::ofp_message::OfpMessage;
 
@@ -9,138 +10,1592 @@ pub trait OfpController {
     type Message: OfpMessage;
 
     /// Send a message to the node associated with the given `TcpStream`.
-    fn send_message(u32, Self::Message, &mut TcpStream);
+    fn send_message(u32, Self::Message, &mut TcpStream) -> io::Result<()>;
     /// Perform handshake and begin loop reading incoming messages from client stream.
     fn handle_client_connected(&mut TcpStream);
 }
 
 pub mod openflow0x01 {
     use super::*;
-    use std::io::{Write, Read};
+    use std::any::Any;
+    use std::cmp::max;
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::io;
+    use std::io::Write;
     use std::marker::PhantomData;
-    use std::net::TcpStream;
+    use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+    use std::sync::{mpsc, Arc, Mutex, Weak};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
 
+    use rust_ofp::ofp_codec::MessageReader;
     use rust_ofp::ofp_header::OfpHeader;
+    use rust_ofp::ofp_hello::{Hello, OFPT_HELLO, OFP_VERSION_1_0};
     use rust_ofp::ofp_message::OfpMessage;
-    use rust_ofp::openflow0x01::{FlowMod, PacketIn, PacketOut, SwitchFeatures};
+    use rust_ofp::openflow0x01::{BadRequest, Error, ErrorType, FlowMod, FlowRemoved, HelloFailed,
+                                 PacketIn, PacketOut, PortMod, PortStatus, QueueGetConfigReq,
+                                 QueueGetConfigResp, StatsReq, StatsResp, StatsRespBody,
+                                 SwitchConfig, SwitchFeatures};
     use rust_ofp::openflow0x01::message::Message;
+    use rust_ofp::openflow0x01::vendor::{VendorKind, VendorRegistry, VendorValue};
 
+    /// Item queued to the task writing to an asynchronous switch connection.
+    #[cfg(feature = "tokio")]
+    enum Outbound {
+        Bytes(Vec<u8>),
+        Close,
+    }
+
+    /// Destination for messages sent to a switch through `Switches`.
+    enum SwitchSink {
+        Stream(TcpStream),
+        #[cfg(feature = "tokio")]
+        Channel(::tokio::sync::mpsc::UnboundedSender<Outbound>),
+    }
+
+    impl SwitchSink {
+        fn send(&mut self, bytes: Vec<u8>) -> io::Result<()> {
+            match *self {
+                SwitchSink::Stream(ref mut stream) => stream.write_all(&bytes),
+                #[cfg(feature = "tokio")]
+                SwitchSink::Channel(ref tx) => {
+                    tx.send(Outbound::Bytes(bytes)).map_err(|_| {
+                        io::Error::new(io::ErrorKind::BrokenPipe, "switch connection closed")
+                    })
+                }
+            }
+        }
+
+        /// Close the connection, causing the thread or task reading it to finish.
+        fn shutdown(&mut self) {
+            match *self {
+                SwitchSink::Stream(ref stream) => {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+                #[cfg(feature = "tokio")]
+                SwitchSink::Channel(ref tx) => {
+                    let _ = tx.send(Outbound::Close);
+                }
+            }
+        }
+    }
+
+    /// Reasons a request sent with `Switches::send_request` did not receive a reply.
     #[derive(Debug)]
-    struct ThreadState<Cntl> {
-        switch_id: Option<u64>,
-        phantom: PhantomData<Cntl>,
+    pub enum ReplyError {
+        /// The switch answered the request with an error message.
+        Switch(Error),
+        /// The switch disconnected before replying.
+        Disconnected,
+        /// No reply arrived within the time allowed.
+        Timeout,
     }
 
-    impl<Cntl: OF0x01Controller> ThreadState<Cntl> {
-        fn process_message(&mut self,
-                           cntl: &mut Cntl,
-                           xid: u32,
-                           msg: Message,
-                           stream: &mut TcpStream) {
+    impl fmt::Display for ReplyError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                ReplyError::Switch(ref err) => write!(f, "switch replied with {:?}", err),
+                ReplyError::Disconnected => write!(f, "switch disconnected before replying"),
+                ReplyError::Timeout => write!(f, "timed out waiting for reply"),
+            }
+        }
+    }
+
+    /// Handle to the reply to a request sent with `Switches::send_request`.
+    ///
+    /// Replies are delivered by the thread or task reading the switch's connection, so they
+    /// can be waited for from inside a controller callback, though that holds up the callbacks
+    /// of every switch until the reply arrives; `try_wait` never blocks.
+    pub struct PendingReply {
+        xid: u32,
+        rx: mpsc::Receiver<Result<Message, ReplyError>>,
+        conn: Weak<SwitchConn>,
+    }
+
+    impl PendingReply {
+        /// Return the transaction ID the request was sent with.
+        pub fn xid(&self) -> u32 {
+            self.xid
+        }
+
+        /// Block until the reply arrives.
+        pub fn wait(&self) -> Result<Message, ReplyError> {
+            self.rx.recv().unwrap_or(Err(ReplyError::Disconnected))
+        }
+
+        /// Block until the reply arrives or `timeout` elapses. A request that times out is
+        /// abandoned, and its reply is passed to controller callbacks if it arrives later.
+        pub fn wait_timeout(&self, timeout: Duration) -> Result<Message, ReplyError> {
+            match self.rx.recv_timeout(timeout) {
+                Ok(reply) => reply,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    self.forget();
+                    self.rx.try_recv().unwrap_or(Err(ReplyError::Timeout))
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => Err(ReplyError::Disconnected),
+            }
+        }
+
+        /// Return the reply if it has arrived, without blocking.
+        pub fn try_wait(&self) -> Option<Result<Message, ReplyError>> {
+            match self.rx.try_recv() {
+                Ok(reply) => Some(reply),
+                Err(mpsc::TryRecvError::Empty) => None,
+                Err(mpsc::TryRecvError::Disconnected) => Some(Err(ReplyError::Disconnected)),
+            }
+        }
+
+        /// Stop the connection waiting for the reply, if it is still open.
+        fn forget(&self) {
+            if let Some(conn) = self.conn.upgrade() {
+                conn.forget(self.xid)
+            }
+        }
+    }
+
+    impl Drop for PendingReply {
+        fn drop(&mut self) {
+            self.forget()
+        }
+    }
+
+    /// Marshal message `msg` with transaction ID `xid`, failing with `ErrorKind::InvalidInput`
+    /// if it cannot be sent.
+    fn marshal(xid: u32, msg: Message) -> io::Result<Vec<u8>> {
+        Message::marshal(xid, msg).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Request awaiting its reply, possibly partway through a multi-part statistics reply.
+    struct Pending {
+        tx: mpsc::Sender<Result<Message, ReplyError>>,
+        stats: Option<StatsResp>,
+    }
+
+    /// Append the entries of statistics reply `part` to those already received in `acc`.
+    fn merge_stats(acc: &mut StatsResp, part: StatsResp) {
+        acc.more = part.more;
+        match (&mut acc.body, part.body) {
+            (&mut StatsRespBody::Flow(ref mut acc), StatsRespBody::Flow(part)) => acc.extend(part),
+            (&mut StatsRespBody::Table(ref mut acc), StatsRespBody::Table(part)) => {
+                acc.extend(part)
+            }
+            (&mut StatsRespBody::Port(ref mut acc), StatsRespBody::Port(part)) => acc.extend(part),
+            (&mut StatsRespBody::Queue(ref mut acc), StatsRespBody::Queue(part)) => {
+                acc.extend(part)
+            }
+            (acc, part) => *acc = part,
+        }
+    }
+
+    /// Connection to a single switch, shared between the thread or task reading it and
+    /// `Switches`. Allocates transaction IDs and tracks requests awaiting replies.
+    struct SwitchConn {
+        sink: Mutex<SwitchSink>,
+        next_xid: AtomicUsize,
+        pending: Mutex<HashMap<u32, Pending>>,
+    }
+
+    impl SwitchConn {
+        fn new(sink: SwitchSink) -> SwitchConn {
+            SwitchConn {
+                sink: Mutex::new(sink),
+                next_xid: AtomicUsize::new(1),
+                pending: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// Allocate a transaction ID, never 0 as that is used by messages sent without one.
+        fn next_xid(&self) -> u32 {
+            loop {
+                let xid = self.next_xid.fetch_add(1, Ordering::SeqCst) as u32;
+                if xid != 0 {
+                    return xid;
+                }
+            }
+        }
+
+        fn send_bytes(&self, bytes: Vec<u8>) -> io::Result<()> {
+            self.sink.lock().unwrap().send(bytes)
+        }
+
+        fn send_message(&self, xid: u32, msg: Message) -> io::Result<()> {
+            self.send_bytes(marshal(xid, msg)?)
+        }
+
+        /// Wait for a reply or error carrying transaction ID `xid`.
+        fn track(self: &Arc<Self>, xid: u32) -> PendingReply {
+            let (tx, rx) = mpsc::channel();
+            self.pending.lock().unwrap().insert(xid,
+                                                Pending {
+                                                    tx: tx,
+                                                    stats: None,
+                                                });
+            PendingReply {
+                xid: xid,
+                rx: rx,
+                conn: Arc::downgrade(self),
+            }
+        }
+
+        /// Stop waiting for a reply carrying transaction ID `xid`.
+        fn forget(&self, xid: u32) {
+            self.pending.lock().unwrap().remove(&xid);
+        }
+
+        fn send_request(self: &Arc<Self>, msg: Message) -> io::Result<PendingReply> {
+            let reply = self.track(self.next_xid());
+            self.send_message(reply.xid, msg)?;
+            Ok(reply)
+        }
+
+        /// Complete the pending request with transaction ID `xid` if `msg` is its reply or an
+        /// error. Returns `msg` back if no request was waiting for it.
+        fn resolve(&self, xid: u32, msg: Message) -> Option<Message> {
+            match msg {
+                Message::Error(_) |
+                Message::EchoReply(_) |
+                Message::FeaturesReply(_) |
+                Message::GetConfigReply(_) |
+                Message::StatsReply(_) |
+                Message::QueueGetConfigReply(_) |
+                Message::BarrierReply => (),
+                _ => return Some(msg),
+            }
+            let mut pending = self.pending.lock().unwrap();
+            let mut request = match pending.remove(&xid) {
+                Some(request) => request,
+                None => return Some(msg),
+            };
+            let reply = match msg {
+                Message::Error(err) => Err(ReplyError::Switch(err)),
+                Message::StatsReply(part) => {
+                    let stats = match request.stats.take() {
+                        Some(mut stats) => {
+                            merge_stats(&mut stats, part);
+                            stats
+                        }
+                        None => part,
+                    };
+                    if stats.more {
+                        request.stats = Some(stats);
+                        pending.insert(xid, request);
+                        return None;
+                    }
+                    Ok(Message::StatsReply(stats))
+                }
+                msg => Ok(msg),
+            };
+            let _ = request.tx.send(reply);
+            None
+        }
+
+        /// Fail every pending request once the connection closes.
+        fn fail_pending(&self) {
+            self.pending.lock().unwrap().clear()
+        }
+
+        fn shutdown(&self) {
+            self.sink.lock().unwrap().shutdown()
+        }
+    }
+
+    /// Switch Handles
+    ///
+    /// Cloneable handle to every switch connected to a controller, for sending messages to a
+    /// switch by datapath id rather than through the stream of the current callback.
+    #[derive(Clone)]
+    pub struct Switches {
+        conns: Arc<Mutex<HashMap<u64, Arc<SwitchConn>>>>,
+        vendors: Arc<VendorRegistry>,
+    }
+
+    impl Switches {
+        fn new(vendors: Arc<VendorRegistry>) -> Switches {
+            Switches {
+                conns: Arc::new(Mutex::new(HashMap::new())),
+                vendors: vendors,
+            }
+        }
+
+        /// Register `conn` as the connection to switch `sw`, returning the connection it
+        /// replaces if the switch reconnected without the previous one closing.
+        fn insert(&self, sw: u64, conn: Arc<SwitchConn>) -> Option<Arc<SwitchConn>> {
+            self.conns.lock().unwrap().insert(sw, conn)
+        }
+
+        /// Forget switch `sw`, unless it has since reconnected on a connection other than `conn`.
+        /// Returns whether `sw` was forgotten.
+        fn remove(&self, sw: u64, conn: &Arc<SwitchConn>) -> bool {
+            let mut conns = self.conns.lock().unwrap();
+            if conns.get(&sw).is_some_and(|c| Arc::ptr_eq(c, conn)) {
+                conns.remove(&sw);
+                true
+            } else {
+                false
+            }
+        }
+
+        /// Register `conn` as the connection to switch `sw` once its handshake completes.
+        /// Returns whether an earlier connection to `sw` was closed to make way for it.
+        fn connect(&self, sw: u64, conn: &Arc<SwitchConn>) -> bool {
+            match self.insert(sw, conn.clone()) {
+                Some(old) => {
+                    println!("Switch {} reconnected, closing previous connection.", sw);
+                    old.fail_pending();
+                    old.shutdown();
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn get(&self, sw: u64) -> io::Result<Arc<SwitchConn>> {
+            match self.conns.lock().unwrap().get(&sw) {
+                Some(conn) => Ok(conn.clone()),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, "switch not connected")),
+            }
+        }
+
+        /// Send message `msg` with transaction ID `xid` to switch `sw`. Fails with
+        /// `ErrorKind::NotFound` if `sw` is not connected.
+        pub fn send_message(&self, sw: u64, xid: u32, msg: Message) -> io::Result<()> {
+            self.get(sw)?.send_message(xid, msg)
+        }
+
+        /// Send a vendor message with vendor id `vendor_id` and transaction ID `xid` to switch
+        /// `sw`, its body marshaled from `value` by the controller's `vendor_registry`. Fails with
+        /// `ErrorKind::InvalidInput` if no marshaller registered for vendor messages with
+        /// `vendor_id` handles `value`.
+        pub fn send_vendor_value(&self,
+                                 sw: u64,
+                                 xid: u32,
+                                 vendor_id: u32,
+                                 value: &dyn Any)
+                                 -> io::Result<()> {
+            let body = match self.vendors.marshal(VendorKind::Message, vendor_id, value) {
+                Some(body) => body,
+                None => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "no vendor marshaller for value"))
+                }
+            };
+            self.send_message(sw,
+                              xid,
+                              Message::Vendor {
+                                  vendor_id: vendor_id,
+                                  body: body,
+                              })
+        }
+
+        /// Return the controller's `vendor_registry`, for building vendor actions and statistics
+        /// requests from application-defined values.
+        pub fn vendor_registry(&self) -> &VendorRegistry {
+            &self.vendors
+        }
+
+        /// Allocate a fresh transaction ID on the connection to switch `sw`, for messages such
+        /// as flowmods whose errors should be attributable. Returns `None` if `sw` is not
+        /// connected.
+        pub fn next_xid(&self, sw: u64) -> Option<u32> {
+            self.get(sw).ok().map(|conn| conn.next_xid())
+        }
+
+        /// Send request `msg` to switch `sw` with a freshly allocated transaction ID, returning
+        /// a handle that resolves with the reply, or with the error message carrying the same
+        /// transaction ID. Multi-part statistics replies are collected into a single reply.
+        /// Matched replies are not passed to controller callbacks.
+        pub fn send_request(&self, sw: u64, msg: Message) -> io::Result<PendingReply> {
+            self.get(sw)?.send_request(msg)
+        }
+
+        /// Begin a batch of messages to switch `sw`, committed together with a barrier.
+        pub fn transaction(&self, sw: u64) -> Transaction {
+            Transaction {
+                switches: self.clone(),
+                sw: sw,
+                messages: vec![],
+            }
+        }
+
+        /// Return the datapath ids of all connected switches.
+        pub fn switch_ids(&self) -> Vec<u64> {
+            self.conns.lock().unwrap().keys().cloned().collect()
+        }
+
+        /// Return whether switch `sw` is connected.
+        pub fn is_connected(&self, sw: u64) -> bool {
+            self.conns.lock().unwrap().contains_key(&sw)
+        }
+    }
+
+    /// Reasons a transaction committed with `Transaction::commit` did not succeed.
+    #[derive(Debug)]
+    pub enum TransactionError {
+        /// The switch returned errors for the messages at the given positions in the batch.
+        Rejected(Vec<(usize, Error)>),
+        /// The barrier reply closing the batch never arrived, so the outcome is unknown.
+        Incomplete(ReplyError),
+    }
+
+    impl fmt::Display for TransactionError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                TransactionError::Rejected(ref errs) => {
+                    write!(f, "switch rejected {} message(s) in the batch", errs.len())
+                }
+                TransactionError::Incomplete(ref err) => write!(f, "{}", err),
+            }
+        }
+    }
+
+    /// Transaction
+    ///
+    /// Batch of flowmods and packet outs for a single switch, created with
+    /// `Switches::transaction`. Committing sends the batch followed by a barrier request, whose
+    /// reply guarantees the switch has processed, and reported any errors for, every message
+    /// in the batch.
+    pub struct Transaction {
+        switches: Switches,
+        sw: u64,
+        messages: Vec<Message>,
+    }
+
+    impl Transaction {
+        /// Add flowmod `flow` to the batch.
+        pub fn flow_mod(&mut self, flow: FlowMod) -> &mut Transaction {
+            self.messages.push(Message::FlowMod(flow));
+            self
+        }
+
+        /// Add packet out `pkt` to the batch.
+        pub fn packet_out(&mut self, pkt: PacketOut) -> &mut Transaction {
+            self.messages.push(Message::PacketOut(pkt));
+            self
+        }
+
+        /// Send the batch, each message with a fresh transaction ID, followed by a barrier
+        /// request. Returns a handle that resolves once the barrier reply arrives.
+        pub fn commit(self) -> io::Result<PendingTransaction> {
+            let conn = self.switches.get(self.sw)?;
+            let mut replies = vec![];
+            for msg in self.messages {
+                let reply = conn.track(conn.next_xid());
+                conn.send_message(reply.xid, msg)?;
+                replies.push(reply);
+            }
+            let barrier = conn.send_request(Message::BarrierRequest)?;
+            Ok(PendingTransaction {
+                conn: conn,
+                replies: replies,
+                barrier: barrier,
+            })
+        }
+    }
+
+    /// Handle to the outcome of a committed `Transaction`.
+    ///
+    /// As with `PendingReply`, waiting from inside a controller callback holds up the callbacks
+    /// of every switch. Replies to a transaction dropped before its barrier resolves are passed
+    /// to controller callbacks.
+    pub struct PendingTransaction {
+        conn: Arc<SwitchConn>,
+        replies: Vec<PendingReply>,
+        barrier: PendingReply,
+    }
+
+    impl PendingTransaction {
+        /// Return the transaction IDs the messages in the batch were sent with, in order.
+        pub fn xids(&self) -> Vec<u32> {
+            self.replies.iter().map(|reply| reply.xid).collect()
+        }
+
+        /// Block until the barrier reply arrives.
+        pub fn wait(self) -> Result<(), TransactionError> {
+            let barrier = self.barrier.wait();
+            self.finish(barrier)
+        }
+
+        /// Block until the barrier reply arrives or `timeout` elapses.
+        pub fn wait_timeout(self, timeout: Duration) -> Result<(), TransactionError> {
+            let barrier = self.barrier.wait_timeout(timeout);
+            self.finish(barrier)
+        }
+
+        /// Collect the errors reported for the batch once the barrier has resolved.
+        fn finish(&self, barrier: Result<Message, ReplyError>) -> Result<(), TransactionError> {
+            let mut errs = vec![];
+            for (i, reply) in self.replies.iter().enumerate() {
+                if let Some(Err(ReplyError::Switch(err))) = reply.try_wait() {
+                    errs.push((i, err))
+                }
+            }
+            match barrier {
+                Ok(_) if errs.is_empty() => Ok(()),
+                Ok(_) => Err(TransactionError::Rejected(errs)),
+                Err(e) => Err(TransactionError::Incomplete(e)),
+            }
+        }
+    }
+
+    impl Drop for PendingTransaction {
+        fn drop(&mut self) {
+            for reply in &self.replies {
+                self.conn.forget(reply.xid)
+            }
+            self.conn.forget(self.barrier.xid)
+        }
+    }
+
+    /// Echo Keepalive
+    ///
+    /// How often the controller probes a switch with echo requests, and how many consecutive
+    /// probes may go unanswered before the connection is torn down.
+    #[derive(Copy, Clone, Debug)]
+    pub struct Keepalive {
+        /// Time between echo requests.
+        pub interval: Duration,
+        /// Time to wait for each echo reply before counting it as missed.
+        pub timeout: Duration,
+        /// Number of consecutive missed echo replies after which the switch is considered dead.
+        pub max_missed: u32,
+    }
+
+    impl Default for Keepalive {
+        fn default() -> Keepalive {
+            Keepalive {
+                interval: Duration::from_secs(5),
+                timeout: Duration::from_secs(5),
+                max_missed: 3,
+            }
+        }
+    }
+
+    enum EchoStatus {
+        Idle,
+        Send,
+        Dead,
+    }
+
+    /// Echo requests in flight on a single connection under a `Keepalive` policy.
+    struct EchoState {
+        keepalive: Keepalive,
+        next_send: Instant,
+        outstanding: Option<(u32, Instant)>,
+        missed: u32,
+    }
+
+    impl EchoState {
+        fn new(keepalive: Keepalive, now: Instant) -> EchoState {
+            EchoState {
+                keepalive: keepalive,
+                next_send: now + keepalive.interval,
+                outstanding: None,
+                missed: 0,
+            }
+        }
+
+        /// Return whether an echo request is due, or the switch has missed too many replies.
+        fn poll(&mut self, now: Instant) -> EchoStatus {
+            if let Some((_, sent)) = self.outstanding {
+                if now.duration_since(sent) < self.keepalive.timeout {
+                    return EchoStatus::Idle;
+                }
+                self.outstanding = None;
+                self.missed += 1;
+                if self.missed >= self.keepalive.max_missed {
+                    return EchoStatus::Dead;
+                }
+            }
+            if now < self.next_send {
+                EchoStatus::Idle
+            } else {
+                EchoStatus::Send
+            }
+        }
+
+        fn sent(&mut self, xid: u32, now: Instant) {
+            self.outstanding = Some((xid, now));
+            self.next_send = now + self.keepalive.interval;
+        }
+
+        /// Record the echo reply with transaction ID `xid`, returning its round-trip time if it
+        /// answers the outstanding request.
+        fn replied(&mut self, xid: u32, now: Instant) -> Option<Duration> {
+            match self.outstanding {
+                Some((sent_xid, sent)) if sent_xid == xid => {
+                    self.outstanding = None;
+                    self.missed = 0;
+                    Some(now.duration_since(sent))
+                }
+                _ => None,
+            }
+        }
+
+        /// Return the next instant at which `poll` may have something to do.
+        fn deadline(&self) -> Instant {
+            match self.outstanding {
+                Some((_, sent)) => sent + self.keepalive.timeout,
+                None => self.next_send,
+            }
+        }
+    }
+
+    /// Lifecycle of a switch connection.
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum ConnState {
+        /// Waiting for the switch's hello. Any other message fails the handshake.
+        Hello,
+        /// Features requested, waiting for the switch to identify itself.
+        Features,
+        /// Handshake complete with the switch with the given datapath id.
+        Ready(u64),
+        /// Connection torn down.
+        Closed,
+    }
+
+    impl ConnState {
+        fn switch_id(&self) -> Option<u64> {
+            match *self {
+                ConnState::Ready(sw) => Some(sw),
+                _ => None,
+            }
+        }
+    }
+
+    /// Protocol versions spoken by the controller runtimes, advertised in their hello.
+    ///
+    /// Only OpenFlow 1.0 is supported, as every frame is parsed as an `openflow0x01` message; a
+    /// switch offering no version in common fails the handshake with `OFPET_HELLO_FAILED`.
+    /// OpenFlow 1.3 messages are available from `openflow0x04`, but no runtime serves them.
+    const SUPPORTED_VERSIONS: &[u8] = &[OFP_VERSION_1_0];
+
+    /// Return the marshaled hello the controller opens every connection with.
+    fn controller_hello() -> Vec<u8> {
+        Hello::marshal(0, &Hello::new(SUPPORTED_VERSIONS))
+    }
+
+    /// Return an error message of type `code` quoting the start of the offending frame.
+    fn error_reply(code: ErrorType, header: OfpHeader, body: &[u8]) -> Message {
+        let mut data = vec![];
+        OfpHeader::marshal(&mut data, header);
+        data.extend_from_slice(body);
+        data.truncate(64);
+        Message::Error(Error::Error(code, data))
+    }
+
+    /// How a connection treats a frame before parsing it as an OpenFlow 1.0 message.
+    enum Frame {
+        /// Parse and dispatch the frame.
+        Message,
+        /// The switch's hello settled on the given protocol version.
+        Negotiated(u8),
+        /// Answer with the given error and drop the frame.
+        Reject(Message),
+        /// Answer with the given error and close the connection.
+        Fail(Message),
+    }
+
+    /// Classify the frame with header `header` and body `body`, received in state `state` on a
+    /// connection speaking protocol version `version`.
+    fn classify_frame(state: ConnState, version: u8, header: OfpHeader, body: &[u8]) -> Frame {
+        if state == ConnState::Hello {
+            if header.type_byte() != OFPT_HELLO {
+                println!("Message type {} received before hello.", header.type_byte());
+                let code = ErrorType::HelloFailed(HelloFailed::Incompatible);
+                let msg = b"expected hello".to_vec();
+                return Frame::Fail(Message::Error(Error::Error(code, msg)));
+            }
+            let negotiated = match Hello::parse(&header, body) {
+                Ok(hello) => hello.negotiate(SUPPORTED_VERSIONS),
+                Err(e) => {
+                    println!("Error parsing hello: {}", e);
+                    None
+                }
+            };
+            return match negotiated {
+                Some(version) => Frame::Negotiated(version),
+                None => {
+                    println!("No protocol version in common with switch.");
+                    let code = ErrorType::HelloFailed(HelloFailed::Incompatible);
+                    let msg = format!("supported versions: {:?}", SUPPORTED_VERSIONS);
+                    Frame::Fail(Message::Error(Error::Error(code, msg.into_bytes())))
+                }
+            };
+        }
+        if header.version() != version {
+            println!("Unexpected protocol version {}", header.version());
+            return Frame::Reject(error_reply(ErrorType::BadRequest(BadRequest::BadVersion),
+                                             header,
+                                             body));
+        }
+        if let Err(typ) = header.type_code() {
+            println!("Unknown message type {}", typ);
+            return Frame::Reject(error_reply(ErrorType::BadRequest(BadRequest::BadType),
+                                             header,
+                                             body));
+        }
+        Frame::Message
+    }
+
+    /// Event raised by a switch connection, handled by the controller's callbacks.
+    enum Event {
+        /// The switch with the given datapath id completed its handshake, answering the
+        /// features request with the given transaction ID.
+        Connected(u64, u32, SwitchFeatures),
+        /// The switch with the given datapath id disconnected.
+        Disconnected(u64),
+        /// The switch with the given datapath id answered a keepalive echo after the given
+        /// round-trip time.
+        EchoLatency(u64, Duration),
+        /// Vendor message with the given transaction ID, vendor id, and body, from the switch
+        /// with the given datapath id if it has identified itself.
+        Vendor(Option<u64>, u32, u32, Vec<u8>),
+        /// Vendor message, action, or statistics body of the given kind and vendor id, parsed by
+        /// the controller's vendor registry from the message with the given transaction ID.
+        VendorValue(Option<u64>, u32, VendorKind, u32, VendorValue),
+        /// Message with the given transaction ID from the switch with the given datapath id.
+        Message(u64, u32, Message),
+    }
+
+    /// Invoke the callbacks of controller `cntl` handling `event`.
+    fn dispatch<Cntl: OF0x01Controller>(cntl: &mut Cntl, switches: &Switches, event: Event) {
+        match event {
+            Event::Connected(sw, xid, feats) => {
+                if let Some(config) = cntl.switch_config(sw) {
+                    if let Err(e) = switches.send_message(sw, xid, Message::SetConfig(config)) {
+                        println!("{}", e)
+                    }
+                }
+                cntl.switch_connected(sw, feats, switches)
+            }
+            Event::Disconnected(sw) => cntl.switch_disconnected(sw),
+            Event::EchoLatency(sw, rtt) => cntl.echo_latency(sw, rtt),
+            Event::Vendor(sw, xid, vendor_id, body) => {
+                cntl.vendor_message(sw, xid, vendor_id, body, switches)
+            }
+            Event::VendorValue(sw, xid, kind, vendor_id, value) => {
+                cntl.vendor_value(sw, xid, kind, vendor_id, value, switches)
+            }
+            Event::Message(sw, xid, msg) => {
+                match msg {
+                    Message::Error(err) => cntl.error_message(sw, xid, err, switches),
+                    Message::GetConfigReply(config) => {
+                        cntl.config_reply(sw, xid, config, switches)
+                    }
+                    Message::PacketIn(pkt) => cntl.packet_in(sw, xid, pkt, switches),
+                    Message::FlowRemoved(flow) => cntl.flow_removed(sw, xid, flow, switches),
+                    Message::PortStatus(port) => cntl.port_status(sw, xid, port, switches),
+                    Message::StatsReply(stats) => cntl.stats_reply(sw, xid, stats, switches),
+                    Message::QueueGetConfigReply(queues) => {
+                        cntl.queue_config_reply(sw, xid, queues, switches)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Create a controller and move it to a new thread, invoking its callbacks there for every
+    /// event sent on the returned channel until all senders are dropped. Also returns the handle
+    /// to its switches and the settings it asks for.
+    fn spawn_controller<Cntl>() -> (mpsc::Sender<Event>, Switches, Settings)
+        where Cntl: OF0x01Controller + Send + 'static
+    {
+        let mut cntl = Cntl::new();
+        let settings = Settings::of_controller(&cntl);
+        let switches = Switches::new(settings.vendors.clone());
+        cntl.attach_switches(switches.clone());
+        let (tx, rx) = mpsc::channel();
+        let dispatch_switches = switches.clone();
+        thread::spawn(move || for event in rx {
+            dispatch(&mut cntl, &dispatch_switches, event)
+        });
+        (tx, switches, settings)
+    }
+
+    /// Settings for every switch connection, taken from the controller once when a runtime is
+    /// created.
+    #[derive(Clone)]
+    struct Settings {
+        handshake_timeout: Duration,
+        keepalive: Option<Keepalive>,
+        vendors: Arc<VendorRegistry>,
+    }
+
+    impl Settings {
+        fn of_controller<Cntl: OF0x01Controller>(cntl: &Cntl) -> Settings {
+            Settings {
+                handshake_timeout: cntl.handshake_timeout(),
+                keepalive: cntl.keepalive(),
+                vendors: Arc::new(cntl.vendor_registry()),
+            }
+        }
+    }
+
+    /// Protocol state of a single switch connection. Every message to the switch is sent
+    /// through the connection's `SwitchConn`, and events are passed to the controller through
+    /// the `deliver` function of each call.
+    struct Session {
+        state: ConnState,
+        version: u8,
+        conn: Arc<SwitchConn>,
+        switches: Switches,
+        keepalive: Option<Keepalive>,
+        vendors: Arc<VendorRegistry>,
+        handshake_deadline: Instant,
+        echo: Option<EchoState>,
+    }
+
+    impl Session {
+        fn new(conn: Arc<SwitchConn>, switches: Switches, settings: &Settings) -> Session {
+            Session {
+                state: ConnState::Hello,
+                version: OFP_VERSION_1_0,
+                conn: conn,
+                switches: switches,
+                keepalive: settings.keepalive,
+                vendors: settings.vendors.clone(),
+                handshake_deadline: Instant::now() + settings.handshake_timeout,
+                echo: None,
+            }
+        }
+
+        /// Send the controller's hello, opening the handshake.
+        fn open(&self) -> io::Result<()> {
+            self.conn.send_bytes(controller_hello())
+        }
+
+        /// Handle the frame with header `header` and body `body`. Returns `false` if the
+        /// connection should be closed.
+        fn handle_frame<F>(&mut self,
+                           header: OfpHeader,
+                           body: &[u8],
+                           deliver: &mut F)
+                           -> io::Result<bool>
+            where F: FnMut(Event)
+        {
+            match classify_frame(self.state, self.version, header, body) {
+                Frame::Message => (),
+                Frame::Negotiated(version) => {
+                    self.version = version;
+                    self.state = ConnState::Features;
+                    self.conn.send_message(header.xid(), Message::FeaturesReq)?;
+                    return Ok(true);
+                }
+                Frame::Reject(err) => {
+                    self.conn.send_message(header.xid(), err)?;
+                    return Ok(true);
+                }
+                Frame::Fail(err) => {
+                    self.conn.send_message(header.xid(), err)?;
+                    return Ok(false);
+                }
+            }
+            match Message::parse_with(&self.vendors, &header, body) {
+                Ok((xid, msg, values)) => {
+                    if let Some(msg) = self.conn.resolve(xid, msg) {
+                        self.process_message(xid, msg, deliver)?;
+                        let sw = self.state.switch_id();
+                        for (kind, vendor_id, value) in values {
+                            deliver(Event::VendorValue(sw, xid, kind, vendor_id, value))
+                        }
+                    }
+                }
+                Err(e) => println!("Error parsing message: {}", e),
+            }
+            Ok(true)
+        }
+
+        fn process_message<F>(&mut self, xid: u32, msg: Message, deliver: &mut F) -> io::Result<()>
+            where F: FnMut(Event)
+        {
             match msg {
-                Message::Hello => Cntl::send_message(xid, Message::FeaturesReq, stream),
-                Message::Error(err) => println!("Error: {:?}", err),
                 Message::EchoRequest(bytes) => {
-                    Cntl::send_message(xid, Message::EchoReply(bytes), stream)
+                    self.conn.send_message(xid, Message::EchoReply(bytes))?
                 }
-                Message::EchoReply(_) => (),
-                Message::FeaturesReq => (),
-                Message::FeaturesReply(feats) => {
-                    if self.switch_id.is_some() {
-                        panic!("Switch connection already received.")
+                Message::EchoReply(_) => {
+                    let now = Instant::now();
+                    let rtt = self.echo.as_mut().and_then(|echo| echo.replied(xid, now));
+                    if let (Some(sw), Some(rtt)) = (self.state.switch_id(), rtt) {
+                        deliver(Event::EchoLatency(sw, rtt))
                     }
-                    self.switch_id = Some(feats.datapath_id);
-                    Cntl::switch_connected(cntl, feats.datapath_id, feats, stream)
                 }
-                Message::FlowMod(_) => (),
-                Message::PacketIn(pkt) => {
-                    Cntl::packet_in(cntl, self.switch_id.unwrap(), xid, pkt, stream)
+                Message::Vendor { vendor_id, body } => {
+                    if !self.vendors.is_registered(VendorKind::Message, vendor_id) {
+                        deliver(Event::Vendor(self.state.switch_id(), xid, vendor_id, body))
+                    }
+                }
+                Message::FeaturesReply(feats) => {
+                    match self.state {
+                        ConnState::Features => (),
+                        ConnState::Hello | ConnState::Ready(_) | ConnState::Closed => {
+                            println!("Ignoring unexpected features reply.");
+                            return Ok(());
+                        }
+                    }
+                    let sw = feats.datapath_id;
+                    self.state = ConnState::Ready(sw);
+                    let now = Instant::now();
+                    self.echo = self.keepalive.map(|keepalive| EchoState::new(keepalive, now));
+                    if self.switches.connect(sw, &self.conn) {
+                        deliver(Event::Disconnected(sw))
+                    }
+                    deliver(Event::Connected(sw, xid, feats))
                 }
+                Message::Error(_) |
+                Message::GetConfigReply(_) |
+                Message::PacketIn(_) |
                 Message::FlowRemoved(_) |
                 Message::PortStatus(_) |
+                Message::StatsReply(_) |
+                Message::QueueGetConfigReply(_) => {
+                    if let Message::Error(ref err) = msg {
+                        println!("Error: {:?}", err)
+                    }
+                    if let Some(sw) = self.state.switch_id() {
+                        deliver(Event::Message(sw, xid, msg))
+                    }
+                }
+                Message::Hello |
+                Message::FeaturesReq |
+                Message::GetConfigRequest |
+                Message::SetConfig(_) |
+                Message::FlowMod(_) |
+                Message::PortMod(_) |
                 Message::PacketOut(_) |
+                Message::StatsRequest(_) |
+                Message::QueueGetConfigRequest(_) |
                 Message::BarrierRequest |
                 Message::BarrierReply => (),
             }
+            Ok(())
+        }
+
+        /// Send an echo request if one is due at `now`. Fails with `ErrorKind::TimedOut` if
+        /// the switch has not completed its handshake by its deadline, or has missed too many
+        /// echo replies, and should be considered dead.
+        fn check_timers(&mut self, now: Instant) -> io::Result<()> {
+            match self.state {
+                ConnState::Hello | ConnState::Features if now >= self.handshake_deadline => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut,
+                                              "switch did not complete its handshake"))
+                }
+                _ => (),
+            }
+            let echo = match self.echo {
+                Some(ref mut echo) => echo,
+                None => return Ok(()),
+            };
+            match echo.poll(now) {
+                EchoStatus::Idle => Ok(()),
+                EchoStatus::Send => {
+                    let xid = self.conn.next_xid();
+                    echo.sent(xid, now);
+                    self.conn.send_message(xid, Message::EchoRequest(vec![]))
+                }
+                EchoStatus::Dead => {
+                    Err(io::Error::new(io::ErrorKind::TimedOut,
+                                       "switch stopped answering echo requests"))
+                }
+            }
         }
 
-        fn switch_disconnected(&self, cntl: &mut Cntl) {
-            Cntl::switch_disconnected(cntl, self.switch_id.unwrap())
+        /// Return the next instant at which `check_timers` may have something to do: the
+        /// handshake deadline until the handshake completes, and then the next echo request or
+        /// reply deadline if the connection is probed with echo requests.
+        fn deadline(&self) -> Option<Instant> {
+            match self.state {
+                ConnState::Hello | ConnState::Features => Some(self.handshake_deadline),
+                ConnState::Ready(_) | ConnState::Closed => {
+                    self.echo.as_ref().map(|echo| echo.deadline())
+                }
+            }
+        }
+
+        /// Tear down the connection, raising `Event::Disconnected` if the switch completed its
+        /// handshake and has not since reconnected on another connection.
+        fn close<F: FnMut(Event)>(&mut self, deliver: &mut F) {
+            self.conn.fail_pending();
+            self.conn.shutdown();
+            if let ConnState::Ready(sw) = self.state {
+                if self.switches.remove(sw, &self.conn) {
+                    deliver(Event::Disconnected(sw))
+                }
+            }
+            self.state = ConnState::Closed;
         }
     }
 
     /// OpenFlow0x01 Controller API
     ///
     /// OpenFlow 1.0-specific API for communicating between a controller and the dataplane.
+    /// Callbacks are invoked one at a time, and send messages to switches through `Switches`.
     pub trait OF0x01Controller: OfpController<Message = Message> {
         /// Create a new Controller.
         fn new() -> Self;
+        /// Callback invoked once, before any switch connects, with a handle for sending
+        /// messages to any connected switch by datapath id. Ignores the handle by default.
+        fn attach_switches(&mut self, _: Switches) {}
         /// Callback invoked with `sw` when a switch with identifier `sw` connects to
         /// the controller.
-        fn switch_connected(&mut self, sw: u64, feats: SwitchFeatures, stream: &mut TcpStream);
+        fn switch_connected(&mut self, sw: u64, feats: SwitchFeatures, switches: &Switches);
         /// Callback invoked with `sw` when a switch with identifier `sw` disconnects
         /// from the controller.
         fn switch_disconnected(&mut self, sw: u64);
         /// Callback invoked when a packet `pkt` with transaction ID `xid` from
         /// switch `sw` arrives at the controller.
-        fn packet_in(&mut self, sw: u64, xid: u32, pkt: PacketIn, stream: &mut TcpStream);
+        fn packet_in(&mut self, sw: u64, xid: u32, pkt: PacketIn, switches: &Switches);
+        /// Configuration to send to switch `sw` once it connects, before `switch_connected` is
+        /// invoked. Leaves the switch configuration unchanged by default.
+        fn switch_config(&self, _: u64) -> Option<SwitchConfig> {
+            None
+        }
+        /// Time allowed for a new switch connection to complete its handshake, from the
+        /// controller's hello to the switch's features reply, before it is closed. Allows 10
+        /// seconds by default.
+        fn handshake_timeout(&self) -> Duration {
+            Duration::from_secs(10)
+        }
+        /// Echo keepalive policy for new switch connections, or `None` to only answer echo
+        /// requests from switches. Only answers echo requests by default; return
+        /// `Some(Keepalive::default())` to probe switches every 5 seconds.
+        fn keepalive(&self) -> Option<Keepalive> {
+            None
+        }
+        /// Callback invoked with the round-trip time `rtt` of each keepalive echo answered by
+        /// switch `sw`. Ignores latencies by default.
+        fn echo_latency(&mut self, _: u64, _: Duration) {}
+        /// Callback invoked when error `err` arrives from switch `sw` in response to the message
+        /// with transaction ID `xid`, unless a request sent with `Switches::send_request` is
+        /// waiting for it. Ignores errors by default.
+        fn error_message(&mut self, _: u64, _: u32, _: Error, _: &Switches) {}
+        /// Callback invoked when switch `sw` reports with transaction ID `xid` that flow `flow`
+        /// was removed from its table, for flows installed with `notify_when_removed` set.
+        /// Ignores removals by default.
+        fn flow_removed(&mut self, _: u64, _: u32, _: FlowRemoved, _: &Switches) {}
+        /// Callback invoked when switch `sw` reports with transaction ID `xid` that one of its
+        /// ports was added, removed, or modified, as described by `port`. Ignores port changes
+        /// by default.
+        fn port_status(&mut self, _: u64, _: u32, _: PortStatus, _: &Switches) {}
+        /// Callback invoked when configuration `config` replying to the request with
+        /// transaction ID `xid` arrives from switch `sw`. Ignores replies by default.
+        fn config_reply(&mut self, _: u64, _: u32, _: SwitchConfig, _: &Switches) {}
+        /// Vendor extensions whose bodies the runtime should parse, taken once when the runtime
+        /// is created. Registers none by default.
+        fn vendor_registry(&self) -> VendorRegistry {
+            VendorRegistry::default()
+        }
+        /// Callback invoked when a vendor message with vendor id `vendor_id` and transaction ID
+        /// `xid` arrives from switch `sw`, or from a switch that has not yet identified itself if
+        /// `sw` is `None`, unless `vendor_registry` has a parser for it. Ignores vendor messages
+        /// by default.
+        fn vendor_message(&mut self, _: Option<u64>, _: u32, _: u32, _: Vec<u8>, _: &Switches) {}
+        /// Callback invoked with every vendor message, action, and statistics body of kind `kind`
+        /// and vendor id `vendor_id` that `vendor_registry` has a parser for, in a message with
+        /// transaction ID `xid` from switch `sw`. Vendor messages decoded this way are not passed
+        /// to `vendor_message`, while the messages carrying decoded actions and statistics are
+        /// still passed to their own callbacks first. Ignores values by default.
+        fn vendor_value(&mut self,
+                        _: Option<u64>,
+                        _: u32,
+                        _: VendorKind,
+                        _: u32,
+                        _: VendorValue,
+                        _: &Switches) {
+        }
+        /// Callback invoked when statistics `stats` replying to the request with transaction
+        /// ID `xid` arrive from switch `sw`. Ignores replies by default.
+        fn stats_reply(&mut self, _: u64, _: u32, _: StatsResp, _: &Switches) {}
+        /// Callback invoked when the queue configuration `queues` replying to the request with
+        /// transaction ID `xid` arrives from switch `sw`. Ignores replies by default.
+        fn queue_config_reply(&mut self, _: u64, _: u32, _: QueueGetConfigResp, _: &Switches) {}
 
         /// Send packet `pkt` with transaction ID `xid` to switch `sw` from the controller.
-        fn send_packet_out(_: u64, xid: u32, pkt: PacketOut, stream: &mut TcpStream) {
-            Self::send_message(xid, Message::PacketOut(pkt), stream)
+        fn send_packet_out(sw: u64,
+                           xid: u32,
+                           pkt: PacketOut,
+                           switches: &Switches)
+                           -> io::Result<()> {
+            switches.send_message(sw, xid, Message::PacketOut(pkt))
         }
 
         /// Send flowmod `flow` with transaction ID `xid` to switch `sw` from the controller.
-        fn send_flow_mod(_: u64, xid: u32, flow: FlowMod, stream: &mut TcpStream) {
-            Self::send_message(xid, Message::FlowMod(flow), stream)
+        fn send_flow_mod(sw: u64, xid: u32, flow: FlowMod, switches: &Switches) -> io::Result<()> {
+            switches.send_message(sw, xid, Message::FlowMod(flow))
+        }
+
+        /// Send a request for the configuration of switch `sw` with transaction ID `xid` from the
+        /// controller.
+        fn send_get_config_request(sw: u64, xid: u32, switches: &Switches) -> io::Result<()> {
+            switches.send_message(sw, xid, Message::GetConfigRequest)
+        }
+
+        /// Send configuration `config` with transaction ID `xid` to switch `sw` from the
+        /// controller.
+        fn send_set_config(sw: u64,
+                           xid: u32,
+                           config: SwitchConfig,
+                           switches: &Switches)
+                           -> io::Result<()> {
+            switches.send_message(sw, xid, Message::SetConfig(config))
+        }
+
+        /// Send portmod `port` with transaction ID `xid` to switch `sw` from the controller.
+        fn send_port_mod(sw: u64, xid: u32, port: PortMod, switches: &Switches) -> io::Result<()> {
+            switches.send_message(sw, xid, Message::PortMod(port))
+        }
+
+        /// Send statistics request `req` with transaction ID `xid` to switch `sw` from the
+        /// controller.
+        fn send_stats_request(sw: u64,
+                              xid: u32,
+                              req: StatsReq,
+                              switches: &Switches)
+                              -> io::Result<()> {
+            switches.send_message(sw, xid, Message::StatsRequest(req))
+        }
+
+        /// Send a request for the description of switch `sw` with transaction ID `xid` from the
+        /// controller. The description arrives as a `StatsRespBody::Desc` in `stats_reply`.
+        /// Typically sent from `switch_connected`.
+        fn send_desc_request(sw: u64, xid: u32, switches: &Switches) -> io::Result<()> {
+            Self::send_stats_request(sw, xid, StatsReq::Desc, switches)
+        }
+
+        /// Send a request for the queues configured on a port of switch `sw` with transaction ID
+        /// `xid` from the controller.
+        fn send_queue_get_config_request(sw: u64,
+                                         xid: u32,
+                                         req: QueueGetConfigReq,
+                                         switches: &Switches)
+                                         -> io::Result<()> {
+            switches.send_message(sw, xid, Message::QueueGetConfigRequest(req))
+        }
+
+        /// Send a vendor message with vendor id `vendor_id`, transaction ID `xid`, and body `body`
+        /// to switch `sw` from the controller.
+        fn send_vendor_message(sw: u64,
+                               xid: u32,
+                               vendor_id: u32,
+                               body: Vec<u8>,
+                               switches: &Switches)
+                               -> io::Result<()> {
+            switches.send_message(sw,
+                                  xid,
+                                  Message::Vendor {
+                                      vendor_id: vendor_id,
+                                      body: body,
+                                  })
+        }
+
+        /// Send a vendor message with vendor id `vendor_id` and transaction ID `xid`, its body
+        /// marshaled from `value` by `vendor_registry`, to switch `sw` from the controller.
+        fn send_vendor_value(sw: u64,
+                             xid: u32,
+                             vendor_id: u32,
+                             value: &dyn Any,
+                             switches: &Switches)
+                             -> io::Result<()> {
+            switches.send_vendor_value(sw, xid, vendor_id, value)
         }
 
         /// Send barrier request with transaction ID `xid` to switch `sw` from the controller.
         /// Guarantees switch `sw` processes messages prior to barrier before messages after.
-        fn send_barrier_request(_: u64, xid: u32, stream: &mut TcpStream) {
-            Self::send_message(xid, Message::BarrierRequest, stream)
+        fn send_barrier_request(sw: u64, xid: u32, switches: &Switches) -> io::Result<()> {
+            switches.send_message(sw, xid, Message::BarrierRequest)
         }
     }
 
     impl<Controller: OF0x01Controller> OfpController for Controller {
         type Message = Message;
 
-        fn send_message(xid: u32, message: Message, writer: &mut TcpStream) {
-            let raw_msg = Message::marshal(xid, message);
-            writer.write_all(&raw_msg).unwrap()
+        fn send_message(xid: u32, message: Message, writer: &mut TcpStream) -> io::Result<()> {
+            writer.write_all(&marshal(xid, message)?)
         }
 
         fn handle_client_connected(stream: &mut TcpStream) {
             let mut cntl = Controller::new();
-            Controller::send_message(0, Message::Hello, stream);
+            let settings = Settings::of_controller(&cntl);
+            let switches = Switches::new(settings.vendors.clone());
+            cntl.attach_switches(switches.clone());
+            run_connection(&settings,
+                           &switches,
+                           stream,
+                           |event| dispatch(&mut cntl, &switches, event))
+        }
+    }
 
-            let mut buf = [0u8; 8];
-            let mut thread_state = ThreadState::<Self> {
-                switch_id: None,
+    /// Perform handshake and read messages from switch `stream` until it disconnects, passing
+    /// the events it raises to `deliver`.
+    fn run_connection<F>(settings: &Settings,
+                         switches: &Switches,
+                         stream: &TcpStream,
+                         mut deliver: F)
+        where F: FnMut(Event)
+    {
+        let (read_stream, write_stream) = match (stream.try_clone(), stream.try_clone()) {
+            (Ok(read_stream), Ok(write_stream)) => (read_stream, write_stream),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let conn = Arc::new(SwitchConn::new(SwitchSink::Stream(write_stream)));
+        let mut session = Session::new(conn, switches.clone(), settings);
+        match read_session(&mut session, &mut MessageReader::new(read_stream), &mut deliver) {
+            Ok(()) => println!("Connection closed."),
+            Err(e) => println!("{}", e),
+        }
+        session.close(&mut deliver)
+    }
+
+    /// Open `session` and handle the frames read by `reader` until the switch disconnects, the
+    /// session ends, or a read or write fails.
+    fn read_session<F>(session: &mut Session,
+                       reader: &mut MessageReader<TcpStream>,
+                       deliver: &mut F)
+                       -> io::Result<()>
+        where F: FnMut(Event)
+    {
+        session.open()?;
+        loop {
+            let now = Instant::now();
+            session.check_timers(now)?;
+            let timeout = session.deadline().map(|deadline| {
+                max(deadline.saturating_duration_since(now), Duration::from_millis(1))
+            });
+            reader.get_ref().set_read_timeout(timeout)?;
+            match reader.read_frame() {
+                Ok(Some((header, body))) => {
+                    if !session.handle_frame(header, &body, deliver)? {
+                        return Ok(());
+                    }
+                }
+                Ok(None) => return Ok(()),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock ||
+                              e.kind() == io::ErrorKind::TimedOut => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// OpenFlow0x01 Controller Runtime
+    ///
+    /// Serves any number of OpenFlow 1.0 switches from a single shared controller. Each switch
+    /// connection is read on its own thread, and controller callbacks are invoked one at a time
+    /// on a thread of their own, so a slow or failing switch cannot hold up the others.
+    pub struct Runtime<Cntl> {
+        events: mpsc::Sender<Event>,
+        switches: Switches,
+        settings: Settings,
+        phantom: PhantomData<Cntl>,
+    }
+
+    impl<Cntl: OF0x01Controller + Send + 'static> Runtime<Cntl> {
+        /// Create a runtime around a new controller.
+        pub fn new() -> Runtime<Cntl> {
+            let (events, switches, settings) = spawn_controller::<Cntl>();
+            Runtime {
+                events: events,
+                switches: switches,
+                settings: settings,
                 phantom: PhantomData,
-            };
+            }
+        }
 
-            loop {
-                let res = stream.read(&mut buf);
-                match res {
-                    Ok(num_bytes) if num_bytes > 0 => {
-                        let header = OfpHeader::parse(buf);
-                        let message_len = header.length() - OfpHeader::size();
-                        let mut message_buf = vec![0; message_len];
-                        let _ = stream.read(&mut message_buf);
-                        let (xid, body) = Message::parse(&header, &message_buf);
-                        thread_state.process_message(&mut cntl, xid, body, stream)
+        /// Return a handle for sending messages to connected switches.
+        pub fn switches(&self) -> Switches {
+            self.switches.clone()
+        }
+
+        /// Handle switch connection `stream` on a new thread.
+        pub fn spawn_connection(&self, stream: TcpStream) -> thread::JoinHandle<()> {
+            let events = self.events.clone();
+            let switches = self.switches.clone();
+            let settings = self.settings.clone();
+            thread::spawn(move || {
+                run_connection(&settings, &switches, &stream, |event| {
+                    let _ = events.send(event);
+                })
+            })
+        }
+
+        /// Listen on `addr` and handle every switch that connects. Only returns if the listener
+        /// cannot be bound.
+        pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+            let listener = TcpListener::bind(addr)?;
+            for stream in listener.incoming() {
+                println!("{:?}", stream);
+                match stream {
+                    Ok(stream) => {
+                        self.spawn_connection(stream);
+                    }
+                    Err(e) => println!("Connection failed: {}", e),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<Cntl: OF0x01Controller + Send + 'static> Default for Runtime<Cntl> {
+        fn default() -> Runtime<Cntl> {
+            Runtime::new()
+        }
+    }
+
+    /// OpenFlow0x01 Tokio Runtime
+    ///
+    /// Serves switches from tasks on a tokio executor rather than a thread per connection.
+    /// Framing and message parsing are shared with the blocking runtime. Only available with
+    /// the `tokio` cargo feature.
+    #[cfg(feature = "tokio")]
+    pub mod tokio_runtime {
+        use super::*;
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+        use tokio::net;
+        use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+        use tokio::task::JoinHandle;
+        use tokio::time;
+
+        use rust_ofp::ofp_codec::decode_frame;
+
+        /// Task driving a single switch connection: reads incoming frames and hands them to
+        /// the connection's session, and writes messages queued for the switch.
+        struct Connection {
+            stream: net::TcpStream,
+            in_buf: Vec<u8>,
+            out_buf: Vec<u8>,
+            rx: UnboundedReceiver<Outbound>,
+            session: Session,
+            events: mpsc::Sender<Event>,
+            closing: bool,
+            timer: Pin<Box<time::Sleep>>,
+        }
+
+        impl Connection {
+            fn new(stream: net::TcpStream,
+                   events: mpsc::Sender<Event>,
+                   switches: Switches,
+                   settings: &Settings)
+                   -> Connection {
+                let (tx, rx) = unbounded_channel();
+                let conn = Arc::new(SwitchConn::new(SwitchSink::Channel(tx)));
+                let session = Session::new(conn, switches, settings);
+                if let Err(e) = session.open() {
+                    println!("{}", e)
+                }
+                Connection {
+                    stream: stream,
+                    in_buf: vec![],
+                    out_buf: vec![],
+                    rx: rx,
+                    session: session,
+                    events: events,
+                    closing: false,
+                    timer: Box::pin(time::sleep(Duration::from_secs(0))),
+                }
+            }
+
+            /// Drive the connection until the switch disconnects or an IO error occurs.
+            fn poll_io(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+                let mut chunk = [0u8; 4096];
+                let events = self.events.clone();
+                let mut deliver = |event| {
+                    let _ = events.send(event);
+                };
+                loop {
+                    if let Err(e) = self.session.check_timers(Instant::now()) {
+                        return Poll::Ready(Err(e));
                     }
-                    Ok(_) => {
-                        println!("Connection closed reading header.");
-                        break;
+                    while let Poll::Ready(Some(item)) = self.rx.poll_recv(cx) {
+                        match item {
+                            Outbound::Bytes(bytes) => self.out_buf.extend(bytes),
+                            Outbound::Close => return Poll::Ready(Ok(())),
+                        }
                     }
-                    Err(e) => {
-                        println!("{}", e);
-                        thread_state.switch_disconnected(&mut cntl)
+                    while !self.out_buf.is_empty() {
+                        match Pin::new(&mut self.stream).poll_write(cx, &self.out_buf) {
+                            Poll::Ready(Ok(0)) => return Poll::Ready(Ok(())),
+                            Poll::Ready(Ok(num_bytes)) => {
+                                self.out_buf.drain(..num_bytes);
+                            }
+                            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                            Poll::Pending => break,
+                        }
+                    }
+                    if self.closing {
+                        if self.out_buf.is_empty() {
+                            return Poll::Ready(Ok(()));
+                        }
+                        return Poll::Pending;
+                    }
+                    let mut read_buf = ReadBuf::new(&mut chunk);
+                    match Pin::new(&mut self.stream).poll_read(cx, &mut read_buf) {
+                        Poll::Ready(Ok(())) => {
+                            if read_buf.filled().is_empty() {
+                                return Poll::Ready(Ok(()));
+                            }
+                            self.in_buf.extend_from_slice(read_buf.filled());
+                        }
+                        Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                        Poll::Pending => {
+                            if let Some(deadline) = self.session.deadline() {
+                                self.timer.as_mut().reset(time::Instant::from_std(deadline));
+                                if self.timer.as_mut().poll(cx).is_ready() {
+                                    continue;
+                                }
+                            }
+                            return Poll::Pending;
+                        }
+                    }
+                    while !self.closing {
+                        match decode_frame(&mut self.in_buf) {
+                            Ok(Some((header, body))) => {
+                                match self.session.handle_frame(header, &body, &mut deliver) {
+                                    Ok(open) => self.closing = !open,
+                                    Err(e) => return Poll::Ready(Err(e)),
+                                }
+                            }
+                            Ok(None) => break,
+                            Err(e) => {
+                                return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                                      e)))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        impl Future for Connection {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+                let conn = self.get_mut();
+                match conn.poll_io(cx) {
+                    Poll::Pending => Poll::Pending,
+                    Poll::Ready(res) => {
+                        match res {
+                            Ok(()) => println!("Connection closed."),
+                            Err(e) => println!("{}", e),
+                        }
+                        let events = &conn.events;
+                        conn.session.close(&mut |event| {
+                            let _ = events.send(event);
+                        });
+                        Poll::Ready(())
                     }
                 }
             }
         }
+
+        /// Future accepting switch connections on a listener, returned by `AsyncRuntime::run`.
+        pub struct Serve {
+            listener: net::TcpListener,
+            events: mpsc::Sender<Event>,
+            switches: Switches,
+            settings: Settings,
+        }
+
+        impl Future for Serve {
+            type Output = io::Result<()>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                let serve = self.get_mut();
+                loop {
+                    match serve.listener.poll_accept(cx) {
+                        Poll::Ready(Ok((stream, addr))) => {
+                            println!("{:?}", addr);
+                            let conn = Connection::new(stream,
+                                                       serve.events.clone(),
+                                                       serve.switches.clone(),
+                                                       &serve.settings);
+                            tokio::spawn(conn);
+                        }
+                        Poll::Ready(Err(e)) => println!("Connection failed: {}", e),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            }
+        }
+
+        /// OpenFlow0x01 Asynchronous Controller Runtime
+        ///
+        /// Serves any number of OpenFlow 1.0 switches from a single shared controller, with one
+        /// tokio task per switch connection reading, framing, and writing its messages.
+        ///
+        /// Controller callbacks are not run on the executor. `OF0x01Controller` callbacks are
+        /// synchronous and may block, for instance waiting on a `PendingReply`, which would
+        /// stall an executor thread and every connection task queued on it. As with `Runtime`,
+        /// they are instead invoked one at a time on a dedicated thread fed by the connection
+        /// tasks, so one executor can serve many switches however long callbacks take.
+        pub struct AsyncRuntime<Cntl> {
+            events: mpsc::Sender<Event>,
+            switches: Switches,
+            settings: Settings,
+            phantom: PhantomData<Cntl>,
+        }
+
+        impl<Cntl: OF0x01Controller + Send + 'static> AsyncRuntime<Cntl> {
+            /// Create a runtime around a new controller.
+            pub fn new() -> AsyncRuntime<Cntl> {
+                let (events, switches, settings) = spawn_controller::<Cntl>();
+                AsyncRuntime {
+                    events: events,
+                    switches: switches,
+                    settings: settings,
+                    phantom: PhantomData,
+                }
+            }
+
+            /// Return a handle for sending messages to connected switches.
+            pub fn switches(&self) -> Switches {
+                self.switches.clone()
+            }
+
+            /// Handle switch connection `stream` on a new task. Must be called from within a
+            /// tokio runtime.
+            pub fn spawn_connection(&self, stream: net::TcpStream) -> JoinHandle<()> {
+                tokio::spawn(Connection::new(stream,
+                                             self.events.clone(),
+                                             self.switches.clone(),
+                                             &self.settings))
+            }
+
+            /// Return a future that handles every switch connecting to `listener`.
+            pub fn run(&self, listener: net::TcpListener) -> Serve {
+                Serve {
+                    listener: listener,
+                    events: self.events.clone(),
+                    switches: self.switches.clone(),
+                    settings: self.settings.clone(),
+                }
+            }
+
+            /// Listen on `addr` and handle every switch that connects, on a new multi-threaded
+            /// tokio runtime. Only returns if the listener cannot be bound.
+            pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
+                let listener = TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                let runtime = tokio::runtime::Runtime::new()?;
+                let listener = {
+                    let _guard = runtime.enter();
+                    net::TcpListener::from_std(listener)?
+                };
+                runtime.block_on(self.run(listener))
+            }
+        }
+
+        impl<Cntl: OF0x01Controller + Send + 'static> Default for AsyncRuntime<Cntl> {
+            fn default() -> AsyncRuntime<Cntl> {
+                AsyncRuntime::new()
+            }
+        }
     }
 }
@@ -1,5 +1,5 @@
 use std::io::Cursor;
-use std::mem::{size_of, transmute};
+use std::mem::size_of;
 use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
 
 use rust_ofp::openflow0x01::MsgCode;
@@ -10,6 +10,7 @@ use rust_ofp::openflow0x01::MsgCode;
 /// This is parsed to determine version and length of the remaining message, so that
 /// it can be properly handled.
 #[repr(packed)]
+#[derive(Clone, Copy)]
 pub struct OfpHeader {
     version: u8,
     typ: u8,
@@ -36,7 +37,7 @@ impl OfpHeader {
     /// Fills a message buffer with the header fields of an `OfpHeader`.
     pub fn marshal(bytes: &mut Vec<u8>, header: OfpHeader) {
         bytes.write_u8(header.version()).unwrap();
-        bytes.write_u8(header.type_code() as u8).unwrap();
+        bytes.write_u8(header.type_byte()).unwrap();
         bytes.write_u16::<BigEndian>(header.length() as u16).unwrap();
         bytes.write_u32::<BigEndian>(header.xid()).unwrap();
     }
@@ -57,13 +58,15 @@ impl OfpHeader {
         self.version
     }
 
-    /// Return the OpenFlow message type code of a header.
-    /// # Safety
-    ///
-    /// The `typ` field of the `self` header is expected to be a `u8` within the
-    /// defined range of the `MsgCode` enum.
-    pub fn type_code(&self) -> MsgCode {
-        unsafe { transmute(self.typ) }
+    /// Return the raw `type` field of a header.
+    pub fn type_byte(&self) -> u8 {
+        self.typ
+    }
+
+    /// Return the OpenFlow message type code of a header, or the raw `type` field if it is
+    /// not a recognized `MsgCode`.
+    pub fn type_code(&self) -> Result<MsgCode, u8> {
+        MsgCode::of_int(self.typ).ok_or(self.typ)
     }
 
     /// Return the `length` field of a header. Includes the length of the header itself.
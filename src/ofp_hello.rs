@@ -0,0 +1,126 @@
+use std::cmp::min;
+use std::io::{BufRead, Cursor, Read};
+use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
+
+use ofp_header::OfpHeader;
+use parse_error::ParseError;
+
+/// Protocol version of OpenFlow 1.0.
+pub const OFP_VERSION_1_0: u8 = 0x01;
+/// Protocol version of OpenFlow 1.3.
+pub const OFP_VERSION_1_3: u8 = 0x04;
+
+/// Message type of hello messages, the same in every OpenFlow version.
+pub const OFPT_HELLO: u8 = 0;
+
+/// Hello element listing every supported version as a bitmap (OpenFlow 1.3.1 and later).
+const OFPHET_VERSIONBITMAP: u16 = 1;
+
+/// Hello Message
+///
+/// Version-agnostic hello message, exchanged by both sides when a connection opens to agree
+/// on the protocol version used for the rest of the connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hello {
+    /// Highest version supported by the sender, carried in the header.
+    pub version: u8,
+    /// Every version supported by the sender, if it sent a version bitmap element.
+    pub versions: Option<Vec<u8>>,
+}
+
+impl Hello {
+    /// Create a hello advertising every version in `versions`.
+    pub fn new(versions: &[u8]) -> Hello {
+        let mut versions = versions.to_vec();
+        versions.sort();
+        versions.dedup();
+        Hello {
+            version: versions.last().cloned().unwrap_or(OFP_VERSION_1_0),
+            versions: Some(versions),
+        }
+    }
+
+    /// Return the version to use for a connection on which this hello was received, given
+    /// the versions `ours` supported locally, or `None` if there is none in common.
+    ///
+    /// When both sides advertise a version bitmap this is the highest version in both.
+    /// Otherwise it is the lower of the two header versions, provided that is in `ours`.
+    pub fn negotiate(&self, ours: &[u8]) -> Option<u8> {
+        match self.versions {
+            Some(ref theirs) => ours.iter().filter(|v| theirs.contains(v)).max().cloned(),
+            None => {
+                let version = min(ours.iter().max().cloned().unwrap_or(0), self.version);
+                if ours.contains(&version) {
+                    Some(version)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Parse the hello message with header `header` and body `buf`. Unrecognized hello
+    /// elements are skipped.
+    pub fn parse(header: &OfpHeader, buf: &[u8]) -> Result<Hello, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let mut versions = None;
+        while buf.len().saturating_sub(bytes.position() as usize) >= 4 {
+            let typ = bytes.read_u16::<BigEndian>()?;
+            let len = bytes.read_u16::<BigEndian>()? as usize;
+            if len < 4 {
+                return Err(ParseError::BadLength("ofp_hello_elem", len));
+            }
+            let mut body = vec![0; len - 4];
+            bytes.read_exact(&mut body)?;
+            if typ == OFPHET_VERSIONBITMAP {
+                let mut bitmap = Cursor::new(body);
+                let mut supported = vec![];
+                let mut word_num = 0;
+                while let Ok(word) = bitmap.read_u32::<BigEndian>() {
+                    for bit in 0..32 {
+                        if word & (1 << bit) != 0 && word_num * 32 + bit < 256 {
+                            supported.push((word_num * 32 + bit) as u8)
+                        }
+                    }
+                    word_num += 1;
+                }
+                versions = Some(supported);
+            }
+            let pad = (8 - len % 8) % 8;
+            bytes.consume(pad);
+        }
+        Ok(Hello {
+            version: header.version(),
+            versions: versions,
+        })
+    }
+
+    /// Return a marshaled buffer containing an OpenFlow header and hello `hello`, with
+    /// transaction id `xid`.
+    pub fn marshal(xid: u32, hello: &Hello) -> Vec<u8> {
+        let mut body = vec![];
+        if let Some(ref versions) = hello.versions {
+            let words = versions.iter().map(|&v| v as usize / 32 + 1).max().unwrap_or(0);
+            let mut bitmap = vec![0u32; words];
+            for &v in versions {
+                bitmap[v as usize / 32] |= 1 << (v % 32)
+            }
+            body.write_u16::<BigEndian>(OFPHET_VERSIONBITMAP).unwrap();
+            body.write_u16::<BigEndian>((4 + 4 * words) as u16).unwrap();
+            for word in bitmap {
+                body.write_u32::<BigEndian>(word).unwrap();
+            }
+            while body.len() % 8 != 0 {
+                body.push(0)
+            }
+        }
+        let mut bytes = vec![];
+        let header = OfpHeader::new(hello.version,
+                                    OFPT_HELLO,
+                                    (OfpHeader::size() + body.len()) as u16,
+                                    xid);
+        OfpHeader::marshal(&mut bytes, header);
+        bytes.extend(body);
+        bytes
+    }
+}
@@ -1,16 +1,20 @@
+use marshal_error::MarshalError;
 use ofp_header::OfpHeader;
+use parse_error::ParseError;
 
 /// OpenFlow Message
 ///
 /// Version-agnostic API for handling OpenFlow messages at the byte-buffer level.
-pub trait OfpMessage {
+pub trait OfpMessage: Sized {
     /// Return the byte-size of an `OfpMessage`.
     fn size_of(&Self) -> usize;
     /// Create an `OfpHeader` for the given transaction id and OpenFlow message.
     fn header_of(u32, &Self) -> OfpHeader;
-    /// Return a marshaled buffer containing an OpenFlow header and the message `msg`.
-    fn marshal(u32, Self) -> Vec<u8>;
+    /// Return a marshaled buffer containing an OpenFlow header and the message `msg`, or the
+    /// reason `msg` cannot be sent.
+    fn marshal(u32, Self) -> Result<Vec<u8>, MarshalError>;
     /// Returns a pair `(u32, OfpMessage)` of the transaction id and OpenFlow message parsed from
-    /// the given OpenFlow header `header`, and buffer `buf`.
-    fn parse(&OfpHeader, &[u8]) -> (u32, Self);
+    /// the given OpenFlow header `header`, and buffer `buf`, or the reason `buf` could not be
+    /// parsed.
+    fn parse(&OfpHeader, &[u8]) -> Result<(u32, Self), ParseError>;
 }
@@ -1,14 +1,16 @@
 use std::io::{BufRead, Cursor, Read, Write};
-use std::mem::{size_of, transmute};
+use std::mem::size_of;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 use bits::*;
+use marshal_error::MarshalError;
 use packet::{bytes_of_mac, mac_of_bytes};
+use parse_error::ParseError;
 
 /// OpenFlow 1.0 message type codes, used by headers to identify meaning of the rest of a message.
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MsgCode {
     Hello,
     Error,
@@ -34,22 +36,56 @@ pub enum MsgCode {
     QueueGetConfigResp,
 }
 
+impl MsgCode {
+    /// Return the `MsgCode` with type byte `typ`, or `None` if `typ` is not an OpenFlow 1.0
+    /// message type.
+    pub fn of_int(typ: u8) -> Option<MsgCode> {
+        match typ {
+            0 => Some(MsgCode::Hello),
+            1 => Some(MsgCode::Error),
+            2 => Some(MsgCode::EchoReq),
+            3 => Some(MsgCode::EchoResp),
+            4 => Some(MsgCode::Vendor),
+            5 => Some(MsgCode::FeaturesReq),
+            6 => Some(MsgCode::FeaturesResp),
+            7 => Some(MsgCode::GetConfigReq),
+            8 => Some(MsgCode::GetConfigResp),
+            9 => Some(MsgCode::SetConfig),
+            10 => Some(MsgCode::PacketIn),
+            11 => Some(MsgCode::FlowRemoved),
+            12 => Some(MsgCode::PortStatus),
+            13 => Some(MsgCode::PacketOut),
+            14 => Some(MsgCode::FlowMod),
+            15 => Some(MsgCode::PortMod),
+            16 => Some(MsgCode::StatsReq),
+            17 => Some(MsgCode::StatsResp),
+            18 => Some(MsgCode::BarrierReq),
+            19 => Some(MsgCode::BarrierResp),
+            20 => Some(MsgCode::QueueGetConfigReq),
+            21 => Some(MsgCode::QueueGetConfigResp),
+            _ => None,
+        }
+    }
+}
+
 /// Common API for message types implementing OpenFlow Message Codes (see `MsgCode` enum).
-pub trait MessageType {
+pub trait MessageType: Sized {
     /// Return the byte-size of a message.
     fn size_of(&Self) -> usize;
     /// Parse a buffer into a message.
-    fn parse(buf: &[u8]) -> Self;
+    fn parse(buf: &[u8]) -> Result<Self, ParseError>;
     /// Marshal a message into a `u8` buffer.
     fn marshal(Self, &mut Vec<u8>);
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Mask<T> {
     pub value: T,
     pub mask: Option<T>,
 }
 
 /// Fields to match against flows.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Pattern {
     pub dl_src: Option<u64>,
     pub dl_dst: Option<u64>,
@@ -176,73 +212,61 @@ impl Pattern {
         size_of::<OfpMatch>()
     }
 
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Pattern {
-        let w = Wildcards::parse(bytes.read_u32::<BigEndian>().unwrap());
-        let in_port = if w.in_port {
-            None
-        } else {
-            Some(bytes.read_u16::<BigEndian>().unwrap())
-        };
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Pattern, ParseError> {
+        let w = Wildcards::parse(bytes.read_u32::<BigEndian>()?);
+        let in_port = bytes.read_u16::<BigEndian>()?;
+        let mut dl_src: [u8; 6] = [0; 6];
+        bytes.read_exact(&mut dl_src)?;
+        let mut dl_dst: [u8; 6] = [0; 6];
+        bytes.read_exact(&mut dl_dst)?;
+        let dl_vlan = bytes.read_u16::<BigEndian>()?;
+        let dl_vlan_pcp = bytes.read_u8()?;
+        bytes.consume(1);
+        let dl_typ = bytes.read_u16::<BigEndian>()?;
+        let nw_tos = bytes.read_u8()?;
+        let nw_proto = bytes.read_u8()?;
+        bytes.consume(2);
+        let nw_src = bytes.read_u32::<BigEndian>()?;
+        let nw_dst = bytes.read_u32::<BigEndian>()?;
+        let tp_src = bytes.read_u16::<BigEndian>()?;
+        let tp_dst = bytes.read_u16::<BigEndian>()?;
+
+        let in_port = if w.in_port { None } else { Some(in_port) };
         let dl_src = if w.dl_src {
             None
         } else {
-            let mut arr: [u8; 6] = [0; 6];
-            for i in 0..6 {
-                arr[i] = bytes.read_u8().unwrap();
-            }
-            Some(mac_of_bytes(arr))
+            Some(mac_of_bytes(dl_src))
         };
         let dl_dst = if w.dl_dst {
             None
         } else {
-            let mut arr: [u8; 6] = [0; 6];
-            for i in 0..6 {
-                arr[i] = bytes.read_u8().unwrap();
-            }
-            Some(mac_of_bytes(arr))
+            Some(mac_of_bytes(dl_dst))
         };
         let dl_vlan = if w.dl_vlan {
             None
+        } else if dl_vlan == 0xffff {
+            Some(None)
         } else {
-            let vlan = bytes.read_u16::<BigEndian>().unwrap();
-            if vlan == 0xfff {
-                Some(None)
-            } else {
-                Some(Some(vlan))
-            }
+            Some(Some(dl_vlan))
         };
         let dl_vlan_pcp = if w.dl_vlan_pcp {
             None
         } else {
-            Some(bytes.read_u8().unwrap())
-        };
-        bytes.consume(1);
-        let dl_typ = if w.dl_type {
-            None
-        } else {
-            Some(bytes.read_u16::<BigEndian>().unwrap())
-        };
-        let nw_tos = if w.nw_tos {
-            None
-        } else {
-            Some(bytes.read_u8().unwrap())
-        };
-        let nw_proto = if w.nw_proto {
-            None
-        } else {
-            Some(bytes.read_u8().unwrap())
+            Some(dl_vlan_pcp)
         };
-        bytes.consume(2);
+        let dl_typ = if w.dl_type { None } else { Some(dl_typ) };
+        let nw_tos = if w.nw_tos { None } else { Some(nw_tos) };
+        let nw_proto = if w.nw_proto { None } else { Some(nw_proto) };
         let nw_src = if w.nw_src >= 32 {
             None
         } else if w.nw_src == 0 {
             Some(Mask {
-                value: bytes.read_u32::<BigEndian>().unwrap(),
+                value: nw_src,
                 mask: None,
             })
         } else {
             Some(Mask {
-                value: bytes.read_u32::<BigEndian>().unwrap(),
+                value: nw_src,
                 mask: Some(w.nw_src),
             })
         };
@@ -250,26 +274,18 @@ impl Pattern {
             None
         } else if w.nw_dst == 0 {
             Some(Mask {
-                value: bytes.read_u32::<BigEndian>().unwrap(),
+                value: nw_dst,
                 mask: None,
             })
         } else {
             Some(Mask {
-                value: bytes.read_u32::<BigEndian>().unwrap(),
+                value: nw_dst,
                 mask: Some(w.nw_dst),
             })
         };
-        let tp_src = if w.tp_src {
-            None
-        } else {
-            Some(bytes.read_u16::<BigEndian>().unwrap())
-        };
-        let tp_dst = if w.tp_dst {
-            None
-        } else {
-            Some(bytes.read_u16::<BigEndian>().unwrap())
-        };
-        Pattern {
+        let tp_src = if w.tp_src { None } else { Some(tp_src) };
+        let tp_dst = if w.tp_dst { None } else { Some(tp_dst) };
+        Ok(Pattern {
             dl_src: dl_src,
             dl_dst: dl_dst,
             dl_typ: dl_typ,
@@ -282,7 +298,7 @@ impl Pattern {
             tp_src: tp_src,
             tp_dst: tp_dst,
             in_port: in_port,
-        }
+        })
     }
 
     fn if_word48(n: Option<u64>) -> u64 {
@@ -339,7 +355,7 @@ impl Pattern {
 struct OfpMatch(u32, u16, [u8; 6], [u8; 6], u16, u8, u8, u16, u8, u8, u16, u32, u32, u16, u16);
 
 /// Port behavior.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum PseudoPort {
     PhysicalPort(u16),
     InPort,
@@ -365,16 +381,16 @@ enum OfpPort {
 }
 
 impl PseudoPort {
-    fn of_int(p: u16) -> Option<PseudoPort> {
+    fn of_int(p: u16) -> Result<Option<PseudoPort>, ParseError> {
         if (OfpPort::OFPPNone as u16) == p {
-            None
+            Ok(None)
         } else {
-            Some(PseudoPort::make(p, 0))
+            Ok(Some(PseudoPort::make(p, 0)?))
         }
     }
 
-    fn make(p: u16, len: u64) -> PseudoPort {
-        match p {
+    fn make(p: u16, len: u64) -> Result<PseudoPort, ParseError> {
+        let pp = match p {
             p if p == (OfpPort::OFPPInPort as u16) => PseudoPort::InPort,
             p if p == (OfpPort::OFPPTable as u16) => PseudoPort::Table,
             p if p == (OfpPort::OFPPNormal as u16) => PseudoPort::Normal,
@@ -386,10 +402,11 @@ impl PseudoPort {
                 if p <= (OfpPort::OFPPMax as u16) {
                     PseudoPort::PhysicalPort(p)
                 } else {
-                    panic!("Unsupported port number {}", p)
+                    return Err(ParseError::UnknownValue("ofp_port", p as u64));
                 }
             }
-        }
+        };
+        Ok(pp)
     }
 
     fn marshal(pp: PseudoPort, bytes: &mut Vec<u8>) {
@@ -409,7 +426,7 @@ impl PseudoPort {
 }
 
 /// Actions associated with flows and packets.
-#[derive(Copy, Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Action {
     Output(PseudoPort),
     SetDlVlan(Option<u16>),
@@ -422,6 +439,10 @@ pub enum Action {
     SetTpSrc(u16),
     SetTpDst(u16),
     Enqueue(PseudoPort, u32),
+    /// Vendor-defined action with the given vendor id and body. The body must be a multiple of
+    /// 8 bytes long, including any padding the vendor's format calls for, as actions are
+    /// marshaled without adding padding of their own.
+    Vendor(u32, Vec<u8>),
 }
 
 #[repr(packed)]
@@ -445,6 +466,8 @@ struct OfpActionTpPort(u16, u16);
 struct OfpActionNwTos(u8, [u8; 3]);
 #[repr(packed)]
 struct OfpActionEnqueue(u16, [u8; 6], u32);
+#[repr(packed)]
+struct OfpActionVendorHeader(u32);
 
 #[repr(u16)]
 enum OfpActionType {
@@ -460,6 +483,7 @@ enum OfpActionType {
     OFPATSetTpSrc,
     OFPATSetTpDst,
     OFPATEnqueue,
+    OFPATVendor = 0xffff,
 }
 
 impl Action {
@@ -477,6 +501,7 @@ impl Action {
             Action::SetTpSrc(_) => OfpActionType::OFPATSetTpSrc,
             Action::SetTpDst(_) => OfpActionType::OFPATSetTpDst,
             Action::Enqueue(_, _) => OfpActionType::OFPATEnqueue,
+            Action::Vendor(_, _) => OfpActionType::OFPATVendor,
         }
     }
 
@@ -495,6 +520,7 @@ impl Action {
             Action::SetTpSrc(_) |
             Action::SetTpDst(_) => size_of::<OfpActionTpPort>(),
             Action::Enqueue(_, _) => size_of::<OfpActionEnqueue>(),
+            Action::Vendor(_, ref body) => size_of::<OfpActionVendorHeader>() + body.len(),
         };
         h + body
     }
@@ -503,17 +529,31 @@ impl Action {
         actions.iter().fold(0, |acc, x| Action::size_of(x) + acc)
     }
 
-    fn _parse(bytes: &mut Cursor<Vec<u8>>) -> Action {
-        let action_code = bytes.read_u16::<BigEndian>().unwrap();
-        let _ = bytes.read_u16::<BigEndian>().unwrap();
+    /// Check that every action in `actions` can be marshaled. Vendor action bodies must keep
+    /// the length of the action a multiple of 8 bytes.
+    fn check_sequence(actions: &[Action]) -> Result<(), MarshalError> {
+        for action in actions {
+            if let Action::Vendor(_, ref body) = *action {
+                if body.len() % 8 != 0 {
+                    return Err(MarshalError::InvalidValue("ofp_action_vendor_header body length",
+                                                          body.len() as u64));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn _parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Action, ParseError> {
+        let action_code = bytes.read_u16::<BigEndian>()?;
+        let action_len = bytes.read_u16::<BigEndian>()?;
         let action = match action_code {
             t if t == (OfpActionType::OFPATOutput as u16) => {
-                let port_code = bytes.read_u16::<BigEndian>().unwrap();
-                let len = bytes.read_u16::<BigEndian>().unwrap();
-                Action::Output(PseudoPort::make(port_code, len as u64))
+                let port_code = bytes.read_u16::<BigEndian>()?;
+                let len = bytes.read_u16::<BigEndian>()?;
+                Action::Output(PseudoPort::make(port_code, len as u64)?)
             }
             t if t == (OfpActionType::OFPATSetVlanVId as u16) => {
-                let vid = bytes.read_u16::<BigEndian>().unwrap();
+                let vid = bytes.read_u16::<BigEndian>()?;
                 bytes.consume(2);
                 if vid == 0xffff {
                     Action::SetDlVlan(None)
@@ -522,7 +562,7 @@ impl Action {
                 }
             }
             t if t == (OfpActionType::OFPATSetVlanPCP as u16) => {
-                let pcp = bytes.read_u8().unwrap();
+                let pcp = bytes.read_u8()?;
                 bytes.consume(3);
                 Action::SetDlVlanPcp(pcp)
             }
@@ -533,7 +573,7 @@ impl Action {
             t if t == (OfpActionType::OFPATSetDlSrc as u16) => {
                 let mut dl_addr: [u8; 6] = [0; 6];
                 for i in 0..6 {
-                    dl_addr[i] = bytes.read_u8().unwrap();
+                    dl_addr[i] = bytes.read_u8()?;
                 }
                 bytes.consume(6);
                 Action::SetDlSrc(mac_of_bytes(dl_addr))
@@ -541,52 +581,60 @@ impl Action {
             t if t == (OfpActionType::OFPATSetDlDst as u16) => {
                 let mut dl_addr: [u8; 6] = [0; 6];
                 for i in 0..6 {
-                    dl_addr[i] = bytes.read_u8().unwrap();
+                    dl_addr[i] = bytes.read_u8()?;
                 }
                 bytes.consume(6);
                 Action::SetDlDst(mac_of_bytes(dl_addr))
             }
             t if t == (OfpActionType::OFPATSetNwSrc as u16) => {
-                Action::SetNwSrc(bytes.read_u32::<BigEndian>().unwrap())
+                Action::SetNwSrc(bytes.read_u32::<BigEndian>()?)
             }
             t if t == (OfpActionType::OFPATSetNwDst as u16) => {
-                Action::SetNwDst(bytes.read_u32::<BigEndian>().unwrap())
+                Action::SetNwDst(bytes.read_u32::<BigEndian>()?)
             }
             t if t == (OfpActionType::OFPATSetNwTos as u16) => {
-                let nw_tos = bytes.read_u8().unwrap();
+                let nw_tos = bytes.read_u8()?;
                 bytes.consume(3);
                 Action::SetNwTos(nw_tos)
             }
             t if t == (OfpActionType::OFPATSetTpSrc as u16) => {
-                let pt = bytes.read_u16::<BigEndian>().unwrap();
+                let pt = bytes.read_u16::<BigEndian>()?;
                 bytes.consume(2);
                 Action::SetTpSrc(pt)
             }
             t if t == (OfpActionType::OFPATSetTpDst as u16) => {
-                let pt = bytes.read_u16::<BigEndian>().unwrap();
+                let pt = bytes.read_u16::<BigEndian>()?;
                 bytes.consume(2);
                 Action::SetTpDst(pt)
             }
             t if t == (OfpActionType::OFPATEnqueue as u16) => {
-                let pt = bytes.read_u16::<BigEndian>().unwrap();
+                let pt = bytes.read_u16::<BigEndian>()?;
                 bytes.consume(6);
-                let qid = bytes.read_u32::<BigEndian>().unwrap();
-                Action::Enqueue(PseudoPort::make(pt, 0), qid)
+                let qid = bytes.read_u32::<BigEndian>()?;
+                Action::Enqueue(PseudoPort::make(pt, 0)?, qid)
+            }
+            t if t == (OfpActionType::OFPATVendor as u16) => {
+                let vendor = bytes.read_u32::<BigEndian>()?;
+                let header_len = size_of::<OfpActionHeader>() + size_of::<OfpActionVendorHeader>();
+                if (action_len as usize) < header_len || action_len % 8 != 0 {
+                    return Err(ParseError::BadLength("ofp_action_vendor_header",
+                                                     action_len as usize));
+                }
+                let mut body = vec![0; action_len as usize - header_len];
+                bytes.read_exact(&mut body)?;
+                Action::Vendor(vendor, body)
             }
-            t => panic!("Unrecognized OfpActionType {}", t),
+            t => return Err(ParseError::UnsupportedType("ofp_action_type", t as u64)),
         };
-        action
+        Ok(action)
     }
 
-    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Vec<Action> {
-        if bytes.get_ref().is_empty() {
-            vec![]
-        } else {
-            let action = Action::_parse(bytes);
-            let mut v = vec![action];
-            v.append(&mut Action::parse_sequence(bytes));
-            v
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<Action>, ParseError> {
+        let mut v = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            v.push(Action::_parse(bytes)?)
         }
+        Ok(v)
     }
 
     fn move_controller_last(acts: Vec<Action>) -> Vec<Action> {
@@ -652,11 +700,16 @@ impl Action {
                 }
                 bytes.write_u32::<BigEndian>(qid).unwrap();
             }
+            Action::Vendor(vendor, body) => {
+                bytes.write_u32::<BigEndian>(vendor).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
         }
     }
 }
 
 /// How long before a flow entry expires.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Timeout {
     Permanent,
     ExpiresAfter(u16),
@@ -679,6 +732,7 @@ impl Timeout {
 }
 
 /// Capabilities supported by the datapath.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Capabilities {
     pub flow_stats: bool,
     pub table_stats: bool,
@@ -689,7 +743,33 @@ pub struct Capabilities {
     pub arp_match_ip: bool,
 }
 
+impl Capabilities {
+    fn of_int(d: u32) -> Capabilities {
+        Capabilities {
+            flow_stats: test_bit(0, d as u64),
+            table_stats: test_bit(1, d as u64),
+            port_stats: test_bit(2, d as u64),
+            stp: test_bit(3, d as u64),
+            ip_reasm: test_bit(5, d as u64),
+            queue_stats: test_bit(6, d as u64),
+            arp_match_ip: test_bit(7, d as u64),
+        }
+    }
+
+    fn to_int(c: &Capabilities) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, c.flow_stats) as u32;
+        let ret = bit(1, ret as u64, c.table_stats) as u32;
+        let ret = bit(2, ret as u64, c.port_stats) as u32;
+        let ret = bit(3, ret as u64, c.stp) as u32;
+        let ret = bit(5, ret as u64, c.ip_reasm) as u32;
+        let ret = bit(6, ret as u64, c.queue_stats) as u32;
+        bit(7, ret as u64, c.arp_match_ip) as u32
+    }
+}
+
 /// Actions supported by the datapath.
+#[derive(Clone, Debug, PartialEq)]
 pub struct SupportedActions {
     pub output: bool,
     pub set_vlan_id: bool,
@@ -706,7 +786,45 @@ pub struct SupportedActions {
     pub vendor: bool,
 }
 
+impl SupportedActions {
+    fn of_int(d: u32) -> SupportedActions {
+        SupportedActions {
+            output: test_bit(0, d as u64),
+            set_vlan_id: test_bit(1, d as u64),
+            set_vlan_pcp: test_bit(2, d as u64),
+            strip_vlan: test_bit(3, d as u64),
+            set_dl_src: test_bit(4, d as u64),
+            set_dl_dst: test_bit(5, d as u64),
+            set_nw_src: test_bit(6, d as u64),
+            set_nw_dst: test_bit(7, d as u64),
+            set_nw_tos: test_bit(8, d as u64),
+            set_tp_src: test_bit(9, d as u64),
+            set_tp_dst: test_bit(10, d as u64),
+            enqueue: test_bit(11, d as u64),
+            vendor: test_bit(12, d as u64),
+        }
+    }
+
+    fn to_int(sa: &SupportedActions) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, sa.output) as u32;
+        let ret = bit(1, ret as u64, sa.set_vlan_id) as u32;
+        let ret = bit(2, ret as u64, sa.set_vlan_pcp) as u32;
+        let ret = bit(3, ret as u64, sa.strip_vlan) as u32;
+        let ret = bit(4, ret as u64, sa.set_dl_src) as u32;
+        let ret = bit(5, ret as u64, sa.set_dl_dst) as u32;
+        let ret = bit(6, ret as u64, sa.set_nw_src) as u32;
+        let ret = bit(7, ret as u64, sa.set_nw_dst) as u32;
+        let ret = bit(8, ret as u64, sa.set_nw_tos) as u32;
+        let ret = bit(9, ret as u64, sa.set_tp_src) as u32;
+        let ret = bit(10, ret as u64, sa.set_tp_dst) as u32;
+        let ret = bit(11, ret as u64, sa.enqueue) as u32;
+        bit(12, ret as u64, sa.vendor) as u32
+    }
+}
+
 /// Switch features.
+#[derive(Clone, Debug, PartialEq)]
 pub struct SwitchFeatures {
     pub datapath_id: u64,
     pub num_buffers: u32,
@@ -725,67 +843,102 @@ impl MessageType for SwitchFeatures {
         size_of::<OfpSwitchFeatures>() + pds
     }
 
-    fn parse(buf: &[u8]) -> SwitchFeatures {
+    fn parse(buf: &[u8]) -> Result<SwitchFeatures, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let datapath_id = bytes.read_u64::<BigEndian>().unwrap();
-        let num_buffers = bytes.read_u32::<BigEndian>().unwrap();
-        let num_tables = bytes.read_u8().unwrap();
+        let datapath_id = bytes.read_u64::<BigEndian>()?;
+        let num_buffers = bytes.read_u32::<BigEndian>()?;
+        let num_tables = bytes.read_u8()?;
         bytes.consume(3);
-        let supported_capabilities = {
-            let d = bytes.read_u32::<BigEndian>().unwrap();
-            Capabilities {
-                flow_stats: test_bit(0, d as u64),
-                table_stats: test_bit(1, d as u64),
-                port_stats: test_bit(2, d as u64),
-                stp: test_bit(3, d as u64),
-                ip_reasm: test_bit(5, d as u64),
-                queue_stats: test_bit(6, d as u64),
-                arp_match_ip: test_bit(7, d as u64),
-            }
-        };
-        let supported_actions = {
-            let d = bytes.read_u32::<BigEndian>().unwrap();
-            SupportedActions {
-                output: test_bit(0, d as u64),
-                set_vlan_id: test_bit(1, d as u64),
-                set_vlan_pcp: test_bit(2, d as u64),
-                strip_vlan: test_bit(3, d as u64),
-                set_dl_src: test_bit(4, d as u64),
-                set_dl_dst: test_bit(5, d as u64),
-                set_nw_src: test_bit(6, d as u64),
-                set_nw_dst: test_bit(7, d as u64),
-                set_nw_tos: test_bit(8, d as u64),
-                set_tp_src: test_bit(9, d as u64),
-                set_tp_dst: test_bit(10, d as u64),
-                enqueue: test_bit(11, d as u64),
-                vendor: test_bit(12, d as u64),
-            }
-        };
+        let supported_capabilities = Capabilities::of_int(bytes.read_u32::<BigEndian>()?);
+        let supported_actions = SupportedActions::of_int(bytes.read_u32::<BigEndian>()?);
         let ports = {
             let mut v = vec![];
             let pos = bytes.position() as usize;
             let rem = bytes.get_ref()[pos..].to_vec();
             let num_ports = rem.len() / size_of::<OfpPhyPort>();
             for _ in 0..num_ports {
-                v.push(PortDesc::parse(&mut bytes))
+                v.push(PortDesc::parse(&mut bytes)?)
             }
             v
         };
-        SwitchFeatures {
+        Ok(SwitchFeatures {
             datapath_id: datapath_id,
             num_buffers: num_buffers,
             num_tables: num_tables,
             supported_capabilities: supported_capabilities,
             supported_actions: supported_actions,
             ports: ports,
+        })
+    }
+
+    fn marshal(sf: SwitchFeatures, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(sf.datapath_id).unwrap();
+        bytes.write_u32::<BigEndian>(sf.num_buffers).unwrap();
+        bytes.write_u8(sf.num_tables).unwrap();
+        for _ in 0..3 {
+            bytes.write_u8(0).unwrap();
+        }
+        bytes.write_u32::<BigEndian>(Capabilities::to_int(&sf.supported_capabilities)).unwrap();
+        bytes.write_u32::<BigEndian>(SupportedActions::to_int(&sf.supported_actions)).unwrap();
+        for pd in sf.ports {
+            PortDesc::marshal(pd, bytes)
         }
     }
+}
+
+/// How the datapath handles IP fragments.
+#[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FragHandling {
+    /// No special handling for fragments.
+    Normal,
+    /// Drop fragments.
+    Drop,
+    /// Reassemble fragments (only if `Capabilities.ip_reasm` is set).
+    Reassemble,
+}
+
+/// Switch configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchConfig {
+    pub frag: FragHandling,
+    /// Max bytes of a new flow that the datapath should send to the controller.
+    pub miss_send_len: u16,
+}
+
+#[repr(packed)]
+struct OfpSwitchConfig(u16, u16);
+
+impl MessageType for SwitchConfig {
+    fn size_of(_: &SwitchConfig) -> usize {
+        size_of::<OfpSwitchConfig>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<SwitchConfig, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let flags = bytes.read_u16::<BigEndian>()?;
+        let frag = match flags & 3 {
+            0 => FragHandling::Normal,
+            1 => FragHandling::Drop,
+            2 => FragHandling::Reassemble,
+            f => return Err(ParseError::UnknownValue("ofp_config_flags", f as u64)),
+        };
+        let miss_send_len = bytes.read_u16::<BigEndian>()?;
+        Ok(SwitchConfig {
+            frag: frag,
+            miss_send_len: miss_send_len,
+        })
+    }
 
-    fn marshal(_: SwitchFeatures, _: &mut Vec<u8>) {}
+    fn marshal(sc: SwitchConfig, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(sc.frag as u16).unwrap();
+        bytes.write_u16::<BigEndian>(sc.miss_send_len).unwrap();
+    }
 }
 
 /// Type of modification to perform on a flow table.
 #[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FlowModCmd {
     AddFlow,
     ModFlow,
@@ -794,7 +947,21 @@ pub enum FlowModCmd {
     DeleteStrictFlow,
 }
 
+impl FlowModCmd {
+    fn of_int(code: u16) -> Result<FlowModCmd, ParseError> {
+        match code {
+            0 => Ok(FlowModCmd::AddFlow),
+            1 => Ok(FlowModCmd::ModFlow),
+            2 => Ok(FlowModCmd::ModStrictFlow),
+            3 => Ok(FlowModCmd::DeleteFlow),
+            4 => Ok(FlowModCmd::DeleteStrictFlow),
+            _ => Err(ParseError::UnknownValue("ofp_flow_mod_command", code as u64)),
+        }
+    }
+}
+
 /// Represents modifications to a flow table from the controller.
+#[derive(Clone, Debug, PartialEq)]
 pub struct FlowMod {
     pub command: FlowModCmd,
     pub pattern: Pattern,
@@ -821,6 +988,16 @@ impl FlowMod {
         2 & flags != 0
     }
 
+    /// Check that flowmod `fm` can be installed. Packets cannot be output to `OFPP_TABLE` from
+    /// a flow table.
+    fn check(fm: &FlowMod) -> Result<(), MarshalError> {
+        if fm.actions.contains(&Action::Output(PseudoPort::Table)) {
+            return Err(MarshalError::InvalidValue("ofp_action_output port",
+                                                  OfpPort::OFPPTable as u64));
+        }
+        Action::check_sequence(&fm.actions)
+    }
+
     fn notify_when_removed_of_flags(flags: u16) -> bool {
         1 & flags != 0
     }
@@ -832,19 +1009,19 @@ impl MessageType for FlowMod {
         Action::size_of_sequence(&msg.actions)
     }
 
-    fn parse(buf: &[u8]) -> FlowMod {
+    fn parse(buf: &[u8]) -> Result<FlowMod, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let pattern = Pattern::parse(&mut bytes);
-        let cookie = bytes.read_u64::<BigEndian>().unwrap();
-        let command = unsafe { transmute(bytes.read_u16::<BigEndian>().unwrap()) };
-        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>().unwrap());
-        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>().unwrap());
-        let prio = bytes.read_u16::<BigEndian>().unwrap();
-        let buffer_id = bytes.read_i32::<BigEndian>().unwrap();
-        let out_port = PseudoPort::of_int(bytes.read_u16::<BigEndian>().unwrap());
-        let flags = bytes.read_u16::<BigEndian>().unwrap();
-        let actions = Action::parse_sequence(&mut bytes);
-        FlowMod {
+        let pattern = Pattern::parse(&mut bytes)?;
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let command = FlowModCmd::of_int(bytes.read_u16::<BigEndian>()?)?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let prio = bytes.read_u16::<BigEndian>()?;
+        let buffer_id = bytes.read_i32::<BigEndian>()?;
+        let out_port = PseudoPort::of_int(bytes.read_u16::<BigEndian>()?)?;
+        let flags = bytes.read_u16::<BigEndian>()?;
+        let actions = Action::parse_sequence(&mut bytes)?;
+        Ok(FlowMod {
             command: command,
             pattern: pattern,
             priority: prio,
@@ -861,7 +1038,7 @@ impl MessageType for FlowMod {
             },
             out_port: out_port,
             check_overlap: FlowMod::check_overlap_of_flags(flags),
-        }
+        })
     }
 
     fn marshal(fm: FlowMod, bytes: &mut Vec<u8>) {
@@ -884,19 +1061,13 @@ impl MessageType for FlowMod {
                                                           fm.notify_when_removed))
             .unwrap();
         for act in Action::move_controller_last(fm.actions) {
-            match act {
-                Action::Output(PseudoPort::Table) => {
-                    panic!("OFPPTable not allowed in installed flow.")
-                }
-                _ => (),
-            }
             Action::marshal(act, bytes)
         }
     }
 }
 
 /// The data associated with a packet received by the controller.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Payload {
     Buffered(u32, Vec<u8>),
     NotBuffered(Vec<u8>),
@@ -920,15 +1091,25 @@ impl Payload {
 
 /// The reason a packet arrives at the controller.
 #[repr(u8)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PacketInReason {
     NoMatch,
     ExplicitSend,
 }
 
+impl PacketInReason {
+    fn of_int(code: u8) -> Result<PacketInReason, ParseError> {
+        match code {
+            0 => Ok(PacketInReason::NoMatch),
+            1 => Ok(PacketInReason::ExplicitSend),
+            _ => Err(ParseError::UnknownValue("ofp_packet_in_reason", code as u64)),
+        }
+    }
+}
+
 
 /// Represents packets received by the datapath and sent to the controller.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PacketIn {
     pub input_payload: Payload,
     pub total_len: u16,
@@ -944,27 +1125,27 @@ impl MessageType for PacketIn {
         size_of::<OfpPacketIn>() + Payload::size_of(&pi.input_payload)
     }
 
-    fn parse(buf: &[u8]) -> PacketIn {
+    fn parse(buf: &[u8]) -> Result<PacketIn, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let buf_id = match bytes.read_i32::<BigEndian>().unwrap() {
+        let buf_id = match bytes.read_i32::<BigEndian>()? {
             -1 => None,
             n => Some(n),
         };
-        let total_len = bytes.read_u16::<BigEndian>().unwrap();
-        let port = bytes.read_u16::<BigEndian>().unwrap();
-        let reason = unsafe { transmute(bytes.read_u8().unwrap()) };
+        let total_len = bytes.read_u16::<BigEndian>()?;
+        let port = bytes.read_u16::<BigEndian>()?;
+        let reason = PacketInReason::of_int(bytes.read_u8()?)?;
         bytes.consume(1);
-        let pk = bytes.fill_buf().unwrap().to_vec();
+        let pk = bytes.fill_buf()?.to_vec();
         let payload = match buf_id {
             None => Payload::NotBuffered(pk),
             Some(n) => Payload::Buffered(n as u32, pk),
         };
-        PacketIn {
+        Ok(PacketIn {
             input_payload: payload,
             total_len: total_len,
             port: port,
             reason: reason,
-        }
+        })
     }
 
     fn marshal(pi: PacketIn, bytes: &mut Vec<u8>) {
@@ -976,11 +1157,13 @@ impl MessageType for PacketIn {
         bytes.write_u16::<BigEndian>(pi.total_len).unwrap();
         bytes.write_u16::<BigEndian>(pi.port).unwrap();
         bytes.write_u8(pi.reason as u8).unwrap();
+        bytes.write_u8(0).unwrap();
         Payload::marshal(pi.input_payload, bytes)
     }
 }
 
 /// Represents packets sent from the controller.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PacketOut {
     pub output_payload: Payload,
     pub port_id: Option<u16>,
@@ -996,22 +1179,22 @@ impl MessageType for PacketOut {
         Payload::size_of(&po.output_payload)
     }
 
-    fn parse(buf: &[u8]) -> PacketOut {
+    fn parse(buf: &[u8]) -> Result<PacketOut, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let buf_id = match bytes.read_i32::<BigEndian>().unwrap() {
+        let buf_id = match bytes.read_i32::<BigEndian>()? {
             -1 => None,
             n => Some(n),
         };
-        let in_port = bytes.read_u16::<BigEndian>().unwrap();
-        let actions_len = bytes.read_u16::<BigEndian>().unwrap();
+        let in_port = bytes.read_u16::<BigEndian>()?;
+        let actions_len = bytes.read_u16::<BigEndian>()?;
         let mut actions_buf = vec![0; actions_len as usize];
-        bytes.read_exact(&mut actions_buf).unwrap();
+        bytes.read_exact(&mut actions_buf)?;
         let mut actions_bytes = Cursor::new(actions_buf);
-        let actions = Action::parse_sequence(&mut actions_bytes);
-        PacketOut {
+        let actions = Action::parse_sequence(&mut actions_bytes)?;
+        Ok(PacketOut {
             output_payload: match buf_id {
-                None => Payload::NotBuffered(bytes.fill_buf().unwrap().to_vec()),
-                Some(n) => Payload::Buffered(n as u32, bytes.fill_buf().unwrap().to_vec()),
+                None => Payload::NotBuffered(bytes.fill_buf()?.to_vec()),
+                Some(n) => Payload::Buffered(n as u32, bytes.fill_buf()?.to_vec()),
             },
             port_id: {
                 if in_port == OfpPort::OFPPNone as u16 {
@@ -1021,7 +1204,7 @@ impl MessageType for PacketOut {
                 }
             },
             apply_actions: actions,
-        }
+        })
     }
 
     fn marshal(po: PacketOut, bytes: &mut Vec<u8>) {
@@ -1044,13 +1227,26 @@ impl MessageType for PacketOut {
 
 /// Reason a flow was removed from a switch
 #[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FlowRemovedReason {
     IdleTimeout,
     HardTimeout,
     Delete,
 }
 
+impl FlowRemovedReason {
+    fn of_int(code: u8) -> Result<FlowRemovedReason, ParseError> {
+        match code {
+            0 => Ok(FlowRemovedReason::IdleTimeout),
+            1 => Ok(FlowRemovedReason::HardTimeout),
+            2 => Ok(FlowRemovedReason::Delete),
+            _ => Err(ParseError::UnknownValue("ofp_flow_removed_reason", code as u64)),
+        }
+    }
+}
+
 /// Flow removed (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
 pub struct FlowRemoved {
     pub pattern: Pattern,
     pub cookie: i64,
@@ -1071,20 +1267,20 @@ impl MessageType for FlowRemoved {
         Pattern::size_of(&f.pattern) + size_of::<OfpFlowRemoved>()
     }
 
-    fn parse(buf: &[u8]) -> FlowRemoved {
+    fn parse(buf: &[u8]) -> Result<FlowRemoved, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let pattern = Pattern::parse(&mut bytes);
-        let cookie = bytes.read_i64::<BigEndian>().unwrap();
-        let priority = bytes.read_u16::<BigEndian>().unwrap();
-        let reason = unsafe { transmute(bytes.read_u8().unwrap()) };
+        let pattern = Pattern::parse(&mut bytes)?;
+        let cookie = bytes.read_i64::<BigEndian>()?;
+        let priority = bytes.read_u16::<BigEndian>()?;
+        let reason = FlowRemovedReason::of_int(bytes.read_u8()?)?;
         bytes.consume(1);
-        let duration_sec = bytes.read_u32::<BigEndian>().unwrap();
-        let duration_nsec = bytes.read_u32::<BigEndian>().unwrap();
-        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>().unwrap());
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
         bytes.consume(2);
-        let packet_count = bytes.read_u64::<BigEndian>().unwrap();
-        let byte_count = bytes.read_u64::<BigEndian>().unwrap();
-        FlowRemoved {
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        Ok(FlowRemoved {
             pattern: pattern,
             cookie: cookie,
             priority: priority,
@@ -1094,7 +1290,7 @@ impl MessageType for FlowRemoved {
             idle_timeout: idle,
             packet_count: packet_count,
             byte_count: byte_count,
-        }
+        })
     }
 
     fn marshal(f: FlowRemoved, bytes: &mut Vec<u8>) {
@@ -1106,6 +1302,7 @@ impl MessageType for FlowRemoved {
         bytes.write_u32::<BigEndian>(f.duration_sec).unwrap();
         bytes.write_u32::<BigEndian>(f.duration_nsec).unwrap();
         bytes.write_u16::<BigEndian>(Timeout::to_int(f.idle_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
         bytes.write_u64::<BigEndian>(f.packet_count).unwrap();
         bytes.write_u64::<BigEndian>(f.byte_count).unwrap();
     }
@@ -1113,6 +1310,7 @@ impl MessageType for FlowRemoved {
 
 /// STP state of a port.
 #[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum StpState {
     Listen,
     Learn,
@@ -1121,12 +1319,33 @@ pub enum StpState {
 }
 
 /// Current state of a physical port. Not configurable by the controller.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PortState {
     pub down: bool,
     pub stp_state: StpState,
 }
 
+impl PortState {
+    fn of_int(d: u32) -> PortState {
+        let stp_state = match (d >> 8) & 3 {
+            0 => StpState::Listen,
+            1 => StpState::Learn,
+            2 => StpState::Forward,
+            _ => StpState::Block,
+        };
+        PortState {
+            down: test_bit(0, d as u64),
+            stp_state: stp_state,
+        }
+    }
+
+    fn to_int(ps: &PortState) -> u32 {
+        bit(0, 0, ps.down) as u32 | (ps.stp_state as u32) << 8
+    }
+}
+
 /// Features of physical ports available in a datapath.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PortFeatures {
     pub f_10mbhd: bool,
     pub f_10mbfd: bool,
@@ -1159,12 +1378,29 @@ impl PortFeatures {
             pause_asym: test_bit(11, d as u64),
         }
     }
+
+    fn to_int(pf: &PortFeatures) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, pf.f_10mbhd) as u32;
+        let ret = bit(1, ret as u64, pf.f_10mbfd) as u32;
+        let ret = bit(2, ret as u64, pf.f_100mbhd) as u32;
+        let ret = bit(3, ret as u64, pf.f_100mbfd) as u32;
+        let ret = bit(4, ret as u64, pf.f_1gbhd) as u32;
+        let ret = bit(5, ret as u64, pf.f_1gbfd) as u32;
+        let ret = bit(6, ret as u64, pf.f_10gbfd) as u32;
+        let ret = bit(7, ret as u64, pf.copper) as u32;
+        let ret = bit(8, ret as u64, pf.fiber) as u32;
+        let ret = bit(9, ret as u64, pf.autoneg) as u32;
+        let ret = bit(10, ret as u64, pf.pause) as u32;
+        bit(11, ret as u64, pf.pause_asym) as u32
+    }
 }
 
 /// Flags to indicate behavior of the physical port.
 ///
 /// These flags are used both to describe the current configuration of a physical port,
 /// and to configure a port's behavior.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PortConfig {
     pub down: bool,
     pub no_stp: bool,
@@ -1175,7 +1411,33 @@ pub struct PortConfig {
     pub no_packet_in: bool,
 }
 
+impl PortConfig {
+    fn of_int(d: u32) -> PortConfig {
+        PortConfig {
+            down: test_bit(0, d as u64),
+            no_stp: test_bit(1, d as u64),
+            no_recv: test_bit(2, d as u64),
+            no_recv_stp: test_bit(3, d as u64),
+            no_flood: test_bit(4, d as u64),
+            no_fwd: test_bit(5, d as u64),
+            no_packet_in: test_bit(6, d as u64),
+        }
+    }
+
+    fn to_int(pc: &PortConfig) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, pc.down) as u32;
+        let ret = bit(1, ret as u64, pc.no_stp) as u32;
+        let ret = bit(2, ret as u64, pc.no_recv) as u32;
+        let ret = bit(3, ret as u64, pc.no_recv_stp) as u32;
+        let ret = bit(4, ret as u64, pc.no_flood) as u32;
+        let ret = bit(5, ret as u64, pc.no_fwd) as u32;
+        bit(6, ret as u64, pc.no_packet_in) as u32
+    }
+}
+
 /// Description of a physical port.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PortDesc {
     pub port_no: u16,
     pub hw_addr: u64,
@@ -1196,60 +1458,23 @@ impl PortDesc {
         size_of::<OfpPhyPort>()
     }
 
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> PortDesc {
-        let port_no = bytes.read_u16::<BigEndian>().unwrap();
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortDesc, ParseError> {
+        let port_no = bytes.read_u16::<BigEndian>()?;
         let hw_addr = {
             let mut arr: [u8; 6] = [0; 6];
             for i in 0..6 {
-                arr[i] = bytes.read_u8().unwrap();
+                arr[i] = bytes.read_u8()?;
             }
             mac_of_bytes(arr)
         };
-        let name = {
-            let mut arr: [u8; 16] = [0; 16];
-            for i in 0..16 {
-                arr[i] = bytes.read_u8().unwrap();
-            }
-            String::from_utf8(arr.to_vec()).unwrap()
-        };
-        let config = {
-            let d = bytes.read_u32::<BigEndian>().unwrap();
-            PortConfig {
-                down: test_bit(0, d as u64),
-                no_stp: test_bit(1, d as u64),
-                no_recv: test_bit(2, d as u64),
-                no_recv_stp: test_bit(3, d as u64),
-                no_flood: test_bit(4, d as u64),
-                no_fwd: test_bit(5, d as u64),
-                no_packet_in: test_bit(6, d as u64),
-            }
-        };
-        let state = {
-            let d = bytes.read_u32::<BigEndian>().unwrap();
-            PortState {
-                down: test_bit(0, d as u64),
-                stp_state: {
-                    let mask: u32 = 3 << 8;
-                    let d_masked = d & mask;
-                    if d_masked == (StpState::Listen as u32) << 8 {
-                        StpState::Listen
-                    } else if d_masked == (StpState::Learn as u32) << 8 {
-                        StpState::Learn
-                    } else if d_masked == (StpState::Forward as u32) << 8 {
-                        StpState::Forward
-                    } else if d_masked == (StpState::Block as u32) << 8 {
-                        StpState::Block
-                    } else {
-                        panic!("Unexpected ofp_port_state for STP: {}", d_masked)
-                    }
-                },
-            }
-        };
-        let curr = PortFeatures::of_int(bytes.read_u32::<BigEndian>().unwrap());
-        let advertised = PortFeatures::of_int(bytes.read_u32::<BigEndian>().unwrap());
-        let supported = PortFeatures::of_int(bytes.read_u32::<BigEndian>().unwrap());
-        let peer = PortFeatures::of_int(bytes.read_u32::<BigEndian>().unwrap());
-        PortDesc {
+        let name = parse_fixed_string(bytes, 16)?;
+        let config = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let state = PortState::of_int(bytes.read_u32::<BigEndian>()?);
+        let curr = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let advertised = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let supported = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let peer = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        Ok(PortDesc {
             port_no: port_no,
             hw_addr: hw_addr,
             name: name,
@@ -1259,54 +1484,331 @@ impl PortDesc {
             advertised: advertised,
             supported: supported,
             peer: peer,
-        }
+        })
+    }
+
+    fn marshal(pd: PortDesc, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(pd.port_no).unwrap();
+        bytes.write_all(&bytes_of_mac(pd.hw_addr)).unwrap();
+        marshal_fixed_string(&pd.name, 16, bytes);
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pd.config)).unwrap();
+        bytes.write_u32::<BigEndian>(PortState::to_int(&pd.state)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.curr)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.advertised)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.supported)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.peer)).unwrap();
     }
 }
 
 /// What changed about a physical port.
 #[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PortReason {
     PortAdd,
     PortDelete,
     PortModify,
 }
 
+impl PortReason {
+    fn of_int(code: u8) -> Result<PortReason, ParseError> {
+        match code {
+            0 => Ok(PortReason::PortAdd),
+            1 => Ok(PortReason::PortDelete),
+            2 => Ok(PortReason::PortModify),
+            _ => Err(ParseError::UnknownValue("ofp_port_reason", code as u64)),
+        }
+    }
+}
+
 /// A physical port has changed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PortStatus {
     pub reason: PortReason,
     pub desc: PortDesc,
 }
 
+#[repr(packed)]
+struct OfpPortStatus(u8, [u8; 7]);
+
 impl MessageType for PortStatus {
-    fn size_of(_: &PortStatus) -> usize {
-        size_of::<PortReason>() + size_of::<OfpPhyPort>()
+    fn size_of(ps: &PortStatus) -> usize {
+        size_of::<OfpPortStatus>() + PortDesc::size_of(&ps.desc)
     }
 
-    fn parse(buf: &[u8]) -> PortStatus {
+    fn parse(buf: &[u8]) -> Result<PortStatus, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let reason = unsafe { transmute(bytes.read_u8().unwrap()) };
+        let reason = PortReason::of_int(bytes.read_u8()?)?;
         bytes.consume(7);
-        let desc = PortDesc::parse(&mut bytes);
-        PortStatus {
+        let desc = PortDesc::parse(&mut bytes)?;
+        Ok(PortStatus {
             reason: reason,
             desc: desc,
+        })
+    }
+
+    fn marshal(ps: PortStatus, bytes: &mut Vec<u8>) {
+        bytes.write_u8(ps.reason as u8).unwrap();
+        for _ in 0..7 {
+            bytes.write_u8(0).unwrap();
+        }
+        PortDesc::marshal(ps.desc, bytes);
+    }
+}
+
+/// Modifies the behavior of a physical port (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortMod {
+    pub port_no: u16,
+    /// Must match the hardware address of the port, as reported in its `PortDesc`.
+    pub hw_addr: u64,
+    pub config: PortConfig,
+    /// Flags of `config` to change. Flags not set in `mask` are left unchanged.
+    pub mask: PortConfig,
+    /// Features to advertise. All flags unset leaves the advertised features unchanged.
+    pub advertise: PortFeatures,
+}
+
+#[repr(packed)]
+struct OfpPortMod(u16, [u8; 6], u32, u32, u32, [u8; 4]);
+
+impl MessageType for PortMod {
+    fn size_of(_: &PortMod) -> usize {
+        size_of::<OfpPortMod>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<PortMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let port_no = bytes.read_u16::<BigEndian>()?;
+        let hw_addr = {
+            let mut arr: [u8; 6] = [0; 6];
+            bytes.read_exact(&mut arr)?;
+            mac_of_bytes(arr)
+        };
+        let config = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let mask = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let advertise = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        Ok(PortMod {
+            port_no: port_no,
+            hw_addr: hw_addr,
+            config: config,
+            mask: mask,
+            advertise: advertise,
+        })
+    }
+
+    fn marshal(pm: PortMod, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(pm.port_no).unwrap();
+        bytes.write_all(&bytes_of_mac(pm.hw_addr)).unwrap();
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pm.config)).unwrap();
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pm.mask)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pm.advertise)).unwrap();
+        bytes.write_u32::<BigEndian>(0).unwrap();
+    }
+}
+
+/// Property of a queue attached to a physical port.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueueProperty {
+    /// Minimum guaranteed rate, in 1/10 of a percent. Values above 1000 disable the guarantee.
+    MinRate(u16),
+    Unparsable(u16, Vec<u8>),
+}
+
+#[repr(packed)]
+struct OfpQueuePropHeader(u16, u16, [u8; 4]);
+#[repr(packed)]
+struct OfpQueuePropMinRate(u16, [u8; 6]);
+
+#[repr(u16)]
+enum OfpQueueProperties {
+    OFPQTMinRate = 1,
+}
+
+impl QueueProperty {
+    fn size_of(prop: &QueueProperty) -> usize {
+        let h = size_of::<OfpQueuePropHeader>();
+        let body = match *prop {
+            QueueProperty::MinRate(_) => size_of::<OfpQueuePropMinRate>(),
+            QueueProperty::Unparsable(_, ref buf) => buf.len(),
+        };
+        h + body
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<QueueProperty, ParseError> {
+        let property = bytes.read_u16::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(4);
+        if (len as usize) < size_of::<OfpQueuePropHeader>() {
+            return Err(ParseError::BadLength("ofp_queue_prop_header", len as usize));
+        }
+        let prop = match property {
+            t if t == (OfpQueueProperties::OFPQTMinRate as u16) => {
+                let rate = bytes.read_u16::<BigEndian>()?;
+                bytes.consume(6);
+                QueueProperty::MinRate(rate)
+            }
+            t => {
+                let mut body = vec![0; len as usize - size_of::<OfpQueuePropHeader>()];
+                bytes.read_exact(&mut body)?;
+                QueueProperty::Unparsable(t, body)
+            }
+        };
+        Ok(prop)
+    }
+
+    fn marshal(prop: QueueProperty, bytes: &mut Vec<u8>) {
+        let property = match prop {
+            QueueProperty::MinRate(_) => OfpQueueProperties::OFPQTMinRate as u16,
+            QueueProperty::Unparsable(t, _) => t,
+        };
+        bytes.write_u16::<BigEndian>(property).unwrap();
+        bytes.write_u16::<BigEndian>(QueueProperty::size_of(&prop) as u16).unwrap();
+        bytes.write_u32::<BigEndian>(0).unwrap();
+        match prop {
+            QueueProperty::MinRate(rate) => {
+                bytes.write_u16::<BigEndian>(rate).unwrap();
+                for _ in 0..6 {
+                    bytes.write_u8(0).unwrap();
+                }
+            }
+            QueueProperty::Unparsable(_, buf) => bytes.write_all(&buf).unwrap(),
+        }
+    }
+}
+
+/// A queue attached to a physical port, and its properties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketQueue {
+    pub queue_id: u32,
+    pub properties: Vec<QueueProperty>,
+}
+
+#[repr(packed)]
+struct OfpPacketQueue(u32, u16, [u8; 2]);
+
+impl PacketQueue {
+    fn size_of(pq: &PacketQueue) -> usize {
+        let props: usize = pq.properties.iter().map(QueueProperty::size_of).sum();
+        size_of::<OfpPacketQueue>() + props
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PacketQueue, ParseError> {
+        let queue_id = bytes.read_u32::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(2);
+        if (len as usize) < size_of::<OfpPacketQueue>() {
+            return Err(ParseError::BadLength("ofp_packet_queue", len as usize));
+        }
+        let mut props_buf = vec![0; len as usize - size_of::<OfpPacketQueue>()];
+        bytes.read_exact(&mut props_buf)?;
+        let mut props_bytes = Cursor::new(props_buf);
+        let mut properties = vec![];
+        while (props_bytes.position() as usize) < props_bytes.get_ref().len() {
+            properties.push(QueueProperty::parse(&mut props_bytes)?)
+        }
+        Ok(PacketQueue {
+            queue_id: queue_id,
+            properties: properties,
+        })
+    }
+
+    fn marshal(pq: PacketQueue, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(pq.queue_id).unwrap();
+        bytes.write_u16::<BigEndian>(PacketQueue::size_of(&pq) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        for prop in pq.properties {
+            QueueProperty::marshal(prop, bytes)
+        }
+    }
+}
+
+/// Request for the queues configured on physical port `port` (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueGetConfigReq {
+    pub port: u16,
+}
+
+#[repr(packed)]
+struct OfpQueueGetConfigRequest(u16, [u8; 2]);
+
+impl MessageType for QueueGetConfigReq {
+    fn size_of(_: &QueueGetConfigReq) -> usize {
+        size_of::<OfpQueueGetConfigRequest>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<QueueGetConfigReq, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        Ok(QueueGetConfigReq { port: bytes.read_u16::<BigEndian>()? })
+    }
+
+    fn marshal(req: QueueGetConfigReq, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(req.port).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+    }
+}
+
+/// Queues configured on physical port `port` (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueGetConfigResp {
+    pub port: u16,
+    pub queues: Vec<PacketQueue>,
+}
+
+#[repr(packed)]
+struct OfpQueueGetConfigReply(u16, [u8; 6]);
+
+impl MessageType for QueueGetConfigResp {
+    fn size_of(resp: &QueueGetConfigResp) -> usize {
+        let queues: usize = resp.queues.iter().map(PacketQueue::size_of).sum();
+        size_of::<OfpQueueGetConfigReply>() + queues
+    }
+
+    fn parse(buf: &[u8]) -> Result<QueueGetConfigResp, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let port = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(6);
+        let mut queues = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            queues.push(PacketQueue::parse(&mut bytes)?)
         }
+        Ok(QueueGetConfigResp {
+            port: port,
+            queues: queues,
+        })
     }
 
-    fn marshal(_: PortStatus, _: &mut Vec<u8>) {}
+    fn marshal(resp: QueueGetConfigResp, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(resp.port).unwrap();
+        for _ in 0..6 {
+            bytes.write_u8(0).unwrap();
+        }
+        for queue in resp.queues {
+            PacketQueue::marshal(queue, bytes)
+        }
+    }
 }
 
 /// Reason Hello failed.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum HelloFailed {
     Incompatible,
     EPerm,
 }
 
+impl HelloFailed {
+    fn of_int(code: u16) -> Result<HelloFailed, ParseError> {
+        match code {
+            0 => Ok(HelloFailed::Incompatible),
+            1 => Ok(HelloFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_hello_failed_code", code as u64)),
+        }
+    }
+}
+
 /// Reason the controller made a bad request to a switch.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum BadRequest {
     BadVersion,
     BadType,
@@ -1319,9 +1821,26 @@ pub enum BadRequest {
     BufferUnknown,
 }
 
+impl BadRequest {
+    fn of_int(code: u16) -> Result<BadRequest, ParseError> {
+        match code {
+            0 => Ok(BadRequest::BadVersion),
+            1 => Ok(BadRequest::BadType),
+            2 => Ok(BadRequest::BadStat),
+            3 => Ok(BadRequest::BadVendor),
+            4 => Ok(BadRequest::BadSubType),
+            5 => Ok(BadRequest::EPerm),
+            6 => Ok(BadRequest::BadLen),
+            7 => Ok(BadRequest::BufferEmpty),
+            8 => Ok(BadRequest::BufferUnknown),
+            _ => Err(ParseError::UnknownValue("ofp_bad_request_code", code as u64)),
+        }
+    }
+}
+
 /// Reason the controller action failed.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum BadAction {
     BadType,
     BadLen,
@@ -1334,9 +1853,26 @@ pub enum BadAction {
     BadQueue,
 }
 
+impl BadAction {
+    fn of_int(code: u16) -> Result<BadAction, ParseError> {
+        match code {
+            0 => Ok(BadAction::BadType),
+            1 => Ok(BadAction::BadLen),
+            2 => Ok(BadAction::BadVendor),
+            3 => Ok(BadAction::BadVendorType),
+            4 => Ok(BadAction::BadOutPort),
+            5 => Ok(BadAction::BadArgument),
+            6 => Ok(BadAction::EPerm),
+            7 => Ok(BadAction::TooMany),
+            8 => Ok(BadAction::BadQueue),
+            _ => Err(ParseError::UnknownValue("ofp_bad_action_code", code as u64)),
+        }
+    }
+}
+
 /// Reason a FlowMod from the controller failed.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FlowModFailed {
     AllTablesFull,
     Overlap,
@@ -1346,25 +1882,60 @@ pub enum FlowModFailed {
     Unsupported,
 }
 
+impl FlowModFailed {
+    fn of_int(code: u16) -> Result<FlowModFailed, ParseError> {
+        match code {
+            0 => Ok(FlowModFailed::AllTablesFull),
+            1 => Ok(FlowModFailed::Overlap),
+            2 => Ok(FlowModFailed::EPerm),
+            3 => Ok(FlowModFailed::BadEmergTimeout),
+            4 => Ok(FlowModFailed::BadCommand),
+            5 => Ok(FlowModFailed::Unsupported),
+            _ => Err(ParseError::UnknownValue("ofp_flow_mod_failed_code", code as u64)),
+        }
+    }
+}
+
 /// Reason a PortMod from the controller failed.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PortModFailed {
     BadPort,
     BadHwAddr,
 }
 
+impl PortModFailed {
+    fn of_int(code: u16) -> Result<PortModFailed, ParseError> {
+        match code {
+            0 => Ok(PortModFailed::BadPort),
+            1 => Ok(PortModFailed::BadHwAddr),
+            _ => Err(ParseError::UnknownValue("ofp_port_mod_failed_code", code as u64)),
+        }
+    }
+}
+
 /// Reason a queue operation from the controller failed.
 #[repr(u16)]
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum QueueOpFailed {
     BadPort,
     BadQueue,
     EPerm,
 }
 
+impl QueueOpFailed {
+    fn of_int(code: u16) -> Result<QueueOpFailed, ParseError> {
+        match code {
+            0 => Ok(QueueOpFailed::BadPort),
+            1 => Ok(QueueOpFailed::BadQueue),
+            2 => Ok(QueueOpFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_queue_op_failed_code", code as u64)),
+        }
+    }
+}
+
 /// High-level type of OpenFlow error
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ErrorType {
     HelloFailed(HelloFailed),
     BadRequest(BadRequest),
@@ -1375,7 +1946,7 @@ pub enum ErrorType {
 }
 
 /// Error message (datapath -> controller)
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Error {
     Error(ErrorType, Vec<u8>),
 }
@@ -1390,48 +1961,888 @@ impl MessageType for Error {
         }
     }
 
-    fn parse(buf: &[u8]) -> Error {
+    fn parse(buf: &[u8]) -> Result<Error, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
-        let error_type = bytes.read_u16::<BigEndian>().unwrap();
-        let error_code = bytes.read_u16::<BigEndian>().unwrap();
+        let error_type = bytes.read_u16::<BigEndian>()?;
+        let error_code = bytes.read_u16::<BigEndian>()?;
         let code = match error_type {
-            0 => ErrorType::HelloFailed(unsafe { transmute(error_code) }),
-            1 => ErrorType::BadRequest(unsafe { transmute(error_code) }),
-            2 => ErrorType::BadAction(unsafe { transmute(error_code) }),
-            3 => ErrorType::FlowModFailed(unsafe { transmute(error_code) }),
-            4 => ErrorType::PortModFailed(unsafe { transmute(error_code) }),
-            5 => ErrorType::QueueOpFailed(unsafe { transmute(error_code) }),
-            _ => panic!("bad ErrorType in Error {}", error_type),
+            0 => ErrorType::HelloFailed(HelloFailed::of_int(error_code)?),
+            1 => ErrorType::BadRequest(BadRequest::of_int(error_code)?),
+            2 => ErrorType::BadAction(BadAction::of_int(error_code)?),
+            3 => ErrorType::FlowModFailed(FlowModFailed::of_int(error_code)?),
+            4 => ErrorType::PortModFailed(PortModFailed::of_int(error_code)?),
+            5 => ErrorType::QueueOpFailed(QueueOpFailed::of_int(error_code)?),
+            _ => return Err(ParseError::UnknownValue("ofp_error_type", error_type as u64)),
+        };
+        Ok(Error::Error(code, bytes.fill_buf()?.to_vec()))
+    }
+
+    fn marshal(err: Error, bytes: &mut Vec<u8>) {
+        let Error::Error(code, body) = err;
+        let (error_type, error_code) = match code {
+            ErrorType::HelloFailed(c) => (0, c as u16),
+            ErrorType::BadRequest(c) => (1, c as u16),
+            ErrorType::BadAction(c) => (2, c as u16),
+            ErrorType::FlowModFailed(c) => (3, c as u16),
+            ErrorType::PortModFailed(c) => (4, c as u16),
+            ErrorType::QueueOpFailed(c) => (5, c as u16),
+        };
+        bytes.write_u16::<BigEndian>(error_type).unwrap();
+        bytes.write_u16::<BigEndian>(error_code).unwrap();
+        bytes.write_all(&body).unwrap();
+    }
+}
+
+/// Read a fixed-length, null-padded string field of `len` bytes.
+fn parse_fixed_string(bytes: &mut Cursor<Vec<u8>>, len: usize) -> Result<String, ParseError> {
+    let mut arr = vec![0; len];
+    bytes.read_exact(&mut arr)?;
+    let end = arr.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&arr[..end]).into_owned())
+}
+
+/// Write `s` as a fixed-length string field of `len` bytes, truncating or null-padding it.
+fn marshal_fixed_string(s: &str, len: usize, bytes: &mut Vec<u8>) {
+    let s = s.as_bytes();
+    let n = if s.len() < len { s.len() } else { len };
+    bytes.write_all(&s[..n]).unwrap();
+    for _ in n..len {
+        bytes.write_u8(0).unwrap();
+    }
+}
+
+/// Type of statistics carried in the body of a stats request or reply.
+#[repr(u16)]
+enum OfpStatsType {
+    OFPSTDesc,
+    OFPSTFlow,
+    OFPSTAggregate,
+    OFPSTTable,
+    OFPSTPort,
+    OFPSTQueue,
+    OFPSTVendor = 0xffff,
+}
+
+#[repr(packed)]
+struct OfpStatsMsg(u16, u16);
+
+/// Request for individual flow statistics from the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowStatsRequest {
+    pub pattern: Pattern,
+    pub table_id: u8,
+    pub out_port: Option<PseudoPort>,
+}
+
+#[repr(packed)]
+struct OfpFlowStatsRequest(u8, u8, u16);
+
+impl FlowStatsRequest {
+    fn size_of(fsr: &FlowStatsRequest) -> usize {
+        Pattern::size_of(&fsr.pattern) + size_of::<OfpFlowStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<FlowStatsRequest, ParseError> {
+        let pattern = Pattern::parse(bytes)?;
+        let table_id = bytes.read_u8()?;
+        bytes.consume(1);
+        let out_port = PseudoPort::of_int(bytes.read_u16::<BigEndian>()?)?;
+        Ok(FlowStatsRequest {
+            pattern: pattern,
+            table_id: table_id,
+            out_port: out_port,
+        })
+    }
+
+    fn marshal(fsr: FlowStatsRequest, bytes: &mut Vec<u8>) {
+        Pattern::marshal(fsr.pattern, bytes);
+        bytes.write_u8(fsr.table_id).unwrap();
+        bytes.write_u8(0).unwrap();
+        match fsr.out_port {
+            None => bytes.write_u16::<BigEndian>(OfpPort::OFPPNone as u16).unwrap(),
+            Some(x) => PseudoPort::marshal(x, bytes),
+        }
+    }
+}
+
+/// Statistics of an individual flow installed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowStats {
+    pub table_id: u8,
+    pub pattern: Pattern,
+    pub duration_sec: u32,
+    pub duration_nsec: u32,
+    pub priority: u16,
+    pub idle_timeout: Timeout,
+    pub hard_timeout: Timeout,
+    pub cookie: u64,
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub actions: Vec<Action>,
+}
+
+#[repr(packed)]
+struct OfpFlowStats(u16, u8, u8, u32, u32, u16, u16, u16, [u8; 6], u64, u64, u64);
+
+impl FlowStats {
+    fn size_of(fs: &FlowStats) -> usize {
+        Pattern::size_of(&fs.pattern) + size_of::<OfpFlowStats>() +
+        Action::size_of_sequence(&fs.actions)
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<FlowStats, ParseError> {
+        let length = bytes.read_u16::<BigEndian>()?;
+        let table_id = bytes.read_u8()?;
+        bytes.consume(1);
+        let pattern = Pattern::parse(bytes)?;
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        let priority = bytes.read_u16::<BigEndian>()?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        bytes.consume(6);
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let header_len = size_of::<OfpFlowStats>() + Pattern::size_of(&pattern);
+        if (length as usize) < header_len {
+            return Err(ParseError::BadLength("ofp_flow_stats", length as usize));
+        }
+        let mut actions_buf = vec![0; length as usize - header_len];
+        bytes.read_exact(&mut actions_buf)?;
+        let mut actions_bytes = Cursor::new(actions_buf);
+        let actions = Action::parse_sequence(&mut actions_bytes)?;
+        Ok(FlowStats {
+            table_id: table_id,
+            pattern: pattern,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+            priority: priority,
+            idle_timeout: idle,
+            hard_timeout: hard,
+            cookie: cookie,
+            packet_count: packet_count,
+            byte_count: byte_count,
+            actions: actions,
+        })
+    }
+
+    fn marshal(fs: FlowStats, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(FlowStats::size_of(&fs) as u16).unwrap();
+        bytes.write_u8(fs.table_id).unwrap();
+        bytes.write_u8(0).unwrap();
+        Pattern::marshal(fs.pattern, bytes);
+        bytes.write_u32::<BigEndian>(fs.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(fs.duration_nsec).unwrap();
+        bytes.write_u16::<BigEndian>(fs.priority).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fs.idle_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fs.hard_timeout)).unwrap();
+        for _ in 0..6 {
+            bytes.write_u8(0).unwrap();
+        }
+        bytes.write_u64::<BigEndian>(fs.cookie).unwrap();
+        bytes.write_u64::<BigEndian>(fs.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(fs.byte_count).unwrap();
+        for act in fs.actions {
+            Action::marshal(act, bytes)
+        }
+    }
+}
+
+/// Aggregate statistics of all flows matching an aggregate stats request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateStats {
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub flow_count: u32,
+}
+
+#[repr(packed)]
+struct OfpAggregateStatsReply(u64, u64, u32, [u8; 4]);
+
+impl AggregateStats {
+    fn size_of(_: &AggregateStats) -> usize {
+        size_of::<OfpAggregateStatsReply>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<AggregateStats, ParseError> {
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let flow_count = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(AggregateStats {
+            packet_count: packet_count,
+            byte_count: byte_count,
+            flow_count: flow_count,
+        })
+    }
+
+    fn marshal(agg: AggregateStats, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(agg.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(agg.byte_count).unwrap();
+        bytes.write_u32::<BigEndian>(agg.flow_count).unwrap();
+        bytes.write_u32::<BigEndian>(0).unwrap();
+    }
+}
+
+/// Statistics of a flow table in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableStats {
+    pub table_id: u8,
+    pub name: String,
+    /// Bitmap of `OFPFW_*` wildcards supported by the table.
+    pub wildcards: u32,
+    pub max_entries: u32,
+    pub active_count: u32,
+    pub lookup_count: u64,
+    pub matched_count: u64,
+}
+
+#[repr(packed)]
+struct OfpTableStats(u8, [u8; 3], [u8; 32], u32, u32, u32, u64, u64);
+
+impl TableStats {
+    fn size_of(_: &TableStats) -> usize {
+        size_of::<OfpTableStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<TableStats, ParseError> {
+        let table_id = bytes.read_u8()?;
+        bytes.consume(3);
+        let name = parse_fixed_string(bytes, 32)?;
+        let wildcards = bytes.read_u32::<BigEndian>()?;
+        let max_entries = bytes.read_u32::<BigEndian>()?;
+        let active_count = bytes.read_u32::<BigEndian>()?;
+        let lookup_count = bytes.read_u64::<BigEndian>()?;
+        let matched_count = bytes.read_u64::<BigEndian>()?;
+        Ok(TableStats {
+            table_id: table_id,
+            name: name,
+            wildcards: wildcards,
+            max_entries: max_entries,
+            active_count: active_count,
+            lookup_count: lookup_count,
+            matched_count: matched_count,
+        })
+    }
+
+    fn marshal(ts: TableStats, bytes: &mut Vec<u8>) {
+        bytes.write_u8(ts.table_id).unwrap();
+        for _ in 0..3 {
+            bytes.write_u8(0).unwrap();
+        }
+        marshal_fixed_string(&ts.name, 32, bytes);
+        bytes.write_u32::<BigEndian>(ts.wildcards).unwrap();
+        bytes.write_u32::<BigEndian>(ts.max_entries).unwrap();
+        bytes.write_u32::<BigEndian>(ts.active_count).unwrap();
+        bytes.write_u64::<BigEndian>(ts.lookup_count).unwrap();
+        bytes.write_u64::<BigEndian>(ts.matched_count).unwrap();
+    }
+}
+
+/// Request for statistics of a physical port, or of all ports if `port_no` is `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortStatsRequest {
+    pub port_no: Option<u16>,
+}
+
+#[repr(packed)]
+struct OfpPortStatsRequest(u16, [u8; 6]);
+
+impl PortStatsRequest {
+    fn size_of(_: &PortStatsRequest) -> usize {
+        size_of::<OfpPortStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortStatsRequest, ParseError> {
+        let port_no = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(6);
+        Ok(PortStatsRequest {
+            port_no: if port_no == OfpPort::OFPPNone as u16 {
+                None
+            } else {
+                Some(port_no)
+            },
+        })
+    }
+
+    fn marshal(psr: PortStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(psr.port_no.unwrap_or(OfpPort::OFPPNone as u16)).unwrap();
+        for _ in 0..6 {
+            bytes.write_u8(0).unwrap();
+        }
+    }
+}
+
+/// Statistics of a physical port in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortStats {
+    pub port_no: u16,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_frame_err: u64,
+    pub rx_over_err: u64,
+    pub rx_crc_err: u64,
+    pub collisions: u64,
+}
+
+#[repr(packed)]
+struct OfpPortStats(u16, [u8; 6], [u64; 12]);
+
+impl PortStats {
+    fn size_of(_: &PortStats) -> usize {
+        size_of::<OfpPortStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortStats, ParseError> {
+        let port_no = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(6);
+        Ok(PortStats {
+            port_no: port_no,
+            rx_packets: bytes.read_u64::<BigEndian>()?,
+            tx_packets: bytes.read_u64::<BigEndian>()?,
+            rx_bytes: bytes.read_u64::<BigEndian>()?,
+            tx_bytes: bytes.read_u64::<BigEndian>()?,
+            rx_dropped: bytes.read_u64::<BigEndian>()?,
+            tx_dropped: bytes.read_u64::<BigEndian>()?,
+            rx_errors: bytes.read_u64::<BigEndian>()?,
+            tx_errors: bytes.read_u64::<BigEndian>()?,
+            rx_frame_err: bytes.read_u64::<BigEndian>()?,
+            rx_over_err: bytes.read_u64::<BigEndian>()?,
+            rx_crc_err: bytes.read_u64::<BigEndian>()?,
+            collisions: bytes.read_u64::<BigEndian>()?,
+        })
+    }
+
+    fn marshal(ps: PortStats, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(ps.port_no).unwrap();
+        for _ in 0..6 {
+            bytes.write_u8(0).unwrap();
+        }
+        for count in &[ps.rx_packets,
+                       ps.tx_packets,
+                       ps.rx_bytes,
+                       ps.tx_bytes,
+                       ps.rx_dropped,
+                       ps.tx_dropped,
+                       ps.rx_errors,
+                       ps.tx_errors,
+                       ps.rx_frame_err,
+                       ps.rx_over_err,
+                       ps.rx_crc_err,
+                       ps.collisions] {
+            bytes.write_u64::<BigEndian>(*count).unwrap();
+        }
+    }
+}
+
+/// Description of the switch manufacturer, hardware, and software.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchDesc {
+    pub mfr_desc: String,
+    pub hw_desc: String,
+    pub sw_desc: String,
+    pub serial_num: String,
+    pub dp_desc: String,
+}
+
+#[repr(packed)]
+struct OfpDescStats([u8; 256], [u8; 256], [u8; 256], [u8; 32], [u8; 256]);
+
+impl SwitchDesc {
+    fn size_of(_: &SwitchDesc) -> usize {
+        size_of::<OfpDescStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<SwitchDesc, ParseError> {
+        let mfr_desc = parse_fixed_string(bytes, 256)?;
+        let hw_desc = parse_fixed_string(bytes, 256)?;
+        let sw_desc = parse_fixed_string(bytes, 256)?;
+        let serial_num = parse_fixed_string(bytes, 32)?;
+        let dp_desc = parse_fixed_string(bytes, 256)?;
+        Ok(SwitchDesc {
+            mfr_desc: mfr_desc,
+            hw_desc: hw_desc,
+            sw_desc: sw_desc,
+            serial_num: serial_num,
+            dp_desc: dp_desc,
+        })
+    }
+
+    fn marshal(desc: SwitchDesc, bytes: &mut Vec<u8>) {
+        marshal_fixed_string(&desc.mfr_desc, 256, bytes);
+        marshal_fixed_string(&desc.hw_desc, 256, bytes);
+        marshal_fixed_string(&desc.sw_desc, 256, bytes);
+        marshal_fixed_string(&desc.serial_num, 32, bytes);
+        marshal_fixed_string(&desc.dp_desc, 256, bytes);
+    }
+}
+
+/// Request for statistics of queue `queue_id` on port `port_no`. `None` requests all ports or
+/// all queues, respectively.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStatsRequest {
+    pub port_no: Option<u16>,
+    pub queue_id: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpQueueStatsRequest(u16, [u8; 2], u32);
+
+const OFPQ_ALL: u32 = 0xffffffff;
+
+impl QueueStatsRequest {
+    fn size_of(_: &QueueStatsRequest) -> usize {
+        size_of::<OfpQueueStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<QueueStatsRequest, ParseError> {
+        let port_no = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(2);
+        let queue_id = bytes.read_u32::<BigEndian>()?;
+        Ok(QueueStatsRequest {
+            port_no: if port_no == OfpPort::OFPPAll as u16 {
+                None
+            } else {
+                Some(port_no)
+            },
+            queue_id: if queue_id == OFPQ_ALL {
+                None
+            } else {
+                Some(queue_id)
+            },
+        })
+    }
+
+    fn marshal(qsr: QueueStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(qsr.port_no.unwrap_or(OfpPort::OFPPAll as u16)).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u32::<BigEndian>(qsr.queue_id.unwrap_or(OFPQ_ALL)).unwrap();
+    }
+}
+
+/// Statistics of a queue attached to a physical port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStats {
+    pub port_no: u16,
+    pub queue_id: u32,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+}
+
+#[repr(packed)]
+struct OfpQueueStats(u16, [u8; 2], u32, u64, u64, u64);
+
+impl QueueStats {
+    fn size_of(_: &QueueStats) -> usize {
+        size_of::<OfpQueueStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<QueueStats, ParseError> {
+        let port_no = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(2);
+        let queue_id = bytes.read_u32::<BigEndian>()?;
+        let tx_bytes = bytes.read_u64::<BigEndian>()?;
+        let tx_packets = bytes.read_u64::<BigEndian>()?;
+        let tx_errors = bytes.read_u64::<BigEndian>()?;
+        Ok(QueueStats {
+            port_no: port_no,
+            queue_id: queue_id,
+            tx_bytes: tx_bytes,
+            tx_packets: tx_packets,
+            tx_errors: tx_errors,
+        })
+    }
+
+    fn marshal(qs: QueueStats, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(qs.port_no).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u32::<BigEndian>(qs.queue_id).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_bytes).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_packets).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_errors).unwrap();
+    }
+}
+
+/// Statistics request (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatsReq {
+    Desc,
+    Flow(FlowStatsRequest),
+    /// Aggregate statistics of all flows matching the request, which shares the layout of a
+    /// flow statistics request.
+    Aggregate(FlowStatsRequest),
+    Table,
+    Port(PortStatsRequest),
+    Queue(QueueStatsRequest),
+    /// Vendor-defined statistics with the given vendor id and request body.
+    Vendor(u32, Vec<u8>),
+}
+
+impl StatsReq {
+    fn type_code(req: &StatsReq) -> OfpStatsType {
+        match *req {
+            StatsReq::Desc => OfpStatsType::OFPSTDesc,
+            StatsReq::Flow(_) => OfpStatsType::OFPSTFlow,
+            StatsReq::Aggregate(_) => OfpStatsType::OFPSTAggregate,
+            StatsReq::Table => OfpStatsType::OFPSTTable,
+            StatsReq::Port(_) => OfpStatsType::OFPSTPort,
+            StatsReq::Queue(_) => OfpStatsType::OFPSTQueue,
+            StatsReq::Vendor(_, _) => OfpStatsType::OFPSTVendor,
+        }
+    }
+}
+
+impl MessageType for StatsReq {
+    fn size_of(req: &StatsReq) -> usize {
+        let body = match *req {
+            StatsReq::Desc => 0,
+            StatsReq::Flow(ref fsr) |
+            StatsReq::Aggregate(ref fsr) => FlowStatsRequest::size_of(fsr),
+            StatsReq::Table => 0,
+            StatsReq::Port(ref psr) => PortStatsRequest::size_of(psr),
+            StatsReq::Queue(ref qsr) => QueueStatsRequest::size_of(qsr),
+            StatsReq::Vendor(_, ref body) => size_of::<u32>() + body.len(),
+        };
+        size_of::<OfpStatsMsg>() + body
+    }
+
+    fn parse(buf: &[u8]) -> Result<StatsReq, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let typ = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(2);
+        let req = match typ {
+            t if t == (OfpStatsType::OFPSTDesc as u16) => StatsReq::Desc,
+            t if t == (OfpStatsType::OFPSTFlow as u16) => {
+                StatsReq::Flow(FlowStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTAggregate as u16) => {
+                StatsReq::Aggregate(FlowStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTTable as u16) => StatsReq::Table,
+            t if t == (OfpStatsType::OFPSTPort as u16) => {
+                StatsReq::Port(PortStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTQueue as u16) => {
+                StatsReq::Queue(QueueStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTVendor as u16) => {
+                let vendor = bytes.read_u32::<BigEndian>()?;
+                StatsReq::Vendor(vendor, bytes.fill_buf()?.to_vec())
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_stats_types", t as u64)),
+        };
+        Ok(req)
+    }
+
+    fn marshal(req: StatsReq, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(StatsReq::type_code(&req) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        match req {
+            StatsReq::Desc => (),
+            StatsReq::Flow(fsr) |
+            StatsReq::Aggregate(fsr) => FlowStatsRequest::marshal(fsr, bytes),
+            StatsReq::Table => (),
+            StatsReq::Port(psr) => PortStatsRequest::marshal(psr, bytes),
+            StatsReq::Queue(qsr) => QueueStatsRequest::marshal(qsr, bytes),
+            StatsReq::Vendor(vendor, body) => {
+                bytes.write_u32::<BigEndian>(vendor).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// Body of a statistics reply, by type of statistics.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatsRespBody {
+    Desc(SwitchDesc),
+    Flow(Vec<FlowStats>),
+    Aggregate(AggregateStats),
+    Table(Vec<TableStats>),
+    Port(Vec<PortStats>),
+    Queue(Vec<QueueStats>),
+    /// Vendor-defined statistics with the given vendor id and reply body.
+    Vendor(u32, Vec<u8>),
+}
+
+/// Statistics reply (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsResp {
+    /// More replies to the same request follow this one.
+    pub more: bool,
+    pub body: StatsRespBody,
+}
+
+impl StatsResp {
+    fn type_code(resp: &StatsResp) -> OfpStatsType {
+        match resp.body {
+            StatsRespBody::Desc(_) => OfpStatsType::OFPSTDesc,
+            StatsRespBody::Flow(_) => OfpStatsType::OFPSTFlow,
+            StatsRespBody::Aggregate(_) => OfpStatsType::OFPSTAggregate,
+            StatsRespBody::Table(_) => OfpStatsType::OFPSTTable,
+            StatsRespBody::Port(_) => OfpStatsType::OFPSTPort,
+            StatsRespBody::Queue(_) => OfpStatsType::OFPSTQueue,
+            StatsRespBody::Vendor(_, _) => OfpStatsType::OFPSTVendor,
+        }
+    }
+}
+
+impl MessageType for StatsResp {
+    fn size_of(resp: &StatsResp) -> usize {
+        let body: usize = match resp.body {
+            StatsRespBody::Desc(ref desc) => SwitchDesc::size_of(desc),
+            StatsRespBody::Flow(ref flows) => flows.iter().map(FlowStats::size_of).sum(),
+            StatsRespBody::Aggregate(ref agg) => AggregateStats::size_of(agg),
+            StatsRespBody::Table(ref tables) => tables.iter().map(TableStats::size_of).sum(),
+            StatsRespBody::Port(ref ports) => ports.iter().map(PortStats::size_of).sum(),
+            StatsRespBody::Queue(ref queues) => queues.iter().map(QueueStats::size_of).sum(),
+            StatsRespBody::Vendor(_, ref body) => size_of::<u32>() + body.len(),
+        };
+        size_of::<OfpStatsMsg>() + body
+    }
+
+    fn parse(buf: &[u8]) -> Result<StatsResp, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let typ = bytes.read_u16::<BigEndian>()?;
+        let flags = bytes.read_u16::<BigEndian>()?;
+        let body = match typ {
+            t if t == (OfpStatsType::OFPSTDesc as u16) => {
+                StatsRespBody::Desc(SwitchDesc::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTFlow as u16) => {
+                let mut flows = vec![];
+                while (bytes.position() as usize) < bytes.get_ref().len() {
+                    flows.push(FlowStats::parse(&mut bytes)?)
+                }
+                StatsRespBody::Flow(flows)
+            }
+            t if t == (OfpStatsType::OFPSTAggregate as u16) => {
+                StatsRespBody::Aggregate(AggregateStats::parse(&mut bytes)?)
+            }
+            t if t == (OfpStatsType::OFPSTTable as u16) => {
+                let mut tables = vec![];
+                while (bytes.position() as usize) < bytes.get_ref().len() {
+                    tables.push(TableStats::parse(&mut bytes)?)
+                }
+                StatsRespBody::Table(tables)
+            }
+            t if t == (OfpStatsType::OFPSTPort as u16) => {
+                let mut ports = vec![];
+                while (bytes.position() as usize) < bytes.get_ref().len() {
+                    ports.push(PortStats::parse(&mut bytes)?)
+                }
+                StatsRespBody::Port(ports)
+            }
+            t if t == (OfpStatsType::OFPSTQueue as u16) => {
+                let mut queues = vec![];
+                while (bytes.position() as usize) < bytes.get_ref().len() {
+                    queues.push(QueueStats::parse(&mut bytes)?)
+                }
+                StatsRespBody::Queue(queues)
+            }
+            t if t == (OfpStatsType::OFPSTVendor as u16) => {
+                let vendor = bytes.read_u32::<BigEndian>()?;
+                StatsRespBody::Vendor(vendor, bytes.fill_buf()?.to_vec())
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_stats_types", t as u64)),
         };
-        Error::Error(code, bytes.fill_buf().unwrap().to_vec())
+        Ok(StatsResp {
+            more: test_bit(0, flags as u64),
+            body: body,
+        })
     }
 
-    fn marshal(_: Error, _: &mut Vec<u8>) {}
+    fn marshal(resp: StatsResp, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(StatsResp::type_code(&resp) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(bit(0, 0, resp.more) as u16).unwrap();
+        match resp.body {
+            StatsRespBody::Desc(desc) => SwitchDesc::marshal(desc, bytes),
+            StatsRespBody::Flow(flows) => {
+                for fs in flows {
+                    FlowStats::marshal(fs, bytes)
+                }
+            }
+            StatsRespBody::Aggregate(agg) => AggregateStats::marshal(agg, bytes),
+            StatsRespBody::Table(tables) => {
+                for ts in tables {
+                    TableStats::marshal(ts, bytes)
+                }
+            }
+            StatsRespBody::Port(ports) => {
+                for ps in ports {
+                    PortStats::marshal(ps, bytes)
+                }
+            }
+            StatsRespBody::Queue(queues) => {
+                for qs in queues {
+                    QueueStats::marshal(qs, bytes)
+                }
+            }
+            StatsRespBody::Vendor(vendor, body) => {
+                bytes.write_u32::<BigEndian>(vendor).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// Registry of application-defined codecs for vendor extensions.
+///
+/// Vendor messages, actions, and statistics are parsed into raw vendor ids and bodies by
+/// `message::Message`. Applications register a parser and marshaller for each vendor id they
+/// understand, and use the registry to move between raw bodies and their own types.
+/// `Message::parse_with` decodes every body a registered parser handles, and rejects messages
+/// carrying bodies it cannot parse.
+pub mod vendor {
+    use std::any::Any;
+    use std::collections::HashMap;
+
+    use parse_error::ParseError;
+    use super::{Action, StatsReq};
+
+    /// OpenFlow 1.0 extension points carrying vendor-defined bodies.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum VendorKind {
+        /// Body of `Message::Vendor`.
+        Message,
+        /// Body of `Action::Vendor`.
+        Action,
+        /// Body of `StatsReq::Vendor` and `StatsRespBody::Vendor`.
+        Stats,
+    }
+
+    /// Application-defined value parsed from a vendor-defined body.
+    pub type VendorValue = Box<dyn Any + Send>;
+
+    /// Vendor-defined body decoded by a registered parser: its extension point, vendor id, and
+    /// value.
+    pub type DecodedVendor = (VendorKind, u32, VendorValue);
+
+    /// Parses a vendor-defined body into an application-defined value.
+    pub type VendorParser = Box<dyn Fn(&[u8]) -> Result<VendorValue, ParseError> + Send + Sync>;
+
+    /// Marshals an application-defined value into a vendor-defined body. Returns `None` if
+    /// the value is not of the type the marshaller handles.
+    pub type VendorMarshaller = Box<dyn Fn(&dyn Any) -> Option<Vec<u8>> + Send + Sync>;
+
+    /// Parsers and marshallers for vendor extensions, keyed by extension point and vendor id.
+    pub struct VendorRegistry {
+        parsers: HashMap<(VendorKind, u32), VendorParser>,
+        marshallers: HashMap<(VendorKind, u32), VendorMarshaller>,
+    }
+
+    impl VendorRegistry {
+        /// Create an empty `VendorRegistry`.
+        pub fn new() -> VendorRegistry {
+            VendorRegistry {
+                parsers: HashMap::new(),
+                marshallers: HashMap::new(),
+            }
+        }
+
+        /// Register `parser` and `marshaller` for bodies of `kind` with vendor id `vendor_id`,
+        /// replacing any previously registered for the same pair.
+        pub fn register(&mut self,
+                        kind: VendorKind,
+                        vendor_id: u32,
+                        parser: VendorParser,
+                        marshaller: VendorMarshaller) {
+            self.parsers.insert((kind, vendor_id), parser);
+            self.marshallers.insert((kind, vendor_id), marshaller);
+        }
+
+        /// Whether a codec is registered for bodies of `kind` with vendor id `vendor_id`.
+        pub fn is_registered(&self, kind: VendorKind, vendor_id: u32) -> bool {
+            self.parsers.contains_key(&(kind, vendor_id))
+        }
+
+        /// Parse `body` with the parser registered for `kind` and `vendor_id`, if any.
+        pub fn parse(&self,
+                     kind: VendorKind,
+                     vendor_id: u32,
+                     body: &[u8])
+                     -> Option<Result<VendorValue, ParseError>> {
+            self.parsers.get(&(kind, vendor_id)).map(|parser| parser(body))
+        }
+
+        /// Marshal `value` with the marshaller registered for `kind` and `vendor_id`. Returns
+        /// `None` if no marshaller is registered, or it does not handle the type of `value`.
+        pub fn marshal(&self,
+                       kind: VendorKind,
+                       vendor_id: u32,
+                       value: &dyn Any)
+                       -> Option<Vec<u8>> {
+            match self.marshallers.get(&(kind, vendor_id)) {
+                Some(marshaller) => marshaller(value),
+                None => None,
+            }
+        }
+
+        /// Return the vendor action with vendor id `vendor_id` carrying `value`, marshaled as
+        /// `marshal` does.
+        pub fn action(&self, vendor_id: u32, value: &dyn Any) -> Option<Action> {
+            self.marshal(VendorKind::Action, vendor_id, value)
+                .map(|body| Action::Vendor(vendor_id, body))
+        }
+
+        /// Return the vendor statistics request with vendor id `vendor_id` carrying `value`,
+        /// marshaled as `marshal` does.
+        pub fn stats_request(&self, vendor_id: u32, value: &dyn Any) -> Option<StatsReq> {
+            self.marshal(VendorKind::Stats, vendor_id, value)
+                .map(|body| StatsReq::Vendor(vendor_id, body))
+        }
+    }
+
+    impl Default for VendorRegistry {
+        fn default() -> VendorRegistry {
+            VendorRegistry::new()
+        }
+    }
 }
 
 /// Encapsulates handling of messages implementing `MessageType` trait.
 pub mod message {
     use super::*;
+    use super::vendor::{DecodedVendor, VendorKind, VendorRegistry};
     use std::io::Write;
     use ofp_header::OfpHeader;
     use ofp_message::OfpMessage;
     use packet::Packet;
 
     /// Abstractions of OpenFlow 1.0 messages mapping to message codes.
+    #[derive(Clone, Debug, PartialEq)]
     pub enum Message {
         Hello,
         Error(Error),
         EchoRequest(Vec<u8>),
         EchoReply(Vec<u8>),
+        /// Vendor-defined message with the given vendor id and body.
+        Vendor { vendor_id: u32, body: Vec<u8> },
         FeaturesReq,
         FeaturesReply(SwitchFeatures),
+        GetConfigRequest,
+        GetConfigReply(SwitchConfig),
+        SetConfig(SwitchConfig),
         FlowMod(FlowMod),
+        PortMod(PortMod),
         PacketIn(PacketIn),
         FlowRemoved(FlowRemoved),
         PortStatus(PortStatus),
         PacketOut(PacketOut),
+        StatsRequest(StatsReq),
+        StatsReply(StatsResp),
         BarrierRequest,
         BarrierReply,
+        QueueGetConfigRequest(QueueGetConfigReq),
+        QueueGetConfigReply(QueueGetConfigResp),
     }
 
     impl Message {
@@ -1442,15 +2853,24 @@ pub mod message {
                 Message::Error(_) => MsgCode::Error,
                 Message::EchoRequest(_) => MsgCode::EchoReq,
                 Message::EchoReply(_) => MsgCode::EchoResp,
+                Message::Vendor { .. } => MsgCode::Vendor,
                 Message::FeaturesReq => MsgCode::FeaturesReq,
                 Message::FeaturesReply(_) => MsgCode::FeaturesResp,
+                Message::GetConfigRequest => MsgCode::GetConfigReq,
+                Message::GetConfigReply(_) => MsgCode::GetConfigResp,
+                Message::SetConfig(_) => MsgCode::SetConfig,
                 Message::FlowMod(_) => MsgCode::FlowMod,
+                Message::PortMod(_) => MsgCode::PortMod,
                 Message::PacketIn(_) => MsgCode::PacketIn,
                 Message::FlowRemoved(_) => MsgCode::FlowRemoved,
                 Message::PortStatus(_) => MsgCode::PortStatus,
                 Message::PacketOut(_) => MsgCode::PacketOut,
+                Message::StatsRequest(_) => MsgCode::StatsReq,
+                Message::StatsReply(_) => MsgCode::StatsResp,
                 Message::BarrierRequest => MsgCode::BarrierReq,
                 Message::BarrierReply => MsgCode::BarrierResp,
+                Message::QueueGetConfigRequest(_) => MsgCode::QueueGetConfigReq,
+                Message::QueueGetConfigReply(_) => MsgCode::QueueGetConfigResp,
             }
         }
 
@@ -1461,14 +2881,26 @@ pub mod message {
                 Message::Error(buf) => Error::marshal(buf, bytes),
                 Message::EchoReply(buf) => bytes.write_all(&buf).unwrap(),
                 Message::EchoRequest(buf) => bytes.write_all(&buf).unwrap(),
+                Message::Vendor { vendor_id, body } => {
+                    bytes.write_u32::<BigEndian>(vendor_id).unwrap();
+                    bytes.write_all(&body).unwrap()
+                }
                 Message::FeaturesReq => (),
+                Message::FeaturesReply(feats) => SwitchFeatures::marshal(feats, bytes),
+                Message::GetConfigRequest => (),
+                Message::GetConfigReply(config) |
+                Message::SetConfig(config) => SwitchConfig::marshal(config, bytes),
                 Message::FlowMod(flow_mod) => FlowMod::marshal(flow_mod, bytes),
+                Message::PortMod(port_mod) => PortMod::marshal(port_mod, bytes),
                 Message::PacketIn(packet_in) => PacketIn::marshal(packet_in, bytes),
                 Message::FlowRemoved(flow) => FlowRemoved::marshal(flow, bytes),
                 Message::PortStatus(sts) => PortStatus::marshal(sts, bytes),
                 Message::PacketOut(po) => PacketOut::marshal(po, bytes),
+                Message::StatsRequest(req) => StatsReq::marshal(req, bytes),
+                Message::StatsReply(resp) => StatsResp::marshal(resp, bytes),
                 Message::BarrierRequest | Message::BarrierReply => (),
-                _ => (),
+                Message::QueueGetConfigRequest(req) => QueueGetConfigReq::marshal(req, bytes),
+                Message::QueueGetConfigReply(resp) => QueueGetConfigResp::marshal(resp, bytes),
             }
         }
     }
@@ -1477,19 +2909,38 @@ pub mod message {
         fn size_of(msg: &Message) -> usize {
             match *msg {
                 Message::Hello => OfpHeader::size(),
-                Message::Error(ref err) => Error::size_of(err),
+                Message::Error(ref err) => OfpHeader::size() + Error::size_of(err),
                 Message::EchoRequest(ref buf) => OfpHeader::size() + buf.len(),
                 Message::EchoReply(ref buf) => OfpHeader::size() + buf.len(),
+                Message::Vendor { ref body, .. } => {
+                    OfpHeader::size() + size_of::<u32>() + body.len()
+                }
                 Message::FeaturesReq => OfpHeader::size(),
+                Message::FeaturesReply(ref feats) => {
+                    OfpHeader::size() + SwitchFeatures::size_of(feats)
+                }
+                Message::GetConfigRequest => OfpHeader::size(),
+                Message::GetConfigReply(ref config) |
+                Message::SetConfig(ref config) => {
+                    OfpHeader::size() + SwitchConfig::size_of(config)
+                }
                 Message::FlowMod(ref flow_mod) => OfpHeader::size() + FlowMod::size_of(flow_mod),
+                Message::PortMod(ref port_mod) => OfpHeader::size() + PortMod::size_of(port_mod),
                 Message::PacketIn(ref packet_in) => {
                     OfpHeader::size() + PacketIn::size_of(packet_in)
                 }
                 Message::FlowRemoved(ref flow) => OfpHeader::size() + FlowRemoved::size_of(flow),
                 Message::PortStatus(ref ps) => OfpHeader::size() + PortStatus::size_of(ps),
                 Message::PacketOut(ref po) => OfpHeader::size() + PacketOut::size_of(po),
+                Message::StatsRequest(ref req) => OfpHeader::size() + StatsReq::size_of(req),
+                Message::StatsReply(ref resp) => OfpHeader::size() + StatsResp::size_of(resp),
                 Message::BarrierRequest | Message::BarrierReply => OfpHeader::size(),
-                _ => 0,
+                Message::QueueGetConfigRequest(ref req) => {
+                    OfpHeader::size() + QueueGetConfigReq::size_of(req)
+                }
+                Message::QueueGetConfigReply(ref resp) => {
+                    OfpHeader::size() + QueueGetConfigResp::size_of(resp)
+                }
             }
         }
 
@@ -1501,57 +2952,135 @@ pub mod message {
                            xid)
         }
 
-        fn marshal(xid: u32, msg: Message) -> Vec<u8> {
+        fn marshal(xid: u32, msg: Message) -> Result<Vec<u8>, MarshalError> {
+            match msg {
+                Message::FlowMod(ref flow_mod) => FlowMod::check(flow_mod)?,
+                Message::PacketOut(ref pkt) => Action::check_sequence(&pkt.apply_actions)?,
+                Message::StatsReply(StatsResp { body: StatsRespBody::Flow(ref flows), .. }) => {
+                    for flow in flows {
+                        Action::check_sequence(&flow.actions)?
+                    }
+                }
+                _ => (),
+            }
             let hdr = Self::header_of(xid, &msg);
             let mut bytes = vec![];
             OfpHeader::marshal(&mut bytes, hdr);
             Message::marshal_body(msg, &mut bytes);
-            bytes
+            Ok(bytes)
         }
 
-        fn parse(header: &OfpHeader, buf: &[u8]) -> (u32, Message) {
-            let typ = header.type_code();
+        fn parse(header: &OfpHeader, buf: &[u8]) -> Result<(u32, Message), ParseError> {
+            let typ = match header.type_code() {
+                Ok(typ) => typ,
+                Err(typ) => return Err(ParseError::UnsupportedType("ofp_type", typ as u64)),
+            };
             let msg = match typ {
-                MsgCode::Hello => {
-                    println!("Hello!");
-                    Message::Hello
-                }
-                MsgCode::Error => {
-                    println!("Error");
-                    Message::Error(Error::parse(buf))
-                }
+                MsgCode::Hello => Message::Hello,
+                MsgCode::Error => Message::Error(Error::parse(buf)?),
                 MsgCode::EchoReq => Message::EchoRequest(buf.to_vec()),
                 MsgCode::EchoResp => Message::EchoReply(buf.to_vec()),
-                MsgCode::FeaturesResp => {
-                    println!("FeaturesResp");
-                    Message::FeaturesReply(SwitchFeatures::parse(buf))
+                MsgCode::FeaturesReq => Message::FeaturesReq,
+                MsgCode::Vendor => {
+                    let mut bytes = Cursor::new(buf.to_vec());
+                    let vendor_id = bytes.read_u32::<BigEndian>()?;
+                    Message::Vendor {
+                        vendor_id: vendor_id,
+                        body: bytes.fill_buf()?.to_vec(),
+                    }
+                }
+                MsgCode::FeaturesResp => Message::FeaturesReply(SwitchFeatures::parse(buf)?),
+                MsgCode::GetConfigReq => Message::GetConfigRequest,
+                MsgCode::GetConfigResp => Message::GetConfigReply(SwitchConfig::parse(buf)?),
+                MsgCode::SetConfig => Message::SetConfig(SwitchConfig::parse(buf)?),
+                MsgCode::FlowMod => Message::FlowMod(FlowMod::parse(buf)?),
+                MsgCode::PortMod => Message::PortMod(PortMod::parse(buf)?),
+                MsgCode::PacketIn => Message::PacketIn(PacketIn::parse(buf)?),
+                MsgCode::FlowRemoved => Message::FlowRemoved(FlowRemoved::parse(buf)?),
+                MsgCode::PortStatus => Message::PortStatus(PortStatus::parse(buf)?),
+                MsgCode::PacketOut => Message::PacketOut(PacketOut::parse(buf)?),
+                MsgCode::StatsReq => Message::StatsRequest(StatsReq::parse(buf)?),
+                MsgCode::StatsResp => Message::StatsReply(StatsResp::parse(buf)?),
+                MsgCode::BarrierReq => Message::BarrierRequest,
+                MsgCode::BarrierResp => Message::BarrierReply,
+                MsgCode::QueueGetConfigReq => {
+                    Message::QueueGetConfigRequest(QueueGetConfigReq::parse(buf)?)
                 }
-                MsgCode::FlowMod => {
-                    println!("FlowMod");
-                    Message::FlowMod(FlowMod::parse(buf))
+                MsgCode::QueueGetConfigResp => {
+                    Message::QueueGetConfigReply(QueueGetConfigResp::parse(buf)?)
                 }
-                MsgCode::PacketIn => {
-                    println!("PacketIn");
-                    Message::PacketIn(PacketIn::parse(buf))
+            };
+            Ok((header.xid(), msg))
+        }
+    }
+
+    impl Message {
+        /// Parse the message with header `header` and body `buf` as `OfpMessage::parse` does,
+        /// additionally decoding every vendor message, action, and statistics body for which
+        /// `registry` has a parser. Decoded bodies are returned in the order they appear in the
+        /// message, which is left holding the raw bodies.
+        pub fn parse_with(registry: &VendorRegistry,
+                          header: &OfpHeader,
+                          buf: &[u8])
+                          -> Result<(u32, Message, Vec<DecodedVendor>), ParseError> {
+            let (xid, msg) = Message::parse(header, buf)?;
+            let mut values = vec![];
+            match msg {
+                Message::Vendor { vendor_id, ref body } => {
+                    decode(registry, VendorKind::Message, vendor_id, body, &mut values)?
                 }
-                MsgCode::FlowRemoved => {
-                    println!("FlowRemoved");
-                    Message::FlowRemoved(FlowRemoved::parse(buf))
+                Message::FlowMod(ref flow_mod) => {
+                    decode_actions(registry, &flow_mod.actions, &mut values)?
                 }
-                MsgCode::PortStatus => {
-                    println!("PortStatus");
-                    Message::PortStatus(PortStatus::parse(buf))
+                Message::PacketOut(ref pkt) => {
+                    decode_actions(registry, &pkt.apply_actions, &mut values)?
                 }
-                MsgCode::PacketOut => {
-                    println!("PacketOut");
-                    Message::PacketOut(PacketOut::parse(buf))
+                Message::StatsRequest(StatsReq::Vendor(vendor_id, ref body)) => {
+                    decode(registry, VendorKind::Stats, vendor_id, body, &mut values)?
                 }
-                MsgCode::BarrierReq => Message::BarrierRequest,
-                MsgCode::BarrierResp => Message::BarrierReply,
-                code => panic!("Unexpected message type {:?}", code),
-            };
-            (header.xid(), msg)
+                Message::StatsReply(ref stats) => {
+                    match stats.body {
+                        StatsRespBody::Flow(ref flows) => {
+                            for flow in flows {
+                                decode_actions(registry, &flow.actions, &mut values)?
+                            }
+                        }
+                        StatsRespBody::Vendor(vendor_id, ref body) => {
+                            decode(registry, VendorKind::Stats, vendor_id, body, &mut values)?
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+            Ok((xid, msg, values))
+        }
+    }
+
+    /// Append `body` to `values` decoded with the parser `registry` has for `kind` and
+    /// `vendor_id`, if any.
+    fn decode(registry: &VendorRegistry,
+              kind: VendorKind,
+              vendor_id: u32,
+              body: &[u8],
+              values: &mut Vec<DecodedVendor>)
+              -> Result<(), ParseError> {
+        if let Some(value) = registry.parse(kind, vendor_id, body) {
+            values.push((kind, vendor_id, value?))
+        }
+        Ok(())
+    }
+
+    fn decode_actions(registry: &VendorRegistry,
+                      actions: &[Action],
+                      values: &mut Vec<DecodedVendor>)
+                      -> Result<(), ParseError> {
+        for action in actions {
+            if let Action::Vendor(vendor_id, ref body) = *action {
+                decode(registry, VendorKind::Action, vendor_id, body, values)?
+            }
         }
+        Ok(())
     }
 
     /// Return a `FlowMod` adding a flow parameterized by the given `priority`, `pattern`,
@@ -1573,7 +3102,7 @@ pub mod message {
     }
 
     /// Parse a payload buffer into a network level packet.
-    pub fn parse_payload(p: &Payload) -> Packet {
+    pub fn parse_payload(p: &Payload) -> Result<Packet, ParseError> {
         match *p {
             Payload::Buffered(_, ref b) |
             Payload::NotBuffered(ref b) => Packet::parse(&b),
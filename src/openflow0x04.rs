@@ -0,0 +1,4281 @@
+use std::io::{BufRead, Cursor, Read, Write};
+use std::mem::size_of;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use bits::*;
+use packet::{bytes_of_mac, mac_of_bytes};
+use parse_error::ParseError;
+
+pub use openflow0x01::{FragHandling, Mask, MessageType, Payload, SwitchConfig};
+
+/// OpenFlow 1.3 message type codes, used by headers to identify meaning of the rest of a message.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MsgCode {
+    Hello,
+    Error,
+    EchoReq,
+    EchoResp,
+    Experimenter,
+    FeaturesReq,
+    FeaturesResp,
+    GetConfigReq,
+    GetConfigResp,
+    SetConfig,
+    PacketIn,
+    FlowRemoved,
+    PortStatus,
+    PacketOut,
+    FlowMod,
+    GroupMod,
+    PortMod,
+    TableMod,
+    MultipartReq,
+    MultipartResp,
+    BarrierReq,
+    BarrierResp,
+    QueueGetConfigReq,
+    QueueGetConfigResp,
+    RoleReq,
+    RoleResp,
+    GetAsyncReq,
+    GetAsyncResp,
+    SetAsync,
+    MeterMod,
+}
+
+impl MsgCode {
+    /// Return the `MsgCode` with type byte `typ`, or `None` if `typ` is not an OpenFlow 1.3
+    /// message type.
+    pub fn of_int(typ: u8) -> Option<MsgCode> {
+        match typ {
+            0 => Some(MsgCode::Hello),
+            1 => Some(MsgCode::Error),
+            2 => Some(MsgCode::EchoReq),
+            3 => Some(MsgCode::EchoResp),
+            4 => Some(MsgCode::Experimenter),
+            5 => Some(MsgCode::FeaturesReq),
+            6 => Some(MsgCode::FeaturesResp),
+            7 => Some(MsgCode::GetConfigReq),
+            8 => Some(MsgCode::GetConfigResp),
+            9 => Some(MsgCode::SetConfig),
+            10 => Some(MsgCode::PacketIn),
+            11 => Some(MsgCode::FlowRemoved),
+            12 => Some(MsgCode::PortStatus),
+            13 => Some(MsgCode::PacketOut),
+            14 => Some(MsgCode::FlowMod),
+            15 => Some(MsgCode::GroupMod),
+            16 => Some(MsgCode::PortMod),
+            17 => Some(MsgCode::TableMod),
+            18 => Some(MsgCode::MultipartReq),
+            19 => Some(MsgCode::MultipartResp),
+            20 => Some(MsgCode::BarrierReq),
+            21 => Some(MsgCode::BarrierResp),
+            22 => Some(MsgCode::QueueGetConfigReq),
+            23 => Some(MsgCode::QueueGetConfigResp),
+            24 => Some(MsgCode::RoleReq),
+            25 => Some(MsgCode::RoleResp),
+            26 => Some(MsgCode::GetAsyncReq),
+            27 => Some(MsgCode::GetAsyncResp),
+            28 => Some(MsgCode::SetAsync),
+            29 => Some(MsgCode::MeterMod),
+            _ => None,
+        }
+    }
+}
+
+/// Return `len` rounded up to a multiple of 8 bytes, the alignment of most OpenFlow 1.3
+/// structures.
+fn padded(len: usize) -> usize {
+    len.div_ceil(8) * 8
+}
+
+/// Write `n` bytes of zero padding.
+fn write_pad(n: usize, bytes: &mut Vec<u8>) {
+    for _ in 0..n {
+        bytes.write_u8(0).unwrap();
+    }
+}
+
+/// Read a big-endian unsigned integer `width` bytes wide.
+fn read_uint(bytes: &mut Cursor<Vec<u8>>, width: usize) -> Result<u128, ParseError> {
+    let mut v = 0u128;
+    for _ in 0..width {
+        v = v << 8 | bytes.read_u8()? as u128;
+    }
+    Ok(v)
+}
+
+/// Write the low `width` bytes of `v` as a big-endian unsigned integer.
+fn write_uint(v: u128, width: usize, bytes: &mut Vec<u8>) {
+    for i in (0..width).rev() {
+        bytes.write_u8((v >> (8 * i)) as u8).unwrap();
+    }
+}
+
+/// Read `len` bytes into a cursor of their own, so that a variable-length structure can be
+/// parsed without running into whatever follows it.
+fn sub_cursor(bytes: &mut Cursor<Vec<u8>>, len: usize) -> Result<Cursor<Vec<u8>>, ParseError> {
+    let mut buf = vec![0; len];
+    bytes.read_exact(&mut buf)?;
+    Ok(Cursor::new(buf))
+}
+
+/// Read a fixed-length, null-padded string field of `len` bytes.
+fn parse_fixed_string(bytes: &mut Cursor<Vec<u8>>, len: usize) -> Result<String, ParseError> {
+    let mut arr = vec![0; len];
+    bytes.read_exact(&mut arr)?;
+    let end = arr.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&arr[..end]).into_owned())
+}
+
+/// Write `s` as a fixed-length string field of `len` bytes, truncating or null-padding it.
+fn marshal_fixed_string(s: &str, len: usize, bytes: &mut Vec<u8>) {
+    let s = s.as_bytes();
+    let n = if s.len() < len { s.len() } else { len };
+    bytes.write_all(&s[..n]).unwrap();
+    write_pad(len - n, bytes);
+}
+
+/// OXM class of the match fields defined by the OpenFlow specification.
+const OFPXMC_OPENFLOW_BASIC: u16 = 0x8000;
+
+/// OpenFlow Extensible Match (OXM) field.
+///
+/// Fields carrying a `Mask` may be wildcarded bitwise; a `None` mask matches the value exactly.
+/// MAC addresses are held in the low 48 bits of a `u64`, as in `openflow0x01`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Oxm {
+    InPort(u32),
+    InPhyPort(u32),
+    Metadata(Mask<u64>),
+    EthDst(Mask<u64>),
+    EthSrc(Mask<u64>),
+    EthType(u16),
+    /// VLAN id, with the `OFPVID_PRESENT` bit (0x1000) set to match tagged packets.
+    VlanVid(Mask<u16>),
+    VlanPcp(u8),
+    IpDscp(u8),
+    IpEcn(u8),
+    IpProto(u8),
+    Ipv4Src(Mask<u32>),
+    Ipv4Dst(Mask<u32>),
+    TcpSrc(u16),
+    TcpDst(u16),
+    UdpSrc(u16),
+    UdpDst(u16),
+    SctpSrc(u16),
+    SctpDst(u16),
+    Icmpv4Type(u8),
+    Icmpv4Code(u8),
+    ArpOp(u16),
+    ArpSpa(Mask<u32>),
+    ArpTpa(Mask<u32>),
+    ArpSha(Mask<u64>),
+    ArpTha(Mask<u64>),
+    Ipv6Src(Mask<u128>),
+    Ipv6Dst(Mask<u128>),
+    Ipv6Flabel(Mask<u32>),
+    Icmpv6Type(u8),
+    Icmpv6Code(u8),
+    Ipv6NdTarget(u128),
+    Ipv6NdSll(u64),
+    Ipv6NdTll(u64),
+    MplsLabel(u32),
+    MplsTc(u8),
+    MplsBos(u8),
+    /// PBB I-SID, in the low 24 bits.
+    PbbIsid(Mask<u32>),
+    TunnelId(Mask<u64>),
+    Ipv6Exthdr(Mask<u16>),
+    /// Field of a class other than OpenFlow basic, or a basic field unknown to this crate,
+    /// with its payload (value, followed by mask if `hasmask` is set) left unparsed.
+    Other {
+        class: u16,
+        field: u8,
+        hasmask: bool,
+        body: Vec<u8>,
+    },
+}
+
+#[repr(packed)]
+struct OfpOxmHeader(u16, u8, u8);
+
+impl Oxm {
+    /// Return the value width in bytes of OpenFlow basic field `field`, and whether the field
+    /// may be masked, or `None` if the field is unknown.
+    fn basic_width(field: u8) -> Option<(usize, bool)> {
+        let width = match field {
+            0 | 1 => (4, false),
+            2 => (8, true),
+            3 | 4 => (6, true),
+            5 => (2, false),
+            6 => (2, true),
+            7..=10 => (1, false),
+            11 | 12 => (4, true),
+            13..=18 => (2, false),
+            19 | 20 => (1, false),
+            21 => (2, false),
+            22 | 23 => (4, true),
+            24 | 25 => (6, true),
+            26 | 27 => (16, true),
+            28 => (4, true),
+            29 | 30 => (1, false),
+            31 => (16, false),
+            32 | 33 => (6, false),
+            34 => (4, false),
+            35 | 36 => (1, false),
+            37 => (3, true),
+            38 => (8, true),
+            39 => (2, true),
+            _ => return None,
+        };
+        Some(width)
+    }
+
+    /// Build the OpenFlow basic field `field`, known to `basic_width`, from its value and mask.
+    fn of_basic(field: u8, value: u128, mask: Option<u128>) -> Oxm {
+        let m16 = |value: u128, mask: Option<u128>| {
+            Mask {
+                value: value as u16,
+                mask: mask.map(|m| m as u16),
+            }
+        };
+        let m32 = |value: u128, mask: Option<u128>| {
+            Mask {
+                value: value as u32,
+                mask: mask.map(|m| m as u32),
+            }
+        };
+        let m64 = |value: u128, mask: Option<u128>| {
+            Mask {
+                value: value as u64,
+                mask: mask.map(|m| m as u64),
+            }
+        };
+        let m128 = |value: u128, mask: Option<u128>| {
+            Mask {
+                value: value,
+                mask: mask,
+            }
+        };
+        match field {
+            0 => Oxm::InPort(value as u32),
+            1 => Oxm::InPhyPort(value as u32),
+            2 => Oxm::Metadata(m64(value, mask)),
+            3 => Oxm::EthDst(m64(value, mask)),
+            4 => Oxm::EthSrc(m64(value, mask)),
+            5 => Oxm::EthType(value as u16),
+            6 => Oxm::VlanVid(m16(value, mask)),
+            7 => Oxm::VlanPcp(value as u8),
+            8 => Oxm::IpDscp(value as u8),
+            9 => Oxm::IpEcn(value as u8),
+            10 => Oxm::IpProto(value as u8),
+            11 => Oxm::Ipv4Src(m32(value, mask)),
+            12 => Oxm::Ipv4Dst(m32(value, mask)),
+            13 => Oxm::TcpSrc(value as u16),
+            14 => Oxm::TcpDst(value as u16),
+            15 => Oxm::UdpSrc(value as u16),
+            16 => Oxm::UdpDst(value as u16),
+            17 => Oxm::SctpSrc(value as u16),
+            18 => Oxm::SctpDst(value as u16),
+            19 => Oxm::Icmpv4Type(value as u8),
+            20 => Oxm::Icmpv4Code(value as u8),
+            21 => Oxm::ArpOp(value as u16),
+            22 => Oxm::ArpSpa(m32(value, mask)),
+            23 => Oxm::ArpTpa(m32(value, mask)),
+            24 => Oxm::ArpSha(m64(value, mask)),
+            25 => Oxm::ArpTha(m64(value, mask)),
+            26 => Oxm::Ipv6Src(m128(value, mask)),
+            27 => Oxm::Ipv6Dst(m128(value, mask)),
+            28 => Oxm::Ipv6Flabel(m32(value, mask)),
+            29 => Oxm::Icmpv6Type(value as u8),
+            30 => Oxm::Icmpv6Code(value as u8),
+            31 => Oxm::Ipv6NdTarget(value),
+            32 => Oxm::Ipv6NdSll(value as u64),
+            33 => Oxm::Ipv6NdTll(value as u64),
+            34 => Oxm::MplsLabel(value as u32),
+            35 => Oxm::MplsTc(value as u8),
+            36 => Oxm::MplsBos(value as u8),
+            37 => Oxm::PbbIsid(m32(value, mask)),
+            38 => Oxm::TunnelId(m64(value, mask)),
+            _ => Oxm::Ipv6Exthdr(m16(value, mask)),
+        }
+    }
+
+    /// Return the class, field code, mask flag, and payload of `oxm`.
+    fn to_tlv(oxm: &Oxm) -> (u16, u8, bool, Vec<u8>) {
+        fn m<T: Copy + Into<u128>>(mask: &Mask<T>) -> (u128, Option<u128>) {
+            (mask.value.into(), mask.mask.map(|m| m.into()))
+        }
+        let (field, width, (value, mask)) = match *oxm {
+            Oxm::Other { class, field, hasmask, ref body } => {
+                return (class, field, hasmask, body.clone())
+            }
+            Oxm::InPort(v) => (0, 4, (v as u128, None)),
+            Oxm::InPhyPort(v) => (1, 4, (v as u128, None)),
+            Oxm::Metadata(ref v) => (2, 8, m(v)),
+            Oxm::EthDst(ref v) => (3, 6, m(v)),
+            Oxm::EthSrc(ref v) => (4, 6, m(v)),
+            Oxm::EthType(v) => (5, 2, (v as u128, None)),
+            Oxm::VlanVid(ref v) => (6, 2, m(v)),
+            Oxm::VlanPcp(v) => (7, 1, (v as u128, None)),
+            Oxm::IpDscp(v) => (8, 1, (v as u128, None)),
+            Oxm::IpEcn(v) => (9, 1, (v as u128, None)),
+            Oxm::IpProto(v) => (10, 1, (v as u128, None)),
+            Oxm::Ipv4Src(ref v) => (11, 4, m(v)),
+            Oxm::Ipv4Dst(ref v) => (12, 4, m(v)),
+            Oxm::TcpSrc(v) => (13, 2, (v as u128, None)),
+            Oxm::TcpDst(v) => (14, 2, (v as u128, None)),
+            Oxm::UdpSrc(v) => (15, 2, (v as u128, None)),
+            Oxm::UdpDst(v) => (16, 2, (v as u128, None)),
+            Oxm::SctpSrc(v) => (17, 2, (v as u128, None)),
+            Oxm::SctpDst(v) => (18, 2, (v as u128, None)),
+            Oxm::Icmpv4Type(v) => (19, 1, (v as u128, None)),
+            Oxm::Icmpv4Code(v) => (20, 1, (v as u128, None)),
+            Oxm::ArpOp(v) => (21, 2, (v as u128, None)),
+            Oxm::ArpSpa(ref v) => (22, 4, m(v)),
+            Oxm::ArpTpa(ref v) => (23, 4, m(v)),
+            Oxm::ArpSha(ref v) => (24, 6, m(v)),
+            Oxm::ArpTha(ref v) => (25, 6, m(v)),
+            Oxm::Ipv6Src(ref v) => (26, 16, m(v)),
+            Oxm::Ipv6Dst(ref v) => (27, 16, m(v)),
+            Oxm::Ipv6Flabel(ref v) => (28, 4, m(v)),
+            Oxm::Icmpv6Type(v) => (29, 1, (v as u128, None)),
+            Oxm::Icmpv6Code(v) => (30, 1, (v as u128, None)),
+            Oxm::Ipv6NdTarget(v) => (31, 16, (v, None)),
+            Oxm::Ipv6NdSll(v) => (32, 6, (v as u128, None)),
+            Oxm::Ipv6NdTll(v) => (33, 6, (v as u128, None)),
+            Oxm::MplsLabel(v) => (34, 4, (v as u128, None)),
+            Oxm::MplsTc(v) => (35, 1, (v as u128, None)),
+            Oxm::MplsBos(v) => (36, 1, (v as u128, None)),
+            Oxm::PbbIsid(ref v) => (37, 3, m(v)),
+            Oxm::TunnelId(ref v) => (38, 8, m(v)),
+            Oxm::Ipv6Exthdr(ref v) => (39, 2, m(v)),
+        };
+        let mut body = vec![];
+        write_uint(value, width, &mut body);
+        if let Some(mask) = mask {
+            write_uint(mask, width, &mut body);
+        }
+        (OFPXMC_OPENFLOW_BASIC, field, mask.is_some(), body)
+    }
+
+    fn size_of(oxm: &Oxm) -> usize {
+        let (_, _, _, body) = Oxm::to_tlv(oxm);
+        size_of::<OfpOxmHeader>() + body.len()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Oxm, ParseError> {
+        let class = bytes.read_u16::<BigEndian>()?;
+        let field_and_mask = bytes.read_u8()?;
+        let len = bytes.read_u8()? as usize;
+        let field = field_and_mask >> 1;
+        let hasmask = test_bit(0, field_and_mask as u64);
+        let width = match Oxm::basic_width(field) {
+            Some((width, maskable)) if class == OFPXMC_OPENFLOW_BASIC => {
+                if hasmask && !maskable {
+                    return Err(ParseError::UnknownValue("oxm_hasmask", field as u64));
+                }
+                Some(width)
+            }
+            _ => None,
+        };
+        match width {
+            Some(width) => {
+                if len != if hasmask { 2 * width } else { width } {
+                    return Err(ParseError::BadLength("oxm_length", len));
+                }
+                let value = read_uint(bytes, width)?;
+                let mask = if hasmask {
+                    Some(read_uint(bytes, width)?)
+                } else {
+                    None
+                };
+                Ok(Oxm::of_basic(field, value, mask))
+            }
+            None => {
+                let mut body = vec![0; len];
+                bytes.read_exact(&mut body)?;
+                Ok(Oxm::Other {
+                    class: class,
+                    field: field,
+                    hasmask: hasmask,
+                    body: body,
+                })
+            }
+        }
+    }
+
+    fn marshal(oxm: Oxm, bytes: &mut Vec<u8>) {
+        let (class, field, hasmask, body) = Oxm::to_tlv(&oxm);
+        bytes.write_u16::<BigEndian>(class).unwrap();
+        bytes.write_u8(bit(0, (field as u64) << 1, hasmask) as u8).unwrap();
+        bytes.write_u8(body.len() as u8).unwrap();
+        bytes.write_all(&body).unwrap();
+    }
+}
+
+/// Fields to match against flows, as a sequence of OXM fields. A packet matches the pattern if
+/// it matches every field; an empty pattern matches every packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub fields: Vec<Oxm>,
+}
+
+#[repr(packed)]
+struct OfpMatch(u16, u16);
+
+/// Match type of OXM-based matches, the only type defined by OpenFlow 1.3.
+const OFPMT_OXM: u16 = 1;
+
+impl Pattern {
+    pub fn match_all() -> Pattern {
+        Pattern { fields: vec![] }
+    }
+
+    /// Return the value of the `InPort` field, if the pattern has one.
+    pub fn in_port(&self) -> Option<u32> {
+        self.fields
+            .iter()
+            .filter_map(|oxm| match *oxm {
+                Oxm::InPort(p) => Some(p),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Length of the match, excluding the padding that follows it.
+    fn unpadded_size_of(p: &Pattern) -> usize {
+        let fields: usize = p.fields.iter().map(Oxm::size_of).sum();
+        size_of::<OfpMatch>() + fields
+    }
+
+    fn size_of(p: &Pattern) -> usize {
+        padded(Pattern::unpadded_size_of(p))
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Pattern, ParseError> {
+        let typ = bytes.read_u16::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()? as usize;
+        if typ != OFPMT_OXM {
+            return Err(ParseError::UnsupportedType("ofp_match_type", typ as u64));
+        }
+        if len < size_of::<OfpMatch>() {
+            return Err(ParseError::BadLength("ofp_match", len));
+        }
+        let mut fields_bytes = sub_cursor(bytes, len - size_of::<OfpMatch>())?;
+        let mut fields = vec![];
+        while (fields_bytes.position() as usize) < fields_bytes.get_ref().len() {
+            fields.push(Oxm::parse(&mut fields_bytes)?)
+        }
+        bytes.consume(padded(len) - len);
+        Ok(Pattern { fields: fields })
+    }
+
+    fn marshal(p: Pattern, bytes: &mut Vec<u8>) {
+        let len = Pattern::unpadded_size_of(&p);
+        bytes.write_u16::<BigEndian>(OFPMT_OXM).unwrap();
+        bytes.write_u16::<BigEndian>(len as u16).unwrap();
+        for oxm in p.fields {
+            Oxm::marshal(oxm, bytes)
+        }
+        write_pad(padded(len) - len, bytes);
+    }
+}
+
+/// Port behavior.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PseudoPort {
+    PhysicalPort(u32),
+    InPort,
+    Table,
+    Normal,
+    Flood,
+    AllPorts,
+    /// Send to the controller, including at most the given number of bytes of the packet.
+    Controller(u16),
+    Local,
+}
+
+#[repr(u32)]
+enum OfpPort {
+    OFPPMax = 0xffffff00,
+    OFPPInPort = 0xfffffff8,
+    OFPPTable = 0xfffffff9,
+    OFPPNormal = 0xfffffffa,
+    OFPPFlood = 0xfffffffb,
+    OFPPAll = 0xfffffffc,
+    OFPPController = 0xfffffffd,
+    OFPPLocal = 0xfffffffe,
+    OFPPAny = 0xffffffff,
+}
+
+/// `max_len` of output actions asking the switch to send whole packets to the controller.
+pub const OFPCML_NO_BUFFER: u16 = 0xffff;
+
+impl PseudoPort {
+    fn of_int(p: u32) -> Result<Option<PseudoPort>, ParseError> {
+        if (OfpPort::OFPPAny as u32) == p {
+            Ok(None)
+        } else {
+            Ok(Some(PseudoPort::make(p, 0)?))
+        }
+    }
+
+    fn make(p: u32, len: u16) -> Result<PseudoPort, ParseError> {
+        let pp = match p {
+            p if p == (OfpPort::OFPPInPort as u32) => PseudoPort::InPort,
+            p if p == (OfpPort::OFPPTable as u32) => PseudoPort::Table,
+            p if p == (OfpPort::OFPPNormal as u32) => PseudoPort::Normal,
+            p if p == (OfpPort::OFPPFlood as u32) => PseudoPort::Flood,
+            p if p == (OfpPort::OFPPAll as u32) => PseudoPort::AllPorts,
+            p if p == (OfpPort::OFPPController as u32) => PseudoPort::Controller(len),
+            p if p == (OfpPort::OFPPLocal as u32) => PseudoPort::Local,
+            _ => {
+                if p <= (OfpPort::OFPPMax as u32) {
+                    PseudoPort::PhysicalPort(p)
+                } else {
+                    return Err(ParseError::UnknownValue("ofp_port_no", p as u64));
+                }
+            }
+        };
+        Ok(pp)
+    }
+
+    fn to_int(pp: PseudoPort) -> u32 {
+        match pp {
+            PseudoPort::PhysicalPort(p) => p,
+            PseudoPort::InPort => OfpPort::OFPPInPort as u32,
+            PseudoPort::Table => OfpPort::OFPPTable as u32,
+            PseudoPort::Normal => OfpPort::OFPPNormal as u32,
+            PseudoPort::Flood => OfpPort::OFPPFlood as u32,
+            PseudoPort::AllPorts => OfpPort::OFPPAll as u32,
+            PseudoPort::Controller(_) => OfpPort::OFPPController as u32,
+            PseudoPort::Local => OfpPort::OFPPLocal as u32,
+        }
+    }
+
+    fn marshal(pp: PseudoPort, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(PseudoPort::to_int(pp)).unwrap()
+    }
+
+    /// Marshal `pp`, or `OFPP_ANY` if it is `None`.
+    fn marshal_option(pp: Option<PseudoPort>, bytes: &mut Vec<u8>) {
+        match pp {
+            Some(pp) => PseudoPort::marshal(pp, bytes),
+            None => bytes.write_u32::<BigEndian>(OfpPort::OFPPAny as u32).unwrap(),
+        }
+    }
+}
+
+/// Group id matching every group, used by group deletions.
+pub const OFPG_ALL: u32 = 0xfffffffc;
+/// Group id standing for no group, used as a wildcard by requests filtering on groups.
+const OFPG_ANY: u32 = 0xffffffff;
+
+/// Return `Some(id)` unless `id` is the wildcard `any`.
+fn of_wildcard(id: u32, any: u32) -> Option<u32> {
+    if id == any {
+        None
+    } else {
+        Some(id)
+    }
+}
+
+/// Actions associated with flows and packets.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Output(PseudoPort),
+    CopyTtlOut,
+    CopyTtlIn,
+    SetMplsTtl(u8),
+    DecMplsTtl,
+    /// Push a new VLAN tag with the given ethertype.
+    PushVlan(u16),
+    PopVlan,
+    /// Push a new MPLS shim header with the given ethertype.
+    PushMpls(u16),
+    /// Pop the outermost MPLS shim header, setting the packet's ethertype to the given one.
+    PopMpls(u16),
+    SetQueue(u32),
+    Group(u32),
+    SetNwTtl(u8),
+    DecNwTtl,
+    /// Rewrite a header field. The field must not be masked.
+    SetField(Oxm),
+    /// Push a new PBB service tag with the given ethertype.
+    PushPbb(u16),
+    PopPbb,
+    /// Experimenter-defined action with the given experimenter id and body. The body is
+    /// padded by its experimenter so that the length of the action is a multiple of 8 bytes.
+    Experimenter(u32, Vec<u8>),
+}
+
+#[repr(packed)]
+struct OfpActionHeader(u16, u16);
+
+#[repr(packed)]
+struct OfpActionOutput(u32, u16, [u8; 6]);
+#[repr(packed)]
+struct OfpActionGeneric([u8; 4]);
+#[repr(packed)]
+struct OfpActionTtl(u8, [u8; 3]);
+#[repr(packed)]
+struct OfpActionPush(u16, [u8; 2]);
+#[repr(packed)]
+struct OfpActionId(u32);
+#[repr(packed)]
+struct OfpActionExperimenterHeader(u32);
+
+#[repr(u16)]
+enum OfpActionType {
+    OFPATOutput = 0,
+    OFPATCopyTtlOut = 11,
+    OFPATCopyTtlIn = 12,
+    OFPATSetMplsTtl = 15,
+    OFPATDecMplsTtl = 16,
+    OFPATPushVlan = 17,
+    OFPATPopVlan = 18,
+    OFPATPushMpls = 19,
+    OFPATPopMpls = 20,
+    OFPATSetQueue = 21,
+    OFPATGroup = 22,
+    OFPATSetNwTtl = 23,
+    OFPATDecNwTtl = 24,
+    OFPATSetField = 25,
+    OFPATPushPbb = 26,
+    OFPATPopPbb = 27,
+    OFPATExperimenter = 0xffff,
+}
+
+impl Action {
+    fn type_code(a: &Action) -> OfpActionType {
+        match *a {
+            Action::Output(_) => OfpActionType::OFPATOutput,
+            Action::CopyTtlOut => OfpActionType::OFPATCopyTtlOut,
+            Action::CopyTtlIn => OfpActionType::OFPATCopyTtlIn,
+            Action::SetMplsTtl(_) => OfpActionType::OFPATSetMplsTtl,
+            Action::DecMplsTtl => OfpActionType::OFPATDecMplsTtl,
+            Action::PushVlan(_) => OfpActionType::OFPATPushVlan,
+            Action::PopVlan => OfpActionType::OFPATPopVlan,
+            Action::PushMpls(_) => OfpActionType::OFPATPushMpls,
+            Action::PopMpls(_) => OfpActionType::OFPATPopMpls,
+            Action::SetQueue(_) => OfpActionType::OFPATSetQueue,
+            Action::Group(_) => OfpActionType::OFPATGroup,
+            Action::SetNwTtl(_) => OfpActionType::OFPATSetNwTtl,
+            Action::DecNwTtl => OfpActionType::OFPATDecNwTtl,
+            Action::SetField(_) => OfpActionType::OFPATSetField,
+            Action::PushPbb(_) => OfpActionType::OFPATPushPbb,
+            Action::PopPbb => OfpActionType::OFPATPopPbb,
+            Action::Experimenter(_, _) => OfpActionType::OFPATExperimenter,
+        }
+    }
+
+    fn size_of(a: &Action) -> usize {
+        let h = size_of::<OfpActionHeader>();
+        let body = match *a {
+            Action::Output(_) => size_of::<OfpActionOutput>(),
+            Action::CopyTtlOut |
+            Action::CopyTtlIn |
+            Action::DecMplsTtl |
+            Action::PopVlan |
+            Action::DecNwTtl |
+            Action::PopPbb => size_of::<OfpActionGeneric>(),
+            Action::SetMplsTtl(_) |
+            Action::SetNwTtl(_) => size_of::<OfpActionTtl>(),
+            Action::PushVlan(_) |
+            Action::PushMpls(_) |
+            Action::PopMpls(_) |
+            Action::PushPbb(_) => size_of::<OfpActionPush>(),
+            Action::SetQueue(_) |
+            Action::Group(_) => size_of::<OfpActionId>(),
+            Action::SetField(ref oxm) => padded(h + Oxm::size_of(oxm)) - h,
+            Action::Experimenter(_, ref body) => {
+                size_of::<OfpActionExperimenterHeader>() + body.len()
+            }
+        };
+        h + body
+    }
+
+    fn size_of_sequence(actions: &[Action]) -> usize {
+        actions.iter().fold(0, |acc, x| Action::size_of(x) + acc)
+    }
+
+    fn _parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Action, ParseError> {
+        let action_code = bytes.read_u16::<BigEndian>()?;
+        let action_len = bytes.read_u16::<BigEndian>()? as usize;
+        let h = size_of::<OfpActionHeader>();
+        if action_len < h + 4 || action_len % 8 != 0 {
+            return Err(ParseError::BadLength("ofp_action_header", action_len));
+        }
+        let mut body = sub_cursor(bytes, action_len - h)?;
+        let action = match action_code {
+            t if t == (OfpActionType::OFPATOutput as u16) => {
+                let port = body.read_u32::<BigEndian>()?;
+                let max_len = body.read_u16::<BigEndian>()?;
+                Action::Output(PseudoPort::make(port, max_len)?)
+            }
+            t if t == (OfpActionType::OFPATCopyTtlOut as u16) => Action::CopyTtlOut,
+            t if t == (OfpActionType::OFPATCopyTtlIn as u16) => Action::CopyTtlIn,
+            t if t == (OfpActionType::OFPATSetMplsTtl as u16) => {
+                Action::SetMplsTtl(body.read_u8()?)
+            }
+            t if t == (OfpActionType::OFPATDecMplsTtl as u16) => Action::DecMplsTtl,
+            t if t == (OfpActionType::OFPATPushVlan as u16) => {
+                Action::PushVlan(body.read_u16::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATPopVlan as u16) => Action::PopVlan,
+            t if t == (OfpActionType::OFPATPushMpls as u16) => {
+                Action::PushMpls(body.read_u16::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATPopMpls as u16) => {
+                Action::PopMpls(body.read_u16::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATSetQueue as u16) => {
+                Action::SetQueue(body.read_u32::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATGroup as u16) => {
+                Action::Group(body.read_u32::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATSetNwTtl as u16) => Action::SetNwTtl(body.read_u8()?),
+            t if t == (OfpActionType::OFPATDecNwTtl as u16) => Action::DecNwTtl,
+            t if t == (OfpActionType::OFPATSetField as u16) => {
+                Action::SetField(Oxm::parse(&mut body)?)
+            }
+            t if t == (OfpActionType::OFPATPushPbb as u16) => {
+                Action::PushPbb(body.read_u16::<BigEndian>()?)
+            }
+            t if t == (OfpActionType::OFPATPopPbb as u16) => Action::PopPbb,
+            t if t == (OfpActionType::OFPATExperimenter as u16) => {
+                let experimenter = body.read_u32::<BigEndian>()?;
+                Action::Experimenter(experimenter, body.fill_buf()?.to_vec())
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_action_type", t as u64)),
+        };
+        Ok(action)
+    }
+
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<Action>, ParseError> {
+        let mut v = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            v.push(Action::_parse(bytes)?)
+        }
+        Ok(v)
+    }
+
+    fn marshal(act: Action, bytes: &mut Vec<u8>) {
+        let size = Action::size_of(&act);
+        bytes.write_u16::<BigEndian>(Action::type_code(&act) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(size as u16).unwrap();
+        match act {
+            Action::Output(pp) => {
+                PseudoPort::marshal(pp, bytes);
+                bytes.write_u16::<BigEndian>(match pp {
+                        PseudoPort::Controller(w) => w,
+                        _ => 0,
+                    })
+                    .unwrap();
+                write_pad(6, bytes);
+            }
+            Action::CopyTtlOut |
+            Action::CopyTtlIn |
+            Action::DecMplsTtl |
+            Action::PopVlan |
+            Action::DecNwTtl |
+            Action::PopPbb => write_pad(4, bytes),
+            Action::SetMplsTtl(ttl) |
+            Action::SetNwTtl(ttl) => {
+                bytes.write_u8(ttl).unwrap();
+                write_pad(3, bytes);
+            }
+            Action::PushVlan(ethertype) |
+            Action::PushMpls(ethertype) |
+            Action::PopMpls(ethertype) |
+            Action::PushPbb(ethertype) => {
+                bytes.write_u16::<BigEndian>(ethertype).unwrap();
+                write_pad(2, bytes);
+            }
+            Action::SetQueue(id) |
+            Action::Group(id) => bytes.write_u32::<BigEndian>(id).unwrap(),
+            Action::SetField(oxm) => {
+                let len = size_of::<OfpActionHeader>() + Oxm::size_of(&oxm);
+                Oxm::marshal(oxm, bytes);
+                write_pad(size - len, bytes);
+            }
+            Action::Experimenter(experimenter, body) => {
+                bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// Instructions executed when a packet matches a flow entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    /// Continue processing the packet in the given (later) table.
+    GotoTable(u8),
+    /// Overwrite the bits of the packet's metadata set in `mask` with those of `metadata`.
+    WriteMetadata { metadata: u64, mask: u64 },
+    /// Merge the actions into the packet's action set.
+    WriteActions(Vec<Action>),
+    /// Apply the actions to the packet immediately.
+    ApplyActions(Vec<Action>),
+    /// Clear the packet's action set.
+    ClearActions,
+    /// Run the packet through the given meter, as configured by a `MeterMod`, which may drop it.
+    Meter(u32),
+    /// Experimenter-defined instruction with the given experimenter id and body.
+    Experimenter(u32, Vec<u8>),
+}
+
+#[repr(packed)]
+struct OfpInstructionHeader(u16, u16);
+
+#[repr(packed)]
+struct OfpInstructionGotoTable(u8, [u8; 3]);
+#[repr(packed)]
+struct OfpInstructionWriteMetadata([u8; 4], u64, u64);
+#[repr(packed)]
+struct OfpInstructionActions([u8; 4]);
+#[repr(packed)]
+struct OfpInstructionMeter(u32);
+#[repr(packed)]
+struct OfpInstructionExperimenter(u32);
+
+#[repr(u16)]
+enum OfpInstructionType {
+    OFPITGotoTable = 1,
+    OFPITWriteMetadata = 2,
+    OFPITWriteActions = 3,
+    OFPITApplyActions = 4,
+    OFPITClearActions = 5,
+    OFPITMeter = 6,
+    OFPITExperimenter = 0xffff,
+}
+
+impl Instruction {
+    fn type_code(ins: &Instruction) -> OfpInstructionType {
+        match *ins {
+            Instruction::GotoTable(_) => OfpInstructionType::OFPITGotoTable,
+            Instruction::WriteMetadata { .. } => OfpInstructionType::OFPITWriteMetadata,
+            Instruction::WriteActions(_) => OfpInstructionType::OFPITWriteActions,
+            Instruction::ApplyActions(_) => OfpInstructionType::OFPITApplyActions,
+            Instruction::ClearActions => OfpInstructionType::OFPITClearActions,
+            Instruction::Meter(_) => OfpInstructionType::OFPITMeter,
+            Instruction::Experimenter(_, _) => OfpInstructionType::OFPITExperimenter,
+        }
+    }
+
+    fn size_of(ins: &Instruction) -> usize {
+        let h = size_of::<OfpInstructionHeader>();
+        let body = match *ins {
+            Instruction::GotoTable(_) => size_of::<OfpInstructionGotoTable>(),
+            Instruction::WriteMetadata { .. } => size_of::<OfpInstructionWriteMetadata>(),
+            Instruction::WriteActions(ref acts) |
+            Instruction::ApplyActions(ref acts) => {
+                size_of::<OfpInstructionActions>() + Action::size_of_sequence(acts)
+            }
+            Instruction::ClearActions => size_of::<OfpInstructionActions>(),
+            Instruction::Meter(_) => size_of::<OfpInstructionMeter>(),
+            Instruction::Experimenter(_, ref body) => {
+                size_of::<OfpInstructionExperimenter>() + body.len()
+            }
+        };
+        h + body
+    }
+
+    fn size_of_sequence(instructions: &[Instruction]) -> usize {
+        instructions.iter().fold(0, |acc, x| Instruction::size_of(x) + acc)
+    }
+
+    fn _parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Instruction, ParseError> {
+        let code = bytes.read_u16::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()? as usize;
+        let h = size_of::<OfpInstructionHeader>();
+        if len < h + 4 {
+            return Err(ParseError::BadLength("ofp_instruction", len));
+        }
+        let mut body = sub_cursor(bytes, len - h)?;
+        let ins = match code {
+            t if t == (OfpInstructionType::OFPITGotoTable as u16) => {
+                Instruction::GotoTable(body.read_u8()?)
+            }
+            t if t == (OfpInstructionType::OFPITWriteMetadata as u16) => {
+                body.consume(4);
+                let metadata = body.read_u64::<BigEndian>()?;
+                let mask = body.read_u64::<BigEndian>()?;
+                Instruction::WriteMetadata {
+                    metadata: metadata,
+                    mask: mask,
+                }
+            }
+            t if t == (OfpInstructionType::OFPITWriteActions as u16) => {
+                body.consume(4);
+                Instruction::WriteActions(Action::parse_sequence(&mut body)?)
+            }
+            t if t == (OfpInstructionType::OFPITApplyActions as u16) => {
+                body.consume(4);
+                Instruction::ApplyActions(Action::parse_sequence(&mut body)?)
+            }
+            t if t == (OfpInstructionType::OFPITClearActions as u16) => Instruction::ClearActions,
+            t if t == (OfpInstructionType::OFPITMeter as u16) => {
+                Instruction::Meter(body.read_u32::<BigEndian>()?)
+            }
+            t if t == (OfpInstructionType::OFPITExperimenter as u16) => {
+                let experimenter = body.read_u32::<BigEndian>()?;
+                Instruction::Experimenter(experimenter, body.fill_buf()?.to_vec())
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_instruction_type", t as u64)),
+        };
+        Ok(ins)
+    }
+
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<Instruction>, ParseError> {
+        let mut v = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            v.push(Instruction::_parse(bytes)?)
+        }
+        Ok(v)
+    }
+
+    fn marshal(ins: Instruction, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(Instruction::type_code(&ins) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(Instruction::size_of(&ins) as u16).unwrap();
+        match ins {
+            Instruction::GotoTable(table_id) => {
+                bytes.write_u8(table_id).unwrap();
+                write_pad(3, bytes);
+            }
+            Instruction::WriteMetadata { metadata, mask } => {
+                write_pad(4, bytes);
+                bytes.write_u64::<BigEndian>(metadata).unwrap();
+                bytes.write_u64::<BigEndian>(mask).unwrap();
+            }
+            Instruction::WriteActions(acts) |
+            Instruction::ApplyActions(acts) => {
+                write_pad(4, bytes);
+                for act in acts {
+                    Action::marshal(act, bytes)
+                }
+            }
+            Instruction::ClearActions => write_pad(4, bytes),
+            Instruction::Meter(meter_id) => bytes.write_u32::<BigEndian>(meter_id).unwrap(),
+            Instruction::Experimenter(experimenter, body) => {
+                bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// How long before a flow entry expires.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Timeout {
+    Permanent,
+    ExpiresAfter(u16),
+}
+
+impl Timeout {
+    fn of_int(tm: u16) -> Timeout {
+        match tm {
+            0 => Timeout::Permanent,
+            d => Timeout::ExpiresAfter(d),
+        }
+    }
+
+    fn to_int(tm: Timeout) -> u16 {
+        match tm {
+            Timeout::Permanent => 0,
+            Timeout::ExpiresAfter(d) => d,
+        }
+    }
+}
+
+/// Capabilities supported by the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    pub flow_stats: bool,
+    pub table_stats: bool,
+    pub port_stats: bool,
+    pub group_stats: bool,
+    pub ip_reasm: bool,
+    pub queue_stats: bool,
+    pub port_blocked: bool,
+}
+
+impl Capabilities {
+    fn of_int(d: u32) -> Capabilities {
+        Capabilities {
+            flow_stats: test_bit(0, d as u64),
+            table_stats: test_bit(1, d as u64),
+            port_stats: test_bit(2, d as u64),
+            group_stats: test_bit(3, d as u64),
+            ip_reasm: test_bit(5, d as u64),
+            queue_stats: test_bit(6, d as u64),
+            port_blocked: test_bit(8, d as u64),
+        }
+    }
+
+    fn to_int(c: &Capabilities) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, c.flow_stats) as u32;
+        let ret = bit(1, ret as u64, c.table_stats) as u32;
+        let ret = bit(2, ret as u64, c.port_stats) as u32;
+        let ret = bit(3, ret as u64, c.group_stats) as u32;
+        let ret = bit(5, ret as u64, c.ip_reasm) as u32;
+        let ret = bit(6, ret as u64, c.queue_stats) as u32;
+        bit(8, ret as u64, c.port_blocked) as u32
+    }
+}
+
+/// Switch features. Unlike OpenFlow 1.0, ports are not listed here but requested with a
+/// `MultipartReqBody::PortDesc` request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchFeatures {
+    pub datapath_id: u64,
+    pub num_buffers: u32,
+    pub num_tables: u8,
+    /// Identifies auxiliary connections; 0 on the main connection.
+    pub auxiliary_id: u8,
+    pub supported_capabilities: Capabilities,
+}
+
+#[repr(packed)]
+struct OfpSwitchFeatures(u64, u32, u8, u8, [u8; 2], u32, u32);
+
+impl MessageType for SwitchFeatures {
+    fn size_of(_: &SwitchFeatures) -> usize {
+        size_of::<OfpSwitchFeatures>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<SwitchFeatures, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let datapath_id = bytes.read_u64::<BigEndian>()?;
+        let num_buffers = bytes.read_u32::<BigEndian>()?;
+        let num_tables = bytes.read_u8()?;
+        let auxiliary_id = bytes.read_u8()?;
+        bytes.consume(2);
+        let supported_capabilities = Capabilities::of_int(bytes.read_u32::<BigEndian>()?);
+        Ok(SwitchFeatures {
+            datapath_id: datapath_id,
+            num_buffers: num_buffers,
+            num_tables: num_tables,
+            auxiliary_id: auxiliary_id,
+            supported_capabilities: supported_capabilities,
+        })
+    }
+
+    fn marshal(sf: SwitchFeatures, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(sf.datapath_id).unwrap();
+        bytes.write_u32::<BigEndian>(sf.num_buffers).unwrap();
+        bytes.write_u8(sf.num_tables).unwrap();
+        bytes.write_u8(sf.auxiliary_id).unwrap();
+        write_pad(2, bytes);
+        bytes.write_u32::<BigEndian>(Capabilities::to_int(&sf.supported_capabilities)).unwrap();
+        bytes.write_u32::<BigEndian>(0).unwrap();
+    }
+}
+
+/// Current state of a port. Not configurable by the controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortState {
+    pub link_down: bool,
+    pub blocked: bool,
+    /// Live for fast failover groups.
+    pub live: bool,
+}
+
+impl PortState {
+    fn of_int(d: u32) -> PortState {
+        PortState {
+            link_down: test_bit(0, d as u64),
+            blocked: test_bit(1, d as u64),
+            live: test_bit(2, d as u64),
+        }
+    }
+
+    fn to_int(ps: &PortState) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, ps.link_down) as u32;
+        let ret = bit(1, ret as u64, ps.blocked) as u32;
+        bit(2, ret as u64, ps.live) as u32
+    }
+}
+
+/// Features of ports available in a datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortFeatures {
+    pub f_10mbhd: bool,
+    pub f_10mbfd: bool,
+    pub f_100mbhd: bool,
+    pub f_100mbfd: bool,
+    pub f_1gbhd: bool,
+    pub f_1gbfd: bool,
+    pub f_10gbfd: bool,
+    pub f_40gbfd: bool,
+    pub f_100gbfd: bool,
+    pub f_1tbfd: bool,
+    pub other: bool,
+    pub copper: bool,
+    pub fiber: bool,
+    pub autoneg: bool,
+    pub pause: bool,
+    pub pause_asym: bool,
+}
+
+impl PortFeatures {
+    fn of_int(d: u32) -> PortFeatures {
+        PortFeatures {
+            f_10mbhd: test_bit(0, d as u64),
+            f_10mbfd: test_bit(1, d as u64),
+            f_100mbhd: test_bit(2, d as u64),
+            f_100mbfd: test_bit(3, d as u64),
+            f_1gbhd: test_bit(4, d as u64),
+            f_1gbfd: test_bit(5, d as u64),
+            f_10gbfd: test_bit(6, d as u64),
+            f_40gbfd: test_bit(7, d as u64),
+            f_100gbfd: test_bit(8, d as u64),
+            f_1tbfd: test_bit(9, d as u64),
+            other: test_bit(10, d as u64),
+            copper: test_bit(11, d as u64),
+            fiber: test_bit(12, d as u64),
+            autoneg: test_bit(13, d as u64),
+            pause: test_bit(14, d as u64),
+            pause_asym: test_bit(15, d as u64),
+        }
+    }
+
+    fn to_int(pf: &PortFeatures) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, pf.f_10mbhd) as u32;
+        let ret = bit(1, ret as u64, pf.f_10mbfd) as u32;
+        let ret = bit(2, ret as u64, pf.f_100mbhd) as u32;
+        let ret = bit(3, ret as u64, pf.f_100mbfd) as u32;
+        let ret = bit(4, ret as u64, pf.f_1gbhd) as u32;
+        let ret = bit(5, ret as u64, pf.f_1gbfd) as u32;
+        let ret = bit(6, ret as u64, pf.f_10gbfd) as u32;
+        let ret = bit(7, ret as u64, pf.f_40gbfd) as u32;
+        let ret = bit(8, ret as u64, pf.f_100gbfd) as u32;
+        let ret = bit(9, ret as u64, pf.f_1tbfd) as u32;
+        let ret = bit(10, ret as u64, pf.other) as u32;
+        let ret = bit(11, ret as u64, pf.copper) as u32;
+        let ret = bit(12, ret as u64, pf.fiber) as u32;
+        let ret = bit(13, ret as u64, pf.autoneg) as u32;
+        let ret = bit(14, ret as u64, pf.pause) as u32;
+        bit(15, ret as u64, pf.pause_asym) as u32
+    }
+}
+
+/// Flags to indicate behavior of a port.
+///
+/// These flags are used both to describe the current configuration of a port, and to
+/// configure a port's behavior.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortConfig {
+    pub down: bool,
+    pub no_recv: bool,
+    pub no_fwd: bool,
+    pub no_packet_in: bool,
+}
+
+impl PortConfig {
+    fn of_int(d: u32) -> PortConfig {
+        PortConfig {
+            down: test_bit(0, d as u64),
+            no_recv: test_bit(2, d as u64),
+            no_fwd: test_bit(5, d as u64),
+            no_packet_in: test_bit(6, d as u64),
+        }
+    }
+
+    fn to_int(pc: &PortConfig) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, pc.down) as u32;
+        let ret = bit(2, ret as u64, pc.no_recv) as u32;
+        let ret = bit(5, ret as u64, pc.no_fwd) as u32;
+        bit(6, ret as u64, pc.no_packet_in) as u32
+    }
+}
+
+/// Description of a port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortDesc {
+    pub port_no: u32,
+    pub hw_addr: u64,
+    pub name: String,
+    pub config: PortConfig,
+    pub state: PortState,
+    pub curr: PortFeatures,
+    pub advertised: PortFeatures,
+    pub supported: PortFeatures,
+    pub peer: PortFeatures,
+    /// Current port bitrate, in kbps.
+    pub curr_speed: u32,
+    /// Maximum port bitrate, in kbps.
+    pub max_speed: u32,
+}
+
+#[repr(packed)]
+struct OfpPort13(u32, [u8; 4], [u8; 6], [u8; 2], [u8; 16], u32, u32, u32, u32, u32, u32, u32, u32);
+
+impl PortDesc {
+    fn size_of(_: &PortDesc) -> usize {
+        size_of::<OfpPort13>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortDesc, ParseError> {
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        let hw_addr = {
+            let mut arr: [u8; 6] = [0; 6];
+            bytes.read_exact(&mut arr)?;
+            mac_of_bytes(arr)
+        };
+        bytes.consume(2);
+        let name = parse_fixed_string(bytes, 16)?;
+        let config = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let state = PortState::of_int(bytes.read_u32::<BigEndian>()?);
+        let curr = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let advertised = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let supported = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let peer = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        let curr_speed = bytes.read_u32::<BigEndian>()?;
+        let max_speed = bytes.read_u32::<BigEndian>()?;
+        Ok(PortDesc {
+            port_no: port_no,
+            hw_addr: hw_addr,
+            name: name,
+            config: config,
+            state: state,
+            curr: curr,
+            advertised: advertised,
+            supported: supported,
+            peer: peer,
+            curr_speed: curr_speed,
+            max_speed: max_speed,
+        })
+    }
+
+    fn marshal(pd: PortDesc, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(pd.port_no).unwrap();
+        write_pad(4, bytes);
+        bytes.write_all(&bytes_of_mac(pd.hw_addr)).unwrap();
+        write_pad(2, bytes);
+        marshal_fixed_string(&pd.name, 16, bytes);
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pd.config)).unwrap();
+        bytes.write_u32::<BigEndian>(PortState::to_int(&pd.state)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.curr)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.advertised)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.supported)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pd.peer)).unwrap();
+        bytes.write_u32::<BigEndian>(pd.curr_speed).unwrap();
+        bytes.write_u32::<BigEndian>(pd.max_speed).unwrap();
+    }
+}
+
+/// Type of modification to perform on a flow table.
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlowModCmd {
+    AddFlow,
+    ModFlow,
+    ModStrictFlow,
+    DeleteFlow,
+    DeleteStrictFlow,
+}
+
+impl FlowModCmd {
+    fn of_int(code: u8) -> Result<FlowModCmd, ParseError> {
+        match code {
+            0 => Ok(FlowModCmd::AddFlow),
+            1 => Ok(FlowModCmd::ModFlow),
+            2 => Ok(FlowModCmd::ModStrictFlow),
+            3 => Ok(FlowModCmd::DeleteFlow),
+            4 => Ok(FlowModCmd::DeleteStrictFlow),
+            _ => Err(ParseError::UnknownValue("ofp_flow_mod_command", code as u64)),
+        }
+    }
+}
+
+/// Flags of a flow entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowModFlags {
+    /// Send a `FlowRemoved` message when the flow expires or is deleted.
+    pub send_flow_rem: bool,
+    /// Fail to add the flow if it overlaps an existing one of the same priority.
+    pub check_overlap: bool,
+    /// Reset the packet and byte counts of the flow when it is modified.
+    pub reset_counts: bool,
+    pub no_packet_counts: bool,
+    pub no_byte_counts: bool,
+}
+
+impl FlowModFlags {
+    /// Return `FlowModFlags` with every flag unset.
+    pub fn none() -> FlowModFlags {
+        FlowModFlags::of_int(0)
+    }
+
+    fn of_int(d: u16) -> FlowModFlags {
+        FlowModFlags {
+            send_flow_rem: test_bit(0, d as u64),
+            check_overlap: test_bit(1, d as u64),
+            reset_counts: test_bit(2, d as u64),
+            no_packet_counts: test_bit(3, d as u64),
+            no_byte_counts: test_bit(4, d as u64),
+        }
+    }
+
+    fn to_int(f: &FlowModFlags) -> u16 {
+        let ret = 0u16;
+        let ret = bit(0, ret as u64, f.send_flow_rem) as u16;
+        let ret = bit(1, ret as u64, f.check_overlap) as u16;
+        let ret = bit(2, ret as u64, f.reset_counts) as u16;
+        let ret = bit(3, ret as u64, f.no_packet_counts) as u16;
+        bit(4, ret as u64, f.no_byte_counts) as u16
+    }
+}
+
+/// Table id standing for every table, used by flow deletions and statistics requests.
+pub const OFPTT_ALL: u8 = 0xff;
+
+/// Represents modifications to a flow table from the controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowMod {
+    pub command: FlowModCmd,
+    pub table_id: u8,
+    pub pattern: Pattern,
+    pub priority: u16,
+    pub instructions: Vec<Instruction>,
+    pub cookie: u64,
+    /// Bits of `cookie` that must match when modifying or deleting flows.
+    pub cookie_mask: u64,
+    pub idle_timeout: Timeout,
+    pub hard_timeout: Timeout,
+    pub flags: FlowModFlags,
+    pub apply_to_packet: Option<u32>,
+    /// Restrict deletions to flows outputting to this port.
+    pub out_port: Option<PseudoPort>,
+    /// Restrict deletions to flows outputting to this group.
+    pub out_group: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpFlowMod(u64, u64, u8, u8, u16, u16, u16, u32, u32, u32, u16, [u8; 2]);
+
+impl MessageType for FlowMod {
+    fn size_of(msg: &FlowMod) -> usize {
+        size_of::<OfpFlowMod>() + Pattern::size_of(&msg.pattern) +
+        Instruction::size_of_sequence(&msg.instructions)
+    }
+
+    fn parse(buf: &[u8]) -> Result<FlowMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let cookie_mask = bytes.read_u64::<BigEndian>()?;
+        let table_id = bytes.read_u8()?;
+        let command = FlowModCmd::of_int(bytes.read_u8()?)?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let prio = bytes.read_u16::<BigEndian>()?;
+        let buffer_id = bytes.read_i32::<BigEndian>()?;
+        let out_port = PseudoPort::of_int(bytes.read_u32::<BigEndian>()?)?;
+        let out_group = of_wildcard(bytes.read_u32::<BigEndian>()?, OFPG_ANY);
+        let flags = FlowModFlags::of_int(bytes.read_u16::<BigEndian>()?);
+        bytes.consume(2);
+        let pattern = Pattern::parse(&mut bytes)?;
+        let instructions = Instruction::parse_sequence(&mut bytes)?;
+        Ok(FlowMod {
+            command: command,
+            table_id: table_id,
+            pattern: pattern,
+            priority: prio,
+            instructions: instructions,
+            cookie: cookie,
+            cookie_mask: cookie_mask,
+            idle_timeout: idle,
+            hard_timeout: hard,
+            flags: flags,
+            apply_to_packet: {
+                match buffer_id {
+                    -1 => None,
+                    n => Some(n as u32),
+                }
+            },
+            out_port: out_port,
+            out_group: out_group,
+        })
+    }
+
+    fn marshal(fm: FlowMod, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(fm.cookie).unwrap();
+        bytes.write_u64::<BigEndian>(fm.cookie_mask).unwrap();
+        bytes.write_u8(fm.table_id).unwrap();
+        bytes.write_u8(fm.command as u8).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fm.idle_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fm.hard_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(fm.priority).unwrap();
+        bytes.write_i32::<BigEndian>(match fm.apply_to_packet {
+                None => -1,
+                Some(buf_id) => buf_id as i32,
+            })
+            .unwrap();
+        PseudoPort::marshal_option(fm.out_port, bytes);
+        bytes.write_u32::<BigEndian>(fm.out_group.unwrap_or(OFPG_ANY)).unwrap();
+        bytes.write_u16::<BigEndian>(FlowModFlags::to_int(&fm.flags)).unwrap();
+        write_pad(2, bytes);
+        Pattern::marshal(fm.pattern, bytes);
+        for ins in fm.instructions {
+            Instruction::marshal(ins, bytes)
+        }
+    }
+}
+
+/// The reason a packet arrives at the controller.
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PacketInReason {
+    NoMatch,
+    Action,
+    InvalidTtl,
+}
+
+impl PacketInReason {
+    fn of_int(code: u8) -> Result<PacketInReason, ParseError> {
+        match code {
+            0 => Ok(PacketInReason::NoMatch),
+            1 => Ok(PacketInReason::Action),
+            2 => Ok(PacketInReason::InvalidTtl),
+            _ => Err(ParseError::UnknownValue("ofp_packet_in_reason", code as u64)),
+        }
+    }
+}
+
+/// Represents packets received by the datapath and sent to the controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketIn {
+    pub input_payload: Payload,
+    pub total_len: u16,
+    pub reason: PacketInReason,
+    /// Table the packet was looked up in.
+    pub table_id: u8,
+    /// Cookie of the flow that sent the packet, or all ones on a table miss.
+    pub cookie: u64,
+    /// Pipeline fields of the packet, including its input port.
+    pub pattern: Pattern,
+}
+
+#[repr(packed)]
+struct OfpPacketIn(i32, u16, u8, u8, u64);
+
+impl MessageType for PacketIn {
+    fn size_of(pi: &PacketIn) -> usize {
+        size_of::<OfpPacketIn>() + Pattern::size_of(&pi.pattern) + 2 +
+        Payload::size_of(&pi.input_payload)
+    }
+
+    fn parse(buf: &[u8]) -> Result<PacketIn, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let buf_id = match bytes.read_i32::<BigEndian>()? {
+            -1 => None,
+            n => Some(n),
+        };
+        let total_len = bytes.read_u16::<BigEndian>()?;
+        let reason = PacketInReason::of_int(bytes.read_u8()?)?;
+        let table_id = bytes.read_u8()?;
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let pattern = Pattern::parse(&mut bytes)?;
+        bytes.consume(2);
+        let pk = bytes.fill_buf()?.to_vec();
+        let payload = match buf_id {
+            None => Payload::NotBuffered(pk),
+            Some(n) => Payload::Buffered(n as u32, pk),
+        };
+        Ok(PacketIn {
+            input_payload: payload,
+            total_len: total_len,
+            reason: reason,
+            table_id: table_id,
+            cookie: cookie,
+            pattern: pattern,
+        })
+    }
+
+    fn marshal(pi: PacketIn, bytes: &mut Vec<u8>) {
+        let (buf_id, pk) = match pi.input_payload {
+            Payload::NotBuffered(pk) => (-1, pk),
+            Payload::Buffered(n, pk) => (n as i32, pk),
+        };
+        bytes.write_i32::<BigEndian>(buf_id).unwrap();
+        bytes.write_u16::<BigEndian>(pi.total_len).unwrap();
+        bytes.write_u8(pi.reason as u8).unwrap();
+        bytes.write_u8(pi.table_id).unwrap();
+        bytes.write_u64::<BigEndian>(pi.cookie).unwrap();
+        Pattern::marshal(pi.pattern, bytes);
+        write_pad(2, bytes);
+        bytes.write_all(&pk).unwrap();
+    }
+}
+
+/// Represents packets sent from the controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketOut {
+    pub output_payload: Payload,
+    /// Port the packet arrived on, or `None` if it originates at the controller.
+    pub port_id: Option<u32>,
+    pub apply_actions: Vec<Action>,
+}
+
+#[repr(packed)]
+struct OfpPacketOut(u32, u32, u16, [u8; 6]);
+
+impl MessageType for PacketOut {
+    fn size_of(po: &PacketOut) -> usize {
+        size_of::<OfpPacketOut>() + Action::size_of_sequence(&po.apply_actions) +
+        Payload::size_of(&po.output_payload)
+    }
+
+    fn parse(buf: &[u8]) -> Result<PacketOut, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let buf_id = match bytes.read_i32::<BigEndian>()? {
+            -1 => None,
+            n => Some(n),
+        };
+        let in_port = bytes.read_u32::<BigEndian>()?;
+        let actions_len = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(6);
+        let mut actions_bytes = sub_cursor(&mut bytes, actions_len as usize)?;
+        let actions = Action::parse_sequence(&mut actions_bytes)?;
+        Ok(PacketOut {
+            output_payload: match buf_id {
+                None => Payload::NotBuffered(bytes.fill_buf()?.to_vec()),
+                Some(n) => Payload::Buffered(n as u32, bytes.fill_buf()?.to_vec()),
+            },
+            port_id: of_wildcard(in_port, OfpPort::OFPPController as u32),
+            apply_actions: actions,
+        })
+    }
+
+    fn marshal(po: PacketOut, bytes: &mut Vec<u8>) {
+        let (buf_id, pk) = match po.output_payload {
+            Payload::Buffered(n, pk) => (n as i32, pk),
+            Payload::NotBuffered(pk) => (-1, pk),
+        };
+        bytes.write_i32::<BigEndian>(buf_id).unwrap();
+        bytes.write_u32::<BigEndian>(po.port_id.unwrap_or(OfpPort::OFPPController as u32))
+            .unwrap();
+        bytes.write_u16::<BigEndian>(Action::size_of_sequence(&po.apply_actions) as u16).unwrap();
+        write_pad(6, bytes);
+        for act in po.apply_actions {
+            Action::marshal(act, bytes);
+        }
+        bytes.write_all(&pk).unwrap();
+    }
+}
+
+/// Reason a flow was removed from a switch
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlowRemovedReason {
+    IdleTimeout,
+    HardTimeout,
+    Delete,
+    GroupDelete,
+}
+
+impl FlowRemovedReason {
+    fn of_int(code: u8) -> Result<FlowRemovedReason, ParseError> {
+        match code {
+            0 => Ok(FlowRemovedReason::IdleTimeout),
+            1 => Ok(FlowRemovedReason::HardTimeout),
+            2 => Ok(FlowRemovedReason::Delete),
+            3 => Ok(FlowRemovedReason::GroupDelete),
+            _ => Err(ParseError::UnknownValue("ofp_flow_removed_reason", code as u64)),
+        }
+    }
+}
+
+/// Flow removed (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowRemoved {
+    pub cookie: u64,
+    pub priority: u16,
+    pub reason: FlowRemovedReason,
+    pub table_id: u8,
+    pub duration_sec: u32,
+    pub duration_nsec: u32,
+    pub idle_timeout: Timeout,
+    pub hard_timeout: Timeout,
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub pattern: Pattern,
+}
+
+#[repr(packed)]
+struct OfpFlowRemoved(u64, u16, u8, u8, u32, u32, u16, u16, u64, u64);
+
+impl MessageType for FlowRemoved {
+    fn size_of(f: &FlowRemoved) -> usize {
+        size_of::<OfpFlowRemoved>() + Pattern::size_of(&f.pattern)
+    }
+
+    fn parse(buf: &[u8]) -> Result<FlowRemoved, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let priority = bytes.read_u16::<BigEndian>()?;
+        let reason = FlowRemovedReason::of_int(bytes.read_u8()?)?;
+        let table_id = bytes.read_u8()?;
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let pattern = Pattern::parse(&mut bytes)?;
+        Ok(FlowRemoved {
+            cookie: cookie,
+            priority: priority,
+            reason: reason,
+            table_id: table_id,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+            idle_timeout: idle,
+            hard_timeout: hard,
+            packet_count: packet_count,
+            byte_count: byte_count,
+            pattern: pattern,
+        })
+    }
+
+    fn marshal(f: FlowRemoved, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(f.cookie).unwrap();
+        bytes.write_u16::<BigEndian>(f.priority).unwrap();
+        bytes.write_u8(f.reason as u8).unwrap();
+        bytes.write_u8(f.table_id).unwrap();
+        bytes.write_u32::<BigEndian>(f.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(f.duration_nsec).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(f.idle_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(f.hard_timeout)).unwrap();
+        bytes.write_u64::<BigEndian>(f.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(f.byte_count).unwrap();
+        Pattern::marshal(f.pattern, bytes);
+    }
+}
+
+/// What changed about a port.
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortReason {
+    PortAdd,
+    PortDelete,
+    PortModify,
+}
+
+impl PortReason {
+    fn of_int(code: u8) -> Result<PortReason, ParseError> {
+        match code {
+            0 => Ok(PortReason::PortAdd),
+            1 => Ok(PortReason::PortDelete),
+            2 => Ok(PortReason::PortModify),
+            _ => Err(ParseError::UnknownValue("ofp_port_reason", code as u64)),
+        }
+    }
+}
+
+/// A port has changed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortStatus {
+    pub reason: PortReason,
+    pub desc: PortDesc,
+}
+
+impl MessageType for PortStatus {
+    fn size_of(ps: &PortStatus) -> usize {
+        8 + PortDesc::size_of(&ps.desc)
+    }
+
+    fn parse(buf: &[u8]) -> Result<PortStatus, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let reason = PortReason::of_int(bytes.read_u8()?)?;
+        bytes.consume(7);
+        let desc = PortDesc::parse(&mut bytes)?;
+        Ok(PortStatus {
+            reason: reason,
+            desc: desc,
+        })
+    }
+
+    fn marshal(ps: PortStatus, bytes: &mut Vec<u8>) {
+        bytes.write_u8(ps.reason as u8).unwrap();
+        write_pad(7, bytes);
+        PortDesc::marshal(ps.desc, bytes);
+    }
+}
+
+/// Modifies the behavior of a port (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortMod {
+    pub port_no: u32,
+    /// Must match the hardware address of the port, as reported in its `PortDesc`.
+    pub hw_addr: u64,
+    pub config: PortConfig,
+    /// Flags of `config` to change. Flags not set in `mask` are left unchanged.
+    pub mask: PortConfig,
+    /// Features to advertise. All flags unset leaves the advertised features unchanged.
+    pub advertise: PortFeatures,
+}
+
+#[repr(packed)]
+struct OfpPortMod(u32, [u8; 4], [u8; 6], [u8; 2], u32, u32, u32, [u8; 4]);
+
+impl MessageType for PortMod {
+    fn size_of(_: &PortMod) -> usize {
+        size_of::<OfpPortMod>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<PortMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        let hw_addr = {
+            let mut arr: [u8; 6] = [0; 6];
+            bytes.read_exact(&mut arr)?;
+            mac_of_bytes(arr)
+        };
+        bytes.consume(2);
+        let config = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let mask = PortConfig::of_int(bytes.read_u32::<BigEndian>()?);
+        let advertise = PortFeatures::of_int(bytes.read_u32::<BigEndian>()?);
+        Ok(PortMod {
+            port_no: port_no,
+            hw_addr: hw_addr,
+            config: config,
+            mask: mask,
+            advertise: advertise,
+        })
+    }
+
+    fn marshal(pm: PortMod, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(pm.port_no).unwrap();
+        write_pad(4, bytes);
+        bytes.write_all(&bytes_of_mac(pm.hw_addr)).unwrap();
+        write_pad(2, bytes);
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pm.config)).unwrap();
+        bytes.write_u32::<BigEndian>(PortConfig::to_int(&pm.mask)).unwrap();
+        bytes.write_u32::<BigEndian>(PortFeatures::to_int(&pm.advertise)).unwrap();
+        write_pad(4, bytes);
+    }
+}
+
+/// Configures a flow table (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableMod {
+    /// Table to configure, or `OFPTT_ALL`.
+    pub table_id: u8,
+    /// Bitmap of `OFPTC_*` flags, all of which are deprecated in OpenFlow 1.3.
+    pub config: u32,
+}
+
+#[repr(packed)]
+struct OfpTableMod(u8, [u8; 3], u32);
+
+impl MessageType for TableMod {
+    fn size_of(_: &TableMod) -> usize {
+        size_of::<OfpTableMod>()
+    }
+
+    fn parse(buf: &[u8]) -> Result<TableMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let table_id = bytes.read_u8()?;
+        bytes.consume(3);
+        let config = bytes.read_u32::<BigEndian>()?;
+        Ok(TableMod {
+            table_id: table_id,
+            config: config,
+        })
+    }
+
+    fn marshal(tm: TableMod, bytes: &mut Vec<u8>) {
+        bytes.write_u8(tm.table_id).unwrap();
+        write_pad(3, bytes);
+        bytes.write_u32::<BigEndian>(tm.config).unwrap();
+    }
+}
+
+/// Type of modification to perform on the group table.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupModCmd {
+    AddGroup,
+    ModifyGroup,
+    DeleteGroup,
+}
+
+impl GroupModCmd {
+    fn of_int(code: u16) -> Result<GroupModCmd, ParseError> {
+        match code {
+            0 => Ok(GroupModCmd::AddGroup),
+            1 => Ok(GroupModCmd::ModifyGroup),
+            2 => Ok(GroupModCmd::DeleteGroup),
+            _ => Err(ParseError::UnknownValue("ofp_group_mod_command", code as u64)),
+        }
+    }
+}
+
+/// How a group applies its buckets to a packet.
+#[repr(u8)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupType {
+    /// Apply every bucket, for multicast and flooding.
+    All,
+    /// Apply one bucket, chosen by a switch-computed selection such as a hash, in proportion
+    /// to the bucket weights.
+    Select,
+    /// Apply the group's single bucket.
+    Indirect,
+    /// Apply the first live bucket, as determined by its watch port and group.
+    FastFailover,
+}
+
+impl GroupType {
+    fn of_int(code: u8) -> Result<GroupType, ParseError> {
+        match code {
+            0 => Ok(GroupType::All),
+            1 => Ok(GroupType::Select),
+            2 => Ok(GroupType::Indirect),
+            3 => Ok(GroupType::FastFailover),
+            _ => Err(ParseError::UnknownValue("ofp_group_type", code as u64)),
+        }
+    }
+}
+
+/// A set of actions applied to packets processed by a group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bucket {
+    /// Relative weight of the bucket in a `GroupType::Select` group; 0 otherwise.
+    pub weight: u16,
+    /// Port whose liveness decides whether a `GroupType::FastFailover` bucket is live.
+    pub watch_port: Option<u32>,
+    /// Group whose liveness decides whether a `GroupType::FastFailover` bucket is live.
+    pub watch_group: Option<u32>,
+    pub actions: Vec<Action>,
+}
+
+#[repr(packed)]
+struct OfpBucket(u16, u16, u32, u32, [u8; 4]);
+
+impl Bucket {
+    fn size_of(b: &Bucket) -> usize {
+        size_of::<OfpBucket>() + Action::size_of_sequence(&b.actions)
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Bucket, ParseError> {
+        let len = bytes.read_u16::<BigEndian>()? as usize;
+        let weight = bytes.read_u16::<BigEndian>()?;
+        let watch_port = of_wildcard(bytes.read_u32::<BigEndian>()?, OfpPort::OFPPAny as u32);
+        let watch_group = of_wildcard(bytes.read_u32::<BigEndian>()?, OFPG_ANY);
+        bytes.consume(4);
+        if len < size_of::<OfpBucket>() {
+            return Err(ParseError::BadLength("ofp_bucket", len));
+        }
+        let mut actions_bytes = sub_cursor(bytes, len - size_of::<OfpBucket>())?;
+        let actions = Action::parse_sequence(&mut actions_bytes)?;
+        Ok(Bucket {
+            weight: weight,
+            watch_port: watch_port,
+            watch_group: watch_group,
+            actions: actions,
+        })
+    }
+
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<Bucket>, ParseError> {
+        let mut v = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            v.push(Bucket::parse(bytes)?)
+        }
+        Ok(v)
+    }
+
+    fn marshal(b: Bucket, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(Bucket::size_of(&b) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(b.weight).unwrap();
+        bytes.write_u32::<BigEndian>(b.watch_port.unwrap_or(OfpPort::OFPPAny as u32)).unwrap();
+        bytes.write_u32::<BigEndian>(b.watch_group.unwrap_or(OFPG_ANY)).unwrap();
+        write_pad(4, bytes);
+        for act in b.actions {
+            Action::marshal(act, bytes)
+        }
+    }
+}
+
+/// Represents modifications to the group table from the controller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupMod {
+    pub command: GroupModCmd,
+    pub typ: GroupType,
+    /// Group to modify, or `OFPG_ALL` to delete every group.
+    pub group_id: u32,
+    pub buckets: Vec<Bucket>,
+}
+
+#[repr(packed)]
+struct OfpGroupMod(u16, u8, u8, u32);
+
+impl MessageType for GroupMod {
+    fn size_of(gm: &GroupMod) -> usize {
+        let buckets: usize = gm.buckets.iter().map(Bucket::size_of).sum();
+        size_of::<OfpGroupMod>() + buckets
+    }
+
+    fn parse(buf: &[u8]) -> Result<GroupMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let command = GroupModCmd::of_int(bytes.read_u16::<BigEndian>()?)?;
+        let typ = GroupType::of_int(bytes.read_u8()?)?;
+        bytes.consume(1);
+        let group_id = bytes.read_u32::<BigEndian>()?;
+        let buckets = Bucket::parse_sequence(&mut bytes)?;
+        Ok(GroupMod {
+            command: command,
+            typ: typ,
+            group_id: group_id,
+            buckets: buckets,
+        })
+    }
+
+    fn marshal(gm: GroupMod, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(gm.command as u16).unwrap();
+        bytes.write_u8(gm.typ as u8).unwrap();
+        bytes.write_u8(0).unwrap();
+        bytes.write_u32::<BigEndian>(gm.group_id).unwrap();
+        for b in gm.buckets {
+            Bucket::marshal(b, bytes)
+        }
+    }
+}
+
+/// Meter id standing for every meter, used by meter deletions and multipart requests.
+pub const OFPM_ALL: u32 = 0xffffffff;
+/// Meter id of the virtual meter rate-limiting packets sent to the controller.
+pub const OFPM_CONTROLLER: u32 = 0xfffffffe;
+/// Meter id of the virtual meter rate-limiting packets on the datapath's slow path.
+pub const OFPM_SLOWPATH: u32 = 0xfffffffd;
+
+/// Type of modification to perform on the meter table.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeterModCmd {
+    AddMeter,
+    ModifyMeter,
+    DeleteMeter,
+}
+
+impl MeterModCmd {
+    fn of_int(code: u16) -> Result<MeterModCmd, ParseError> {
+        match code {
+            0 => Ok(MeterModCmd::AddMeter),
+            1 => Ok(MeterModCmd::ModifyMeter),
+            2 => Ok(MeterModCmd::DeleteMeter),
+            _ => Err(ParseError::UnknownValue("ofp_meter_mod_command", code as u64)),
+        }
+    }
+}
+
+/// Flags of a meter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterFlags {
+    /// Band rates are in kb/s.
+    pub kbps: bool,
+    /// Band rates are in packets per second.
+    pub pktps: bool,
+    /// Apply the burst sizes of the bands.
+    pub burst: bool,
+    /// Collect statistics.
+    pub stats: bool,
+}
+
+impl MeterFlags {
+    fn of_int(d: u16) -> MeterFlags {
+        MeterFlags {
+            kbps: test_bit(0, d as u64),
+            pktps: test_bit(1, d as u64),
+            burst: test_bit(2, d as u64),
+            stats: test_bit(3, d as u64),
+        }
+    }
+
+    fn to_int(f: &MeterFlags) -> u16 {
+        let ret = 0u16;
+        let ret = bit(0, ret as u64, f.kbps) as u16;
+        let ret = bit(1, ret as u64, f.pktps) as u16;
+        let ret = bit(2, ret as u64, f.burst) as u16;
+        bit(3, ret as u64, f.stats) as u16
+    }
+}
+
+/// Rate band of a meter, applied to packets once the rate of the meter exceeds `rate`.
+///
+/// `rate` and `burst_size` are in kb/s and kilobits, or in packets per second and packets, as
+/// set by the meter's `MeterFlags`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeterBand {
+    /// Drop the packet.
+    Drop { rate: u32, burst_size: u32 },
+    /// Increase the drop precedence of the DSCP field of the packet by `prec_level`.
+    DscpRemark {
+        rate: u32,
+        burst_size: u32,
+        prec_level: u8,
+    },
+    /// Experimenter-defined band with the given experimenter id.
+    Experimenter {
+        rate: u32,
+        burst_size: u32,
+        experimenter: u32,
+    },
+}
+
+#[repr(packed)]
+struct OfpMeterBand(u16, u16, u32, u32, [u8; 4]);
+
+#[repr(u16)]
+enum OfpMeterBandType {
+    OFPMBTDrop = 1,
+    OFPMBTDscpRemark = 2,
+    OFPMBTExperimenter = 0xffff,
+}
+
+impl MeterBand {
+    fn size_of(_: &MeterBand) -> usize {
+        size_of::<OfpMeterBand>()
+    }
+
+    fn size_of_sequence(bands: &[MeterBand]) -> usize {
+        bands.iter().map(MeterBand::size_of).sum()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<MeterBand, ParseError> {
+        let typ = bytes.read_u16::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()? as usize;
+        if len != size_of::<OfpMeterBand>() {
+            return Err(ParseError::BadLength("ofp_meter_band", len));
+        }
+        let rate = bytes.read_u32::<BigEndian>()?;
+        let burst_size = bytes.read_u32::<BigEndian>()?;
+        let band = match typ {
+            t if t == (OfpMeterBandType::OFPMBTDrop as u16) => {
+                bytes.consume(4);
+                MeterBand::Drop {
+                    rate: rate,
+                    burst_size: burst_size,
+                }
+            }
+            t if t == (OfpMeterBandType::OFPMBTDscpRemark as u16) => {
+                let prec_level = bytes.read_u8()?;
+                bytes.consume(3);
+                MeterBand::DscpRemark {
+                    rate: rate,
+                    burst_size: burst_size,
+                    prec_level: prec_level,
+                }
+            }
+            t if t == (OfpMeterBandType::OFPMBTExperimenter as u16) => {
+                MeterBand::Experimenter {
+                    rate: rate,
+                    burst_size: burst_size,
+                    experimenter: bytes.read_u32::<BigEndian>()?,
+                }
+            }
+            t => return Err(ParseError::UnknownValue("ofp_meter_band_type", t as u64)),
+        };
+        Ok(band)
+    }
+
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<MeterBand>, ParseError> {
+        let mut v = vec![];
+        while (bytes.position() as usize) < bytes.get_ref().len() {
+            v.push(MeterBand::parse(bytes)?)
+        }
+        Ok(v)
+    }
+
+    fn marshal(band: MeterBand, bytes: &mut Vec<u8>) {
+        let (typ, rate, burst_size) = match band {
+            MeterBand::Drop { rate, burst_size } => {
+                (OfpMeterBandType::OFPMBTDrop, rate, burst_size)
+            }
+            MeterBand::DscpRemark { rate, burst_size, .. } => {
+                (OfpMeterBandType::OFPMBTDscpRemark, rate, burst_size)
+            }
+            MeterBand::Experimenter { rate, burst_size, .. } => {
+                (OfpMeterBandType::OFPMBTExperimenter, rate, burst_size)
+            }
+        };
+        bytes.write_u16::<BigEndian>(typ as u16).unwrap();
+        bytes.write_u16::<BigEndian>(size_of::<OfpMeterBand>() as u16).unwrap();
+        bytes.write_u32::<BigEndian>(rate).unwrap();
+        bytes.write_u32::<BigEndian>(burst_size).unwrap();
+        match band {
+            MeterBand::Drop { .. } => write_pad(4, bytes),
+            MeterBand::DscpRemark { prec_level, .. } => {
+                bytes.write_u8(prec_level).unwrap();
+                write_pad(3, bytes);
+            }
+            MeterBand::Experimenter { experimenter, .. } => {
+                bytes.write_u32::<BigEndian>(experimenter).unwrap()
+            }
+        }
+    }
+}
+
+/// Represents modifications to the meter table from the controller.
+///
+/// Flows are attached to a meter with `Instruction::Meter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterMod {
+    pub command: MeterModCmd,
+    pub flags: MeterFlags,
+    /// Meter to modify, or `OFPM_ALL` to delete every meter.
+    pub meter_id: u32,
+    pub bands: Vec<MeterBand>,
+}
+
+#[repr(packed)]
+struct OfpMeterMod(u16, u16, u32);
+
+impl MessageType for MeterMod {
+    fn size_of(mm: &MeterMod) -> usize {
+        size_of::<OfpMeterMod>() + MeterBand::size_of_sequence(&mm.bands)
+    }
+
+    fn parse(buf: &[u8]) -> Result<MeterMod, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let command = MeterModCmd::of_int(bytes.read_u16::<BigEndian>()?)?;
+        let flags = MeterFlags::of_int(bytes.read_u16::<BigEndian>()?);
+        let meter_id = bytes.read_u32::<BigEndian>()?;
+        let bands = MeterBand::parse_sequence(&mut bytes)?;
+        Ok(MeterMod {
+            command: command,
+            flags: flags,
+            meter_id: meter_id,
+            bands: bands,
+        })
+    }
+
+    fn marshal(mm: MeterMod, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(mm.command as u16).unwrap();
+        bytes.write_u16::<BigEndian>(MeterFlags::to_int(&mm.flags)).unwrap();
+        bytes.write_u32::<BigEndian>(mm.meter_id).unwrap();
+        for band in mm.bands {
+            MeterBand::marshal(band, bytes)
+        }
+    }
+}
+
+/// Reason Hello failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum HelloFailed {
+    Incompatible,
+    EPerm,
+}
+
+impl HelloFailed {
+    fn of_int(code: u16) -> Result<HelloFailed, ParseError> {
+        match code {
+            0 => Ok(HelloFailed::Incompatible),
+            1 => Ok(HelloFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_hello_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason the controller made a bad request to a switch.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadRequest {
+    BadVersion,
+    BadType,
+    BadMultipart,
+    BadExperimenter,
+    BadExpType,
+    EPerm,
+    BadLen,
+    BufferEmpty,
+    BufferUnknown,
+    BadTableId,
+    IsSlave,
+    BadPort,
+    BadPacket,
+    MultipartBufferOverflow,
+}
+
+impl BadRequest {
+    fn of_int(code: u16) -> Result<BadRequest, ParseError> {
+        match code {
+            0 => Ok(BadRequest::BadVersion),
+            1 => Ok(BadRequest::BadType),
+            2 => Ok(BadRequest::BadMultipart),
+            3 => Ok(BadRequest::BadExperimenter),
+            4 => Ok(BadRequest::BadExpType),
+            5 => Ok(BadRequest::EPerm),
+            6 => Ok(BadRequest::BadLen),
+            7 => Ok(BadRequest::BufferEmpty),
+            8 => Ok(BadRequest::BufferUnknown),
+            9 => Ok(BadRequest::BadTableId),
+            10 => Ok(BadRequest::IsSlave),
+            11 => Ok(BadRequest::BadPort),
+            12 => Ok(BadRequest::BadPacket),
+            13 => Ok(BadRequest::MultipartBufferOverflow),
+            _ => Err(ParseError::UnknownValue("ofp_bad_request_code", code as u64)),
+        }
+    }
+}
+
+/// Reason the controller action failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadAction {
+    BadType,
+    BadLen,
+    BadExperimenter,
+    BadExpType,
+    BadOutPort,
+    BadArgument,
+    EPerm,
+    TooMany,
+    BadQueue,
+    BadOutGroup,
+    MatchInconsistent,
+    UnsupportedOrder,
+    BadTag,
+    BadSetType,
+    BadSetLen,
+    BadSetArgument,
+}
+
+impl BadAction {
+    fn of_int(code: u16) -> Result<BadAction, ParseError> {
+        match code {
+            0 => Ok(BadAction::BadType),
+            1 => Ok(BadAction::BadLen),
+            2 => Ok(BadAction::BadExperimenter),
+            3 => Ok(BadAction::BadExpType),
+            4 => Ok(BadAction::BadOutPort),
+            5 => Ok(BadAction::BadArgument),
+            6 => Ok(BadAction::EPerm),
+            7 => Ok(BadAction::TooMany),
+            8 => Ok(BadAction::BadQueue),
+            9 => Ok(BadAction::BadOutGroup),
+            10 => Ok(BadAction::MatchInconsistent),
+            11 => Ok(BadAction::UnsupportedOrder),
+            12 => Ok(BadAction::BadTag),
+            13 => Ok(BadAction::BadSetType),
+            14 => Ok(BadAction::BadSetLen),
+            15 => Ok(BadAction::BadSetArgument),
+            _ => Err(ParseError::UnknownValue("ofp_bad_action_code", code as u64)),
+        }
+    }
+}
+
+/// Reason an instruction from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadInstruction {
+    UnknownInst,
+    UnsupInst,
+    BadTableId,
+    UnsupMetadata,
+    UnsupMetadataMask,
+    BadExperimenter,
+    BadExpType,
+    BadLen,
+    EPerm,
+}
+
+impl BadInstruction {
+    fn of_int(code: u16) -> Result<BadInstruction, ParseError> {
+        match code {
+            0 => Ok(BadInstruction::UnknownInst),
+            1 => Ok(BadInstruction::UnsupInst),
+            2 => Ok(BadInstruction::BadTableId),
+            3 => Ok(BadInstruction::UnsupMetadata),
+            4 => Ok(BadInstruction::UnsupMetadataMask),
+            5 => Ok(BadInstruction::BadExperimenter),
+            6 => Ok(BadInstruction::BadExpType),
+            7 => Ok(BadInstruction::BadLen),
+            8 => Ok(BadInstruction::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_bad_instruction_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a match from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadMatch {
+    BadType,
+    BadLen,
+    BadTag,
+    BadDlAddrMask,
+    BadNwAddrMask,
+    BadWildcards,
+    BadField,
+    BadValue,
+    BadMask,
+    BadPrereq,
+    DupField,
+    EPerm,
+}
+
+impl BadMatch {
+    fn of_int(code: u16) -> Result<BadMatch, ParseError> {
+        match code {
+            0 => Ok(BadMatch::BadType),
+            1 => Ok(BadMatch::BadLen),
+            2 => Ok(BadMatch::BadTag),
+            3 => Ok(BadMatch::BadDlAddrMask),
+            4 => Ok(BadMatch::BadNwAddrMask),
+            5 => Ok(BadMatch::BadWildcards),
+            6 => Ok(BadMatch::BadField),
+            7 => Ok(BadMatch::BadValue),
+            8 => Ok(BadMatch::BadMask),
+            9 => Ok(BadMatch::BadPrereq),
+            10 => Ok(BadMatch::DupField),
+            11 => Ok(BadMatch::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_bad_match_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a FlowMod from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FlowModFailed {
+    Unknown,
+    TableFull,
+    BadTableId,
+    Overlap,
+    EPerm,
+    BadTimeout,
+    BadCommand,
+    BadFlags,
+}
+
+impl FlowModFailed {
+    fn of_int(code: u16) -> Result<FlowModFailed, ParseError> {
+        match code {
+            0 => Ok(FlowModFailed::Unknown),
+            1 => Ok(FlowModFailed::TableFull),
+            2 => Ok(FlowModFailed::BadTableId),
+            3 => Ok(FlowModFailed::Overlap),
+            4 => Ok(FlowModFailed::EPerm),
+            5 => Ok(FlowModFailed::BadTimeout),
+            6 => Ok(FlowModFailed::BadCommand),
+            7 => Ok(FlowModFailed::BadFlags),
+            _ => Err(ParseError::UnknownValue("ofp_flow_mod_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a GroupMod from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupModFailed {
+    GroupExists,
+    InvalidGroup,
+    WeightUnsupported,
+    OutOfGroups,
+    OutOfBuckets,
+    ChainingUnsupported,
+    WatchUnsupported,
+    Loop,
+    UnknownGroup,
+    ChainedGroup,
+    BadType,
+    BadCommand,
+    BadBucket,
+    BadWatch,
+    EPerm,
+}
+
+impl GroupModFailed {
+    fn of_int(code: u16) -> Result<GroupModFailed, ParseError> {
+        match code {
+            0 => Ok(GroupModFailed::GroupExists),
+            1 => Ok(GroupModFailed::InvalidGroup),
+            2 => Ok(GroupModFailed::WeightUnsupported),
+            3 => Ok(GroupModFailed::OutOfGroups),
+            4 => Ok(GroupModFailed::OutOfBuckets),
+            5 => Ok(GroupModFailed::ChainingUnsupported),
+            6 => Ok(GroupModFailed::WatchUnsupported),
+            7 => Ok(GroupModFailed::Loop),
+            8 => Ok(GroupModFailed::UnknownGroup),
+            9 => Ok(GroupModFailed::ChainedGroup),
+            10 => Ok(GroupModFailed::BadType),
+            11 => Ok(GroupModFailed::BadCommand),
+            12 => Ok(GroupModFailed::BadBucket),
+            13 => Ok(GroupModFailed::BadWatch),
+            14 => Ok(GroupModFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_group_mod_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a PortMod from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortModFailed {
+    BadPort,
+    BadHwAddr,
+    BadConfig,
+    BadAdvertise,
+    EPerm,
+}
+
+impl PortModFailed {
+    fn of_int(code: u16) -> Result<PortModFailed, ParseError> {
+        match code {
+            0 => Ok(PortModFailed::BadPort),
+            1 => Ok(PortModFailed::BadHwAddr),
+            2 => Ok(PortModFailed::BadConfig),
+            3 => Ok(PortModFailed::BadAdvertise),
+            4 => Ok(PortModFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_port_mod_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a TableMod from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableModFailed {
+    BadTable,
+    BadConfig,
+    EPerm,
+}
+
+impl TableModFailed {
+    fn of_int(code: u16) -> Result<TableModFailed, ParseError> {
+        match code {
+            0 => Ok(TableModFailed::BadTable),
+            1 => Ok(TableModFailed::BadConfig),
+            2 => Ok(TableModFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_table_mod_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a queue operation from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueueOpFailed {
+    BadPort,
+    BadQueue,
+    EPerm,
+}
+
+impl QueueOpFailed {
+    fn of_int(code: u16) -> Result<QueueOpFailed, ParseError> {
+        match code {
+            0 => Ok(QueueOpFailed::BadPort),
+            1 => Ok(QueueOpFailed::BadQueue),
+            2 => Ok(QueueOpFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_queue_op_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a SetConfig from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SwitchConfigFailed {
+    BadFlags,
+    BadLen,
+    EPerm,
+}
+
+impl SwitchConfigFailed {
+    fn of_int(code: u16) -> Result<SwitchConfigFailed, ParseError> {
+        match code {
+            0 => Ok(SwitchConfigFailed::BadFlags),
+            1 => Ok(SwitchConfigFailed::BadLen),
+            2 => Ok(SwitchConfigFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_switch_config_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a role request from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum RoleRequestFailed {
+    Stale,
+    Unsup,
+    BadRole,
+}
+
+impl RoleRequestFailed {
+    fn of_int(code: u16) -> Result<RoleRequestFailed, ParseError> {
+        match code {
+            0 => Ok(RoleRequestFailed::Stale),
+            1 => Ok(RoleRequestFailed::Unsup),
+            2 => Ok(RoleRequestFailed::BadRole),
+            _ => Err(ParseError::UnknownValue("ofp_role_request_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a table features request from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableFeaturesFailed {
+    BadTable,
+    BadMetadata,
+    BadType,
+    BadLen,
+    BadArgument,
+    EPerm,
+}
+
+impl TableFeaturesFailed {
+    fn of_int(code: u16) -> Result<TableFeaturesFailed, ParseError> {
+        match code {
+            0 => Ok(TableFeaturesFailed::BadTable),
+            1 => Ok(TableFeaturesFailed::BadMetadata),
+            2 => Ok(TableFeaturesFailed::BadType),
+            3 => Ok(TableFeaturesFailed::BadLen),
+            4 => Ok(TableFeaturesFailed::BadArgument),
+            5 => Ok(TableFeaturesFailed::EPerm),
+            _ => Err(ParseError::UnknownValue("ofp_table_features_failed_code", code as u64)),
+        }
+    }
+}
+
+/// Reason a MeterMod from the controller failed.
+#[repr(u16)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeterModFailed {
+    Unknown,
+    MeterExists,
+    InvalidMeter,
+    UnknownMeter,
+    BadCommand,
+    BadFlags,
+    BadRate,
+    BadBurst,
+    BadBand,
+    BadBandValue,
+    OutOfMeters,
+    OutOfBands,
+}
+
+impl MeterModFailed {
+    fn of_int(code: u16) -> Result<MeterModFailed, ParseError> {
+        match code {
+            0 => Ok(MeterModFailed::Unknown),
+            1 => Ok(MeterModFailed::MeterExists),
+            2 => Ok(MeterModFailed::InvalidMeter),
+            3 => Ok(MeterModFailed::UnknownMeter),
+            4 => Ok(MeterModFailed::BadCommand),
+            5 => Ok(MeterModFailed::BadFlags),
+            6 => Ok(MeterModFailed::BadRate),
+            7 => Ok(MeterModFailed::BadBurst),
+            8 => Ok(MeterModFailed::BadBand),
+            9 => Ok(MeterModFailed::BadBandValue),
+            10 => Ok(MeterModFailed::OutOfMeters),
+            11 => Ok(MeterModFailed::OutOfBands),
+            _ => Err(ParseError::UnknownValue("ofp_meter_mod_failed_code", code as u64)),
+        }
+    }
+}
+
+/// High-level type of OpenFlow error
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorType {
+    HelloFailed(HelloFailed),
+    BadRequest(BadRequest),
+    BadAction(BadAction),
+    BadInstruction(BadInstruction),
+    BadMatch(BadMatch),
+    FlowModFailed(FlowModFailed),
+    GroupModFailed(GroupModFailed),
+    PortModFailed(PortModFailed),
+    TableModFailed(TableModFailed),
+    QueueOpFailed(QueueOpFailed),
+    SwitchConfigFailed(SwitchConfigFailed),
+    RoleRequestFailed(RoleRequestFailed),
+    MeterModFailed(MeterModFailed),
+    TableFeaturesFailed(TableFeaturesFailed),
+}
+
+/// Error message (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Error(ErrorType, Vec<u8>),
+    /// Experimenter-defined error with the given type, experimenter id, and data.
+    Experimenter {
+        exp_type: u16,
+        experimenter: u32,
+        data: Vec<u8>,
+    },
+}
+
+#[repr(packed)]
+struct OfpErrorMsg(u16, u16);
+
+const OFPET_EXPERIMENTER: u16 = 0xffff;
+
+impl MessageType for Error {
+    fn size_of(err: &Error) -> usize {
+        match *err {
+            Error::Error(_, ref body) => size_of::<OfpErrorMsg>() + body.len(),
+            Error::Experimenter { ref data, .. } => {
+                size_of::<OfpErrorMsg>() + size_of::<u32>() + data.len()
+            }
+        }
+    }
+
+    fn parse(buf: &[u8]) -> Result<Error, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let error_type = bytes.read_u16::<BigEndian>()?;
+        let error_code = bytes.read_u16::<BigEndian>()?;
+        let code = match error_type {
+            0 => ErrorType::HelloFailed(HelloFailed::of_int(error_code)?),
+            1 => ErrorType::BadRequest(BadRequest::of_int(error_code)?),
+            2 => ErrorType::BadAction(BadAction::of_int(error_code)?),
+            3 => ErrorType::BadInstruction(BadInstruction::of_int(error_code)?),
+            4 => ErrorType::BadMatch(BadMatch::of_int(error_code)?),
+            5 => ErrorType::FlowModFailed(FlowModFailed::of_int(error_code)?),
+            6 => ErrorType::GroupModFailed(GroupModFailed::of_int(error_code)?),
+            7 => ErrorType::PortModFailed(PortModFailed::of_int(error_code)?),
+            8 => ErrorType::TableModFailed(TableModFailed::of_int(error_code)?),
+            9 => ErrorType::QueueOpFailed(QueueOpFailed::of_int(error_code)?),
+            10 => ErrorType::SwitchConfigFailed(SwitchConfigFailed::of_int(error_code)?),
+            11 => ErrorType::RoleRequestFailed(RoleRequestFailed::of_int(error_code)?),
+            12 => ErrorType::MeterModFailed(MeterModFailed::of_int(error_code)?),
+            13 => ErrorType::TableFeaturesFailed(TableFeaturesFailed::of_int(error_code)?),
+            OFPET_EXPERIMENTER => {
+                let experimenter = bytes.read_u32::<BigEndian>()?;
+                return Ok(Error::Experimenter {
+                    exp_type: error_code,
+                    experimenter: experimenter,
+                    data: bytes.fill_buf()?.to_vec(),
+                });
+            }
+            _ => return Err(ParseError::UnknownValue("ofp_error_type", error_type as u64)),
+        };
+        Ok(Error::Error(code, bytes.fill_buf()?.to_vec()))
+    }
+
+    fn marshal(err: Error, bytes: &mut Vec<u8>) {
+        let (code, body) = match err {
+            Error::Error(code, body) => (code, body),
+            Error::Experimenter { exp_type, experimenter, data } => {
+                bytes.write_u16::<BigEndian>(OFPET_EXPERIMENTER).unwrap();
+                bytes.write_u16::<BigEndian>(exp_type).unwrap();
+                bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                bytes.write_all(&data).unwrap();
+                return;
+            }
+        };
+        let (error_type, error_code) = match code {
+            ErrorType::HelloFailed(c) => (0, c as u16),
+            ErrorType::BadRequest(c) => (1, c as u16),
+            ErrorType::BadAction(c) => (2, c as u16),
+            ErrorType::BadInstruction(c) => (3, c as u16),
+            ErrorType::BadMatch(c) => (4, c as u16),
+            ErrorType::FlowModFailed(c) => (5, c as u16),
+            ErrorType::GroupModFailed(c) => (6, c as u16),
+            ErrorType::PortModFailed(c) => (7, c as u16),
+            ErrorType::TableModFailed(c) => (8, c as u16),
+            ErrorType::QueueOpFailed(c) => (9, c as u16),
+            ErrorType::SwitchConfigFailed(c) => (10, c as u16),
+            ErrorType::RoleRequestFailed(c) => (11, c as u16),
+            ErrorType::MeterModFailed(c) => (12, c as u16),
+            ErrorType::TableFeaturesFailed(c) => (13, c as u16),
+        };
+        bytes.write_u16::<BigEndian>(error_type).unwrap();
+        bytes.write_u16::<BigEndian>(error_code).unwrap();
+        bytes.write_all(&body).unwrap();
+    }
+}
+
+/// Type of statistics or state carried in the body of a multipart request or reply.
+#[repr(u16)]
+enum OfpMultipartType {
+    OFPMPDesc,
+    OFPMPFlow,
+    OFPMPAggregate,
+    OFPMPTable,
+    OFPMPPortStats,
+    OFPMPQueue,
+    OFPMPGroup,
+    OFPMPGroupDesc,
+    OFPMPGroupFeatures,
+    OFPMPMeter,
+    OFPMPMeterConfig,
+    OFPMPMeterFeatures,
+    OFPMPPortDesc = 13,
+    OFPMPExperimenter = 0xffff,
+}
+
+#[repr(packed)]
+struct OfpMultipartMsg(u16, u16, [u8; 4]);
+
+#[repr(packed)]
+struct OfpMultipartExperimenter(u32, u32);
+
+/// Description of the switch manufacturer, hardware, and software.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwitchDesc {
+    pub mfr_desc: String,
+    pub hw_desc: String,
+    pub sw_desc: String,
+    pub serial_num: String,
+    pub dp_desc: String,
+}
+
+#[repr(packed)]
+struct OfpDesc([u8; 256], [u8; 256], [u8; 256], [u8; 32], [u8; 256]);
+
+impl SwitchDesc {
+    fn size_of(_: &SwitchDesc) -> usize {
+        size_of::<OfpDesc>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<SwitchDesc, ParseError> {
+        let mfr_desc = parse_fixed_string(bytes, 256)?;
+        let hw_desc = parse_fixed_string(bytes, 256)?;
+        let sw_desc = parse_fixed_string(bytes, 256)?;
+        let serial_num = parse_fixed_string(bytes, 32)?;
+        let dp_desc = parse_fixed_string(bytes, 256)?;
+        Ok(SwitchDesc {
+            mfr_desc: mfr_desc,
+            hw_desc: hw_desc,
+            sw_desc: sw_desc,
+            serial_num: serial_num,
+            dp_desc: dp_desc,
+        })
+    }
+
+    fn marshal(desc: SwitchDesc, bytes: &mut Vec<u8>) {
+        marshal_fixed_string(&desc.mfr_desc, 256, bytes);
+        marshal_fixed_string(&desc.hw_desc, 256, bytes);
+        marshal_fixed_string(&desc.sw_desc, 256, bytes);
+        marshal_fixed_string(&desc.serial_num, 32, bytes);
+        marshal_fixed_string(&desc.dp_desc, 256, bytes);
+    }
+}
+
+/// Request for individual flow statistics from the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowStatsRequest {
+    /// Table to read, or `OFPTT_ALL` for every table.
+    pub table_id: u8,
+    /// Restrict to flows outputting to this port.
+    pub out_port: Option<PseudoPort>,
+    /// Restrict to flows outputting to this group.
+    pub out_group: Option<u32>,
+    pub cookie: u64,
+    /// Bits of `cookie` that must match; 0 matches every flow.
+    pub cookie_mask: u64,
+    pub pattern: Pattern,
+}
+
+#[repr(packed)]
+struct OfpFlowStatsRequest(u8, [u8; 3], u32, u32, [u8; 4], u64, u64);
+
+impl FlowStatsRequest {
+    fn size_of(fsr: &FlowStatsRequest) -> usize {
+        size_of::<OfpFlowStatsRequest>() + Pattern::size_of(&fsr.pattern)
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<FlowStatsRequest, ParseError> {
+        let table_id = bytes.read_u8()?;
+        bytes.consume(3);
+        let out_port = PseudoPort::of_int(bytes.read_u32::<BigEndian>()?)?;
+        let out_group = of_wildcard(bytes.read_u32::<BigEndian>()?, OFPG_ANY);
+        bytes.consume(4);
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let cookie_mask = bytes.read_u64::<BigEndian>()?;
+        let pattern = Pattern::parse(bytes)?;
+        Ok(FlowStatsRequest {
+            table_id: table_id,
+            out_port: out_port,
+            out_group: out_group,
+            cookie: cookie,
+            cookie_mask: cookie_mask,
+            pattern: pattern,
+        })
+    }
+
+    fn marshal(fsr: FlowStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u8(fsr.table_id).unwrap();
+        write_pad(3, bytes);
+        PseudoPort::marshal_option(fsr.out_port, bytes);
+        bytes.write_u32::<BigEndian>(fsr.out_group.unwrap_or(OFPG_ANY)).unwrap();
+        write_pad(4, bytes);
+        bytes.write_u64::<BigEndian>(fsr.cookie).unwrap();
+        bytes.write_u64::<BigEndian>(fsr.cookie_mask).unwrap();
+        Pattern::marshal(fsr.pattern, bytes);
+    }
+}
+
+/// Statistics of an individual flow installed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowStats {
+    pub table_id: u8,
+    pub duration_sec: u32,
+    pub duration_nsec: u32,
+    pub priority: u16,
+    pub idle_timeout: Timeout,
+    pub hard_timeout: Timeout,
+    pub flags: FlowModFlags,
+    pub cookie: u64,
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub pattern: Pattern,
+    pub instructions: Vec<Instruction>,
+}
+
+#[repr(packed)]
+struct OfpFlowStats(u16, u8, u8, u32, u32, u16, u16, u16, u16, [u8; 4], u64, u64, u64);
+
+impl FlowStats {
+    fn size_of(fs: &FlowStats) -> usize {
+        size_of::<OfpFlowStats>() + Pattern::size_of(&fs.pattern) +
+        Instruction::size_of_sequence(&fs.instructions)
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<FlowStats, ParseError> {
+        let length = bytes.read_u16::<BigEndian>()? as usize;
+        let table_id = bytes.read_u8()?;
+        bytes.consume(1);
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        let priority = bytes.read_u16::<BigEndian>()?;
+        let idle = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let hard = Timeout::of_int(bytes.read_u16::<BigEndian>()?);
+        let flags = FlowModFlags::of_int(bytes.read_u16::<BigEndian>()?);
+        bytes.consume(4);
+        let cookie = bytes.read_u64::<BigEndian>()?;
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let pattern = Pattern::parse(bytes)?;
+        let header_len = size_of::<OfpFlowStats>() + Pattern::size_of(&pattern);
+        if length < header_len {
+            return Err(ParseError::BadLength("ofp_flow_stats", length));
+        }
+        let mut instructions_bytes = sub_cursor(bytes, length - header_len)?;
+        let instructions = Instruction::parse_sequence(&mut instructions_bytes)?;
+        Ok(FlowStats {
+            table_id: table_id,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+            priority: priority,
+            idle_timeout: idle,
+            hard_timeout: hard,
+            flags: flags,
+            cookie: cookie,
+            packet_count: packet_count,
+            byte_count: byte_count,
+            pattern: pattern,
+            instructions: instructions,
+        })
+    }
+
+    fn marshal(fs: FlowStats, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(FlowStats::size_of(&fs) as u16).unwrap();
+        bytes.write_u8(fs.table_id).unwrap();
+        bytes.write_u8(0).unwrap();
+        bytes.write_u32::<BigEndian>(fs.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(fs.duration_nsec).unwrap();
+        bytes.write_u16::<BigEndian>(fs.priority).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fs.idle_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(Timeout::to_int(fs.hard_timeout)).unwrap();
+        bytes.write_u16::<BigEndian>(FlowModFlags::to_int(&fs.flags)).unwrap();
+        write_pad(4, bytes);
+        bytes.write_u64::<BigEndian>(fs.cookie).unwrap();
+        bytes.write_u64::<BigEndian>(fs.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(fs.byte_count).unwrap();
+        Pattern::marshal(fs.pattern, bytes);
+        for ins in fs.instructions {
+            Instruction::marshal(ins, bytes)
+        }
+    }
+}
+
+/// Aggregate statistics of all flows matching an aggregate multipart request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateStats {
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub flow_count: u32,
+}
+
+#[repr(packed)]
+struct OfpAggregateStatsReply(u64, u64, u32, [u8; 4]);
+
+impl AggregateStats {
+    fn size_of(_: &AggregateStats) -> usize {
+        size_of::<OfpAggregateStatsReply>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<AggregateStats, ParseError> {
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let flow_count = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(AggregateStats {
+            packet_count: packet_count,
+            byte_count: byte_count,
+            flow_count: flow_count,
+        })
+    }
+
+    fn marshal(agg: AggregateStats, bytes: &mut Vec<u8>) {
+        bytes.write_u64::<BigEndian>(agg.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(agg.byte_count).unwrap();
+        bytes.write_u32::<BigEndian>(agg.flow_count).unwrap();
+        write_pad(4, bytes);
+    }
+}
+
+/// Statistics of a flow table in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableStats {
+    pub table_id: u8,
+    pub active_count: u32,
+    pub lookup_count: u64,
+    pub matched_count: u64,
+}
+
+#[repr(packed)]
+struct OfpTableStats(u8, [u8; 3], u32, u64, u64);
+
+impl TableStats {
+    fn size_of(_: &TableStats) -> usize {
+        size_of::<OfpTableStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<TableStats, ParseError> {
+        let table_id = bytes.read_u8()?;
+        bytes.consume(3);
+        let active_count = bytes.read_u32::<BigEndian>()?;
+        let lookup_count = bytes.read_u64::<BigEndian>()?;
+        let matched_count = bytes.read_u64::<BigEndian>()?;
+        Ok(TableStats {
+            table_id: table_id,
+            active_count: active_count,
+            lookup_count: lookup_count,
+            matched_count: matched_count,
+        })
+    }
+
+    fn marshal(ts: TableStats, bytes: &mut Vec<u8>) {
+        bytes.write_u8(ts.table_id).unwrap();
+        write_pad(3, bytes);
+        bytes.write_u32::<BigEndian>(ts.active_count).unwrap();
+        bytes.write_u64::<BigEndian>(ts.lookup_count).unwrap();
+        bytes.write_u64::<BigEndian>(ts.matched_count).unwrap();
+    }
+}
+
+/// Request for statistics of a port, or of all ports if `port_no` is `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortStatsRequest {
+    pub port_no: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpPortStatsRequest(u32, [u8; 4]);
+
+impl PortStatsRequest {
+    fn size_of(_: &PortStatsRequest) -> usize {
+        size_of::<OfpPortStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortStatsRequest, ParseError> {
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(PortStatsRequest { port_no: of_wildcard(port_no, OfpPort::OFPPAny as u32) })
+    }
+
+    fn marshal(psr: PortStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(psr.port_no.unwrap_or(OfpPort::OFPPAny as u32)).unwrap();
+        write_pad(4, bytes);
+    }
+}
+
+/// Statistics of a port in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortStats {
+    pub port_no: u32,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_frame_err: u64,
+    pub rx_over_err: u64,
+    pub rx_crc_err: u64,
+    pub collisions: u64,
+    /// Time the port has been alive, in seconds.
+    pub duration_sec: u32,
+    /// Time the port has been alive beyond `duration_sec`, in nanoseconds.
+    pub duration_nsec: u32,
+}
+
+#[repr(packed)]
+struct OfpPortStats(u32, [u8; 4], [u64; 12], u32, u32);
+
+impl PortStats {
+    fn size_of(_: &PortStats) -> usize {
+        size_of::<OfpPortStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<PortStats, ParseError> {
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(PortStats {
+            port_no: port_no,
+            rx_packets: bytes.read_u64::<BigEndian>()?,
+            tx_packets: bytes.read_u64::<BigEndian>()?,
+            rx_bytes: bytes.read_u64::<BigEndian>()?,
+            tx_bytes: bytes.read_u64::<BigEndian>()?,
+            rx_dropped: bytes.read_u64::<BigEndian>()?,
+            tx_dropped: bytes.read_u64::<BigEndian>()?,
+            rx_errors: bytes.read_u64::<BigEndian>()?,
+            tx_errors: bytes.read_u64::<BigEndian>()?,
+            rx_frame_err: bytes.read_u64::<BigEndian>()?,
+            rx_over_err: bytes.read_u64::<BigEndian>()?,
+            rx_crc_err: bytes.read_u64::<BigEndian>()?,
+            collisions: bytes.read_u64::<BigEndian>()?,
+            duration_sec: bytes.read_u32::<BigEndian>()?,
+            duration_nsec: bytes.read_u32::<BigEndian>()?,
+        })
+    }
+
+    fn marshal(ps: PortStats, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(ps.port_no).unwrap();
+        write_pad(4, bytes);
+        for count in &[ps.rx_packets,
+                       ps.tx_packets,
+                       ps.rx_bytes,
+                       ps.tx_bytes,
+                       ps.rx_dropped,
+                       ps.tx_dropped,
+                       ps.rx_errors,
+                       ps.tx_errors,
+                       ps.rx_frame_err,
+                       ps.rx_over_err,
+                       ps.rx_crc_err,
+                       ps.collisions] {
+            bytes.write_u64::<BigEndian>(*count).unwrap();
+        }
+        bytes.write_u32::<BigEndian>(ps.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(ps.duration_nsec).unwrap();
+    }
+}
+
+/// Request for statistics of queue `queue_id` on port `port_no`. `None` requests all ports or
+/// all queues, respectively.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStatsRequest {
+    pub port_no: Option<u32>,
+    pub queue_id: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpQueueStatsRequest(u32, u32);
+
+const OFPQ_ALL: u32 = 0xffffffff;
+
+impl QueueStatsRequest {
+    fn size_of(_: &QueueStatsRequest) -> usize {
+        size_of::<OfpQueueStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<QueueStatsRequest, ParseError> {
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        let queue_id = bytes.read_u32::<BigEndian>()?;
+        Ok(QueueStatsRequest {
+            port_no: of_wildcard(port_no, OfpPort::OFPPAny as u32),
+            queue_id: of_wildcard(queue_id, OFPQ_ALL),
+        })
+    }
+
+    fn marshal(qsr: QueueStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(qsr.port_no.unwrap_or(OfpPort::OFPPAny as u32)).unwrap();
+        bytes.write_u32::<BigEndian>(qsr.queue_id.unwrap_or(OFPQ_ALL)).unwrap();
+    }
+}
+
+/// Statistics of a queue attached to a port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueueStats {
+    pub port_no: u32,
+    pub queue_id: u32,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    /// Time the queue has been alive, in seconds.
+    pub duration_sec: u32,
+    /// Time the queue has been alive beyond `duration_sec`, in nanoseconds.
+    pub duration_nsec: u32,
+}
+
+#[repr(packed)]
+struct OfpQueueStats(u32, u32, u64, u64, u64, u32, u32);
+
+impl QueueStats {
+    fn size_of(_: &QueueStats) -> usize {
+        size_of::<OfpQueueStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<QueueStats, ParseError> {
+        let port_no = bytes.read_u32::<BigEndian>()?;
+        let queue_id = bytes.read_u32::<BigEndian>()?;
+        let tx_bytes = bytes.read_u64::<BigEndian>()?;
+        let tx_packets = bytes.read_u64::<BigEndian>()?;
+        let tx_errors = bytes.read_u64::<BigEndian>()?;
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        Ok(QueueStats {
+            port_no: port_no,
+            queue_id: queue_id,
+            tx_bytes: tx_bytes,
+            tx_packets: tx_packets,
+            tx_errors: tx_errors,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+        })
+    }
+
+    fn marshal(qs: QueueStats, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(qs.port_no).unwrap();
+        bytes.write_u32::<BigEndian>(qs.queue_id).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_bytes).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_packets).unwrap();
+        bytes.write_u64::<BigEndian>(qs.tx_errors).unwrap();
+        bytes.write_u32::<BigEndian>(qs.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(qs.duration_nsec).unwrap();
+    }
+}
+
+/// Request for statistics of a group, or of all groups if `group_id` is `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupStatsRequest {
+    pub group_id: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpGroupStatsRequest(u32, [u8; 4]);
+
+impl GroupStatsRequest {
+    fn size_of(_: &GroupStatsRequest) -> usize {
+        size_of::<OfpGroupStatsRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<GroupStatsRequest, ParseError> {
+        let group_id = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(GroupStatsRequest { group_id: of_wildcard(group_id, OFPG_ALL) })
+    }
+
+    fn marshal(gsr: GroupStatsRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(gsr.group_id.unwrap_or(OFPG_ALL)).unwrap();
+        write_pad(4, bytes);
+    }
+}
+
+/// Counters of packets and bytes processed by a bucket of a group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketCounter {
+    pub packet_count: u64,
+    pub byte_count: u64,
+}
+
+#[repr(packed)]
+struct OfpBucketCounter(u64, u64);
+
+/// Statistics of a group in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupStats {
+    pub group_id: u32,
+    /// Number of flows or groups forwarding to this group.
+    pub ref_count: u32,
+    pub packet_count: u64,
+    pub byte_count: u64,
+    pub duration_sec: u32,
+    pub duration_nsec: u32,
+    /// Counters of each bucket, in the order of the group's buckets.
+    pub bucket_stats: Vec<BucketCounter>,
+}
+
+#[repr(packed)]
+struct OfpGroupStats(u16, [u8; 2], u32, u32, [u8; 4], u64, u64, u32, u32);
+
+impl GroupStats {
+    fn size_of(gs: &GroupStats) -> usize {
+        size_of::<OfpGroupStats>() + gs.bucket_stats.len() * size_of::<OfpBucketCounter>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<GroupStats, ParseError> {
+        let length = bytes.read_u16::<BigEndian>()? as usize;
+        bytes.consume(2);
+        let group_id = bytes.read_u32::<BigEndian>()?;
+        let ref_count = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        let packet_count = bytes.read_u64::<BigEndian>()?;
+        let byte_count = bytes.read_u64::<BigEndian>()?;
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        if length < size_of::<OfpGroupStats>() ||
+           (length - size_of::<OfpGroupStats>()) % size_of::<OfpBucketCounter>() != 0 {
+            return Err(ParseError::BadLength("ofp_group_stats", length));
+        }
+        let mut bucket_stats = vec![];
+        for _ in 0..(length - size_of::<OfpGroupStats>()) / size_of::<OfpBucketCounter>() {
+            bucket_stats.push(BucketCounter {
+                packet_count: bytes.read_u64::<BigEndian>()?,
+                byte_count: bytes.read_u64::<BigEndian>()?,
+            })
+        }
+        Ok(GroupStats {
+            group_id: group_id,
+            ref_count: ref_count,
+            packet_count: packet_count,
+            byte_count: byte_count,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+            bucket_stats: bucket_stats,
+        })
+    }
+
+    fn marshal(gs: GroupStats, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(GroupStats::size_of(&gs) as u16).unwrap();
+        write_pad(2, bytes);
+        bytes.write_u32::<BigEndian>(gs.group_id).unwrap();
+        bytes.write_u32::<BigEndian>(gs.ref_count).unwrap();
+        write_pad(4, bytes);
+        bytes.write_u64::<BigEndian>(gs.packet_count).unwrap();
+        bytes.write_u64::<BigEndian>(gs.byte_count).unwrap();
+        bytes.write_u32::<BigEndian>(gs.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(gs.duration_nsec).unwrap();
+        for bc in gs.bucket_stats {
+            bytes.write_u64::<BigEndian>(bc.packet_count).unwrap();
+            bytes.write_u64::<BigEndian>(bc.byte_count).unwrap();
+        }
+    }
+}
+
+/// Description of a group installed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupDesc {
+    pub typ: GroupType,
+    pub group_id: u32,
+    pub buckets: Vec<Bucket>,
+}
+
+#[repr(packed)]
+struct OfpGroupDesc(u16, u8, u8, u32);
+
+impl GroupDesc {
+    fn size_of(gd: &GroupDesc) -> usize {
+        let buckets: usize = gd.buckets.iter().map(Bucket::size_of).sum();
+        size_of::<OfpGroupDesc>() + buckets
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<GroupDesc, ParseError> {
+        let length = bytes.read_u16::<BigEndian>()? as usize;
+        let typ = GroupType::of_int(bytes.read_u8()?)?;
+        bytes.consume(1);
+        let group_id = bytes.read_u32::<BigEndian>()?;
+        if length < size_of::<OfpGroupDesc>() {
+            return Err(ParseError::BadLength("ofp_group_desc", length));
+        }
+        let mut buckets_bytes = sub_cursor(bytes, length - size_of::<OfpGroupDesc>())?;
+        let buckets = Bucket::parse_sequence(&mut buckets_bytes)?;
+        Ok(GroupDesc {
+            typ: typ,
+            group_id: group_id,
+            buckets: buckets,
+        })
+    }
+
+    fn marshal(gd: GroupDesc, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(GroupDesc::size_of(&gd) as u16).unwrap();
+        bytes.write_u8(gd.typ as u8).unwrap();
+        bytes.write_u8(0).unwrap();
+        bytes.write_u32::<BigEndian>(gd.group_id).unwrap();
+        for b in gd.buckets {
+            Bucket::marshal(b, bytes)
+        }
+    }
+}
+
+/// Group capabilities supported by the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupCapabilities {
+    /// Buckets of `GroupType::Select` groups may have weights other than 1.
+    pub select_weight: bool,
+    /// `GroupType::Select` groups take bucket liveness into account.
+    pub select_liveness: bool,
+    /// Buckets may forward to other groups.
+    pub chaining: bool,
+    /// Chaining is checked for loops and deleted groups.
+    pub chaining_checks: bool,
+}
+
+impl GroupCapabilities {
+    fn of_int(d: u32) -> GroupCapabilities {
+        GroupCapabilities {
+            select_weight: test_bit(0, d as u64),
+            select_liveness: test_bit(1, d as u64),
+            chaining: test_bit(2, d as u64),
+            chaining_checks: test_bit(3, d as u64),
+        }
+    }
+
+    fn to_int(gc: &GroupCapabilities) -> u32 {
+        let ret = 0u32;
+        let ret = bit(0, ret as u64, gc.select_weight) as u32;
+        let ret = bit(1, ret as u64, gc.select_liveness) as u32;
+        let ret = bit(2, ret as u64, gc.chaining) as u32;
+        bit(3, ret as u64, gc.chaining_checks) as u32
+    }
+}
+
+/// Group features of the datapath.
+///
+/// `max_groups` and `actions` are indexed by `GroupType as usize`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupFeatures {
+    /// Bitmap of supported group types, with bit `GroupType as u8` set for each.
+    pub types: u32,
+    pub capabilities: GroupCapabilities,
+    /// Maximum number of groups of each type.
+    pub max_groups: [u32; 4],
+    /// Bitmap of action types supported by groups of each type, with bit `OFPAT_*` set for each.
+    pub actions: [u32; 4],
+}
+
+#[repr(packed)]
+struct OfpGroupFeatures(u32, u32, [u32; 4], [u32; 4]);
+
+impl GroupFeatures {
+    fn size_of(_: &GroupFeatures) -> usize {
+        size_of::<OfpGroupFeatures>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<GroupFeatures, ParseError> {
+        let types = bytes.read_u32::<BigEndian>()?;
+        let capabilities = GroupCapabilities::of_int(bytes.read_u32::<BigEndian>()?);
+        let mut max_groups = [0; 4];
+        bytes.read_u32_into::<BigEndian>(&mut max_groups)?;
+        let mut actions = [0; 4];
+        bytes.read_u32_into::<BigEndian>(&mut actions)?;
+        Ok(GroupFeatures {
+            types: types,
+            capabilities: capabilities,
+            max_groups: max_groups,
+            actions: actions,
+        })
+    }
+
+    fn marshal(gf: GroupFeatures, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(gf.types).unwrap();
+        bytes.write_u32::<BigEndian>(GroupCapabilities::to_int(&gf.capabilities)).unwrap();
+        for n in gf.max_groups.iter().chain(gf.actions.iter()) {
+            bytes.write_u32::<BigEndian>(*n).unwrap();
+        }
+    }
+}
+
+/// Request for statistics or configuration of a meter, or of all meters if `meter_id` is
+/// `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterMultipartRequest {
+    pub meter_id: Option<u32>,
+}
+
+#[repr(packed)]
+struct OfpMeterMultipartRequest(u32, [u8; 4]);
+
+impl MeterMultipartRequest {
+    fn size_of(_: &MeterMultipartRequest) -> usize {
+        size_of::<OfpMeterMultipartRequest>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<MeterMultipartRequest, ParseError> {
+        let meter_id = bytes.read_u32::<BigEndian>()?;
+        bytes.consume(4);
+        Ok(MeterMultipartRequest { meter_id: of_wildcard(meter_id, OFPM_ALL) })
+    }
+
+    fn marshal(mmr: MeterMultipartRequest, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(mmr.meter_id.unwrap_or(OFPM_ALL)).unwrap();
+        write_pad(4, bytes);
+    }
+}
+
+/// Counters of packets and bytes processed by a band of a meter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterBandStats {
+    pub packet_band_count: u64,
+    pub byte_band_count: u64,
+}
+
+#[repr(packed)]
+struct OfpMeterBandStats(u64, u64);
+
+/// Statistics of a meter in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterStats {
+    pub meter_id: u32,
+    /// Number of flows bound to the meter.
+    pub flow_count: u32,
+    pub packet_in_count: u64,
+    pub byte_in_count: u64,
+    pub duration_sec: u32,
+    pub duration_nsec: u32,
+    /// Counters of each band, in the order of the meter's bands.
+    pub band_stats: Vec<MeterBandStats>,
+}
+
+#[repr(packed)]
+struct OfpMeterStats(u32, u16, [u8; 6], u32, u64, u64, u32, u32);
+
+impl MeterStats {
+    fn size_of(ms: &MeterStats) -> usize {
+        size_of::<OfpMeterStats>() + ms.band_stats.len() * size_of::<OfpMeterBandStats>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<MeterStats, ParseError> {
+        let meter_id = bytes.read_u32::<BigEndian>()?;
+        let length = bytes.read_u16::<BigEndian>()? as usize;
+        bytes.consume(6);
+        let flow_count = bytes.read_u32::<BigEndian>()?;
+        let packet_in_count = bytes.read_u64::<BigEndian>()?;
+        let byte_in_count = bytes.read_u64::<BigEndian>()?;
+        let duration_sec = bytes.read_u32::<BigEndian>()?;
+        let duration_nsec = bytes.read_u32::<BigEndian>()?;
+        if length < size_of::<OfpMeterStats>() ||
+           (length - size_of::<OfpMeterStats>()) % size_of::<OfpMeterBandStats>() != 0 {
+            return Err(ParseError::BadLength("ofp_meter_stats", length));
+        }
+        let mut band_stats = vec![];
+        for _ in 0..(length - size_of::<OfpMeterStats>()) / size_of::<OfpMeterBandStats>() {
+            band_stats.push(MeterBandStats {
+                packet_band_count: bytes.read_u64::<BigEndian>()?,
+                byte_band_count: bytes.read_u64::<BigEndian>()?,
+            })
+        }
+        Ok(MeterStats {
+            meter_id: meter_id,
+            flow_count: flow_count,
+            packet_in_count: packet_in_count,
+            byte_in_count: byte_in_count,
+            duration_sec: duration_sec,
+            duration_nsec: duration_nsec,
+            band_stats: band_stats,
+        })
+    }
+
+    fn marshal(ms: MeterStats, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(ms.meter_id).unwrap();
+        bytes.write_u16::<BigEndian>(MeterStats::size_of(&ms) as u16).unwrap();
+        write_pad(6, bytes);
+        bytes.write_u32::<BigEndian>(ms.flow_count).unwrap();
+        bytes.write_u64::<BigEndian>(ms.packet_in_count).unwrap();
+        bytes.write_u64::<BigEndian>(ms.byte_in_count).unwrap();
+        bytes.write_u32::<BigEndian>(ms.duration_sec).unwrap();
+        bytes.write_u32::<BigEndian>(ms.duration_nsec).unwrap();
+        for bs in ms.band_stats {
+            bytes.write_u64::<BigEndian>(bs.packet_band_count).unwrap();
+            bytes.write_u64::<BigEndian>(bs.byte_band_count).unwrap();
+        }
+    }
+}
+
+/// Configuration of a meter installed in the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterConfig {
+    pub flags: MeterFlags,
+    pub meter_id: u32,
+    pub bands: Vec<MeterBand>,
+}
+
+#[repr(packed)]
+struct OfpMeterConfig(u16, u16, u32);
+
+impl MeterConfig {
+    fn size_of(mc: &MeterConfig) -> usize {
+        size_of::<OfpMeterConfig>() + MeterBand::size_of_sequence(&mc.bands)
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<MeterConfig, ParseError> {
+        let length = bytes.read_u16::<BigEndian>()? as usize;
+        let flags = MeterFlags::of_int(bytes.read_u16::<BigEndian>()?);
+        let meter_id = bytes.read_u32::<BigEndian>()?;
+        if length < size_of::<OfpMeterConfig>() {
+            return Err(ParseError::BadLength("ofp_meter_config", length));
+        }
+        let mut bands_bytes = sub_cursor(bytes, length - size_of::<OfpMeterConfig>())?;
+        let bands = MeterBand::parse_sequence(&mut bands_bytes)?;
+        Ok(MeterConfig {
+            flags: flags,
+            meter_id: meter_id,
+            bands: bands,
+        })
+    }
+
+    fn marshal(mc: MeterConfig, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(MeterConfig::size_of(&mc) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(MeterFlags::to_int(&mc.flags)).unwrap();
+        bytes.write_u32::<BigEndian>(mc.meter_id).unwrap();
+        for band in mc.bands {
+            MeterBand::marshal(band, bytes)
+        }
+    }
+}
+
+/// Meter features of the datapath.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeterFeatures {
+    pub max_meter: u32,
+    /// Bitmap of supported band types, with bit `OFPMBT_*` set for each.
+    pub band_types: u32,
+    /// Supported `MeterFlags`.
+    pub capabilities: MeterFlags,
+    /// Maximum number of bands per meter.
+    pub max_bands: u8,
+    /// Maximum color value.
+    pub max_color: u8,
+}
+
+#[repr(packed)]
+struct OfpMeterFeatures(u32, u32, u32, u8, u8, [u8; 2]);
+
+impl MeterFeatures {
+    fn size_of(_: &MeterFeatures) -> usize {
+        size_of::<OfpMeterFeatures>()
+    }
+
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<MeterFeatures, ParseError> {
+        let max_meter = bytes.read_u32::<BigEndian>()?;
+        let band_types = bytes.read_u32::<BigEndian>()?;
+        let capabilities = MeterFlags::of_int(bytes.read_u32::<BigEndian>()? as u16);
+        let max_bands = bytes.read_u8()?;
+        let max_color = bytes.read_u8()?;
+        bytes.consume(2);
+        Ok(MeterFeatures {
+            max_meter: max_meter,
+            band_types: band_types,
+            capabilities: capabilities,
+            max_bands: max_bands,
+            max_color: max_color,
+        })
+    }
+
+    fn marshal(mf: MeterFeatures, bytes: &mut Vec<u8>) {
+        bytes.write_u32::<BigEndian>(mf.max_meter).unwrap();
+        bytes.write_u32::<BigEndian>(mf.band_types).unwrap();
+        bytes.write_u32::<BigEndian>(MeterFlags::to_int(&mf.capabilities) as u32).unwrap();
+        bytes.write_u8(mf.max_bands).unwrap();
+        bytes.write_u8(mf.max_color).unwrap();
+        write_pad(2, bytes);
+    }
+}
+
+/// Parse every element of type `T` remaining in `bytes` with `parse`.
+fn parse_all<T, F>(bytes: &mut Cursor<Vec<u8>>, parse: F) -> Result<Vec<T>, ParseError>
+    where F: Fn(&mut Cursor<Vec<u8>>) -> Result<T, ParseError>
+{
+    let mut v = vec![];
+    while (bytes.position() as usize) < bytes.get_ref().len() {
+        v.push(parse(bytes)?)
+    }
+    Ok(v)
+}
+
+/// Body of a multipart request, by type of statistics or state requested.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MultipartReqBody {
+    Desc,
+    Flow(FlowStatsRequest),
+    /// Aggregate statistics of all flows matching the request, which shares the layout of a
+    /// flow statistics request.
+    Aggregate(FlowStatsRequest),
+    Table,
+    PortStats(PortStatsRequest),
+    Queue(QueueStatsRequest),
+    Group(GroupStatsRequest),
+    GroupDesc,
+    GroupFeatures,
+    Meter(MeterMultipartRequest),
+    MeterConfig(MeterMultipartRequest),
+    MeterFeatures,
+    /// Description of every port of the datapath.
+    PortDesc,
+    /// Experimenter-defined request with the given experimenter id, type, and body.
+    Experimenter {
+        experimenter: u32,
+        exp_type: u32,
+        body: Vec<u8>,
+    },
+}
+
+/// Multipart request (controller -> datapath)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipartReq {
+    /// More requests belonging to the same multipart request follow this one.
+    pub more: bool,
+    pub body: MultipartReqBody,
+}
+
+impl MultipartReq {
+    /// Create a single-part `MultipartReq` with body `body`.
+    pub fn new(body: MultipartReqBody) -> MultipartReq {
+        MultipartReq {
+            more: false,
+            body: body,
+        }
+    }
+
+    fn type_code(req: &MultipartReq) -> OfpMultipartType {
+        match req.body {
+            MultipartReqBody::Desc => OfpMultipartType::OFPMPDesc,
+            MultipartReqBody::Flow(_) => OfpMultipartType::OFPMPFlow,
+            MultipartReqBody::Aggregate(_) => OfpMultipartType::OFPMPAggregate,
+            MultipartReqBody::Table => OfpMultipartType::OFPMPTable,
+            MultipartReqBody::PortStats(_) => OfpMultipartType::OFPMPPortStats,
+            MultipartReqBody::Queue(_) => OfpMultipartType::OFPMPQueue,
+            MultipartReqBody::Group(_) => OfpMultipartType::OFPMPGroup,
+            MultipartReqBody::GroupDesc => OfpMultipartType::OFPMPGroupDesc,
+            MultipartReqBody::GroupFeatures => OfpMultipartType::OFPMPGroupFeatures,
+            MultipartReqBody::Meter(_) => OfpMultipartType::OFPMPMeter,
+            MultipartReqBody::MeterConfig(_) => OfpMultipartType::OFPMPMeterConfig,
+            MultipartReqBody::MeterFeatures => OfpMultipartType::OFPMPMeterFeatures,
+            MultipartReqBody::PortDesc => OfpMultipartType::OFPMPPortDesc,
+            MultipartReqBody::Experimenter { .. } => OfpMultipartType::OFPMPExperimenter,
+        }
+    }
+}
+
+impl MessageType for MultipartReq {
+    fn size_of(req: &MultipartReq) -> usize {
+        let body = match req.body {
+            MultipartReqBody::Desc |
+            MultipartReqBody::Table |
+            MultipartReqBody::GroupDesc |
+            MultipartReqBody::GroupFeatures |
+            MultipartReqBody::MeterFeatures |
+            MultipartReqBody::PortDesc => 0,
+            MultipartReqBody::Flow(ref fsr) |
+            MultipartReqBody::Aggregate(ref fsr) => FlowStatsRequest::size_of(fsr),
+            MultipartReqBody::PortStats(ref psr) => PortStatsRequest::size_of(psr),
+            MultipartReqBody::Queue(ref qsr) => QueueStatsRequest::size_of(qsr),
+            MultipartReqBody::Group(ref gsr) => GroupStatsRequest::size_of(gsr),
+            MultipartReqBody::Meter(ref mmr) |
+            MultipartReqBody::MeterConfig(ref mmr) => MeterMultipartRequest::size_of(mmr),
+            MultipartReqBody::Experimenter { ref body, .. } => {
+                size_of::<OfpMultipartExperimenter>() + body.len()
+            }
+        };
+        size_of::<OfpMultipartMsg>() + body
+    }
+
+    fn parse(buf: &[u8]) -> Result<MultipartReq, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let typ = bytes.read_u16::<BigEndian>()?;
+        let flags = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(4);
+        let body = match typ {
+            t if t == (OfpMultipartType::OFPMPDesc as u16) => MultipartReqBody::Desc,
+            t if t == (OfpMultipartType::OFPMPFlow as u16) => {
+                MultipartReqBody::Flow(FlowStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPAggregate as u16) => {
+                MultipartReqBody::Aggregate(FlowStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPTable as u16) => MultipartReqBody::Table,
+            t if t == (OfpMultipartType::OFPMPPortStats as u16) => {
+                MultipartReqBody::PortStats(PortStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPQueue as u16) => {
+                MultipartReqBody::Queue(QueueStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPGroup as u16) => {
+                MultipartReqBody::Group(GroupStatsRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPGroupDesc as u16) => MultipartReqBody::GroupDesc,
+            t if t == (OfpMultipartType::OFPMPGroupFeatures as u16) => {
+                MultipartReqBody::GroupFeatures
+            }
+            t if t == (OfpMultipartType::OFPMPMeter as u16) => {
+                MultipartReqBody::Meter(MeterMultipartRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPMeterConfig as u16) => {
+                MultipartReqBody::MeterConfig(MeterMultipartRequest::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPMeterFeatures as u16) => {
+                MultipartReqBody::MeterFeatures
+            }
+            t if t == (OfpMultipartType::OFPMPPortDesc as u16) => MultipartReqBody::PortDesc,
+            t if t == (OfpMultipartType::OFPMPExperimenter as u16) => {
+                let experimenter = bytes.read_u32::<BigEndian>()?;
+                let exp_type = bytes.read_u32::<BigEndian>()?;
+                MultipartReqBody::Experimenter {
+                    experimenter: experimenter,
+                    exp_type: exp_type,
+                    body: bytes.fill_buf()?.to_vec(),
+                }
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_multipart_type", t as u64)),
+        };
+        Ok(MultipartReq {
+            more: test_bit(0, flags as u64),
+            body: body,
+        })
+    }
+
+    fn marshal(req: MultipartReq, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(MultipartReq::type_code(&req) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(bit(0, 0, req.more) as u16).unwrap();
+        write_pad(4, bytes);
+        match req.body {
+            MultipartReqBody::Desc |
+            MultipartReqBody::Table |
+            MultipartReqBody::GroupDesc |
+            MultipartReqBody::GroupFeatures |
+            MultipartReqBody::MeterFeatures |
+            MultipartReqBody::PortDesc => (),
+            MultipartReqBody::Flow(fsr) |
+            MultipartReqBody::Aggregate(fsr) => FlowStatsRequest::marshal(fsr, bytes),
+            MultipartReqBody::PortStats(psr) => PortStatsRequest::marshal(psr, bytes),
+            MultipartReqBody::Queue(qsr) => QueueStatsRequest::marshal(qsr, bytes),
+            MultipartReqBody::Group(gsr) => GroupStatsRequest::marshal(gsr, bytes),
+            MultipartReqBody::Meter(mmr) |
+            MultipartReqBody::MeterConfig(mmr) => MeterMultipartRequest::marshal(mmr, bytes),
+            MultipartReqBody::Experimenter { experimenter, exp_type, body } => {
+                bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                bytes.write_u32::<BigEndian>(exp_type).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// Body of a multipart reply, by type of statistics or state.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MultipartRespBody {
+    Desc(SwitchDesc),
+    Flow(Vec<FlowStats>),
+    Aggregate(AggregateStats),
+    Table(Vec<TableStats>),
+    PortStats(Vec<PortStats>),
+    Queue(Vec<QueueStats>),
+    Group(Vec<GroupStats>),
+    GroupDesc(Vec<GroupDesc>),
+    GroupFeatures(GroupFeatures),
+    Meter(Vec<MeterStats>),
+    MeterConfig(Vec<MeterConfig>),
+    MeterFeatures(MeterFeatures),
+    PortDesc(Vec<PortDesc>),
+    /// Experimenter-defined reply with the given experimenter id, type, and body.
+    Experimenter {
+        experimenter: u32,
+        exp_type: u32,
+        body: Vec<u8>,
+    },
+}
+
+/// Multipart reply (datapath -> controller)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipartResp {
+    /// More replies to the same request follow this one.
+    pub more: bool,
+    pub body: MultipartRespBody,
+}
+
+impl MultipartResp {
+    fn type_code(resp: &MultipartResp) -> OfpMultipartType {
+        match resp.body {
+            MultipartRespBody::Desc(_) => OfpMultipartType::OFPMPDesc,
+            MultipartRespBody::Flow(_) => OfpMultipartType::OFPMPFlow,
+            MultipartRespBody::Aggregate(_) => OfpMultipartType::OFPMPAggregate,
+            MultipartRespBody::Table(_) => OfpMultipartType::OFPMPTable,
+            MultipartRespBody::PortStats(_) => OfpMultipartType::OFPMPPortStats,
+            MultipartRespBody::Queue(_) => OfpMultipartType::OFPMPQueue,
+            MultipartRespBody::Group(_) => OfpMultipartType::OFPMPGroup,
+            MultipartRespBody::GroupDesc(_) => OfpMultipartType::OFPMPGroupDesc,
+            MultipartRespBody::GroupFeatures(_) => OfpMultipartType::OFPMPGroupFeatures,
+            MultipartRespBody::Meter(_) => OfpMultipartType::OFPMPMeter,
+            MultipartRespBody::MeterConfig(_) => OfpMultipartType::OFPMPMeterConfig,
+            MultipartRespBody::MeterFeatures(_) => OfpMultipartType::OFPMPMeterFeatures,
+            MultipartRespBody::PortDesc(_) => OfpMultipartType::OFPMPPortDesc,
+            MultipartRespBody::Experimenter { .. } => OfpMultipartType::OFPMPExperimenter,
+        }
+    }
+}
+
+impl MessageType for MultipartResp {
+    fn size_of(resp: &MultipartResp) -> usize {
+        let body: usize = match resp.body {
+            MultipartRespBody::Desc(ref desc) => SwitchDesc::size_of(desc),
+            MultipartRespBody::Flow(ref flows) => {
+                flows.iter().map(FlowStats::size_of).sum()
+            }
+            MultipartRespBody::Aggregate(ref agg) => AggregateStats::size_of(agg),
+            MultipartRespBody::Table(ref tables) => {
+                tables.iter().map(TableStats::size_of).sum()
+            }
+            MultipartRespBody::PortStats(ref ports) => {
+                ports.iter().map(PortStats::size_of).sum()
+            }
+            MultipartRespBody::Queue(ref queues) => {
+                queues.iter().map(QueueStats::size_of).sum()
+            }
+            MultipartRespBody::Group(ref groups) => {
+                groups.iter().map(GroupStats::size_of).sum()
+            }
+            MultipartRespBody::GroupDesc(ref groups) => {
+                groups.iter().map(GroupDesc::size_of).sum()
+            }
+            MultipartRespBody::GroupFeatures(ref gf) => GroupFeatures::size_of(gf),
+            MultipartRespBody::Meter(ref meters) => {
+                meters.iter().map(MeterStats::size_of).sum()
+            }
+            MultipartRespBody::MeterConfig(ref meters) => {
+                meters.iter().map(MeterConfig::size_of).sum()
+            }
+            MultipartRespBody::MeterFeatures(ref mf) => MeterFeatures::size_of(mf),
+            MultipartRespBody::PortDesc(ref ports) => {
+                ports.iter().map(PortDesc::size_of).sum()
+            }
+            MultipartRespBody::Experimenter { ref body, .. } => {
+                size_of::<OfpMultipartExperimenter>() + body.len()
+            }
+        };
+        size_of::<OfpMultipartMsg>() + body
+    }
+
+    fn parse(buf: &[u8]) -> Result<MultipartResp, ParseError> {
+        let mut bytes = Cursor::new(buf.to_vec());
+        let typ = bytes.read_u16::<BigEndian>()?;
+        let flags = bytes.read_u16::<BigEndian>()?;
+        bytes.consume(4);
+        let body = match typ {
+            t if t == (OfpMultipartType::OFPMPDesc as u16) => {
+                MultipartRespBody::Desc(SwitchDesc::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPFlow as u16) => {
+                MultipartRespBody::Flow(parse_all(&mut bytes, FlowStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPAggregate as u16) => {
+                MultipartRespBody::Aggregate(AggregateStats::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPTable as u16) => {
+                MultipartRespBody::Table(parse_all(&mut bytes, TableStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPPortStats as u16) => {
+                MultipartRespBody::PortStats(parse_all(&mut bytes, PortStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPQueue as u16) => {
+                MultipartRespBody::Queue(parse_all(&mut bytes, QueueStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPGroup as u16) => {
+                MultipartRespBody::Group(parse_all(&mut bytes, GroupStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPGroupDesc as u16) => {
+                MultipartRespBody::GroupDesc(parse_all(&mut bytes, GroupDesc::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPGroupFeatures as u16) => {
+                MultipartRespBody::GroupFeatures(GroupFeatures::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPMeter as u16) => {
+                MultipartRespBody::Meter(parse_all(&mut bytes, MeterStats::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPMeterConfig as u16) => {
+                MultipartRespBody::MeterConfig(parse_all(&mut bytes, MeterConfig::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPMeterFeatures as u16) => {
+                MultipartRespBody::MeterFeatures(MeterFeatures::parse(&mut bytes)?)
+            }
+            t if t == (OfpMultipartType::OFPMPPortDesc as u16) => {
+                MultipartRespBody::PortDesc(parse_all(&mut bytes, PortDesc::parse)?)
+            }
+            t if t == (OfpMultipartType::OFPMPExperimenter as u16) => {
+                let experimenter = bytes.read_u32::<BigEndian>()?;
+                let exp_type = bytes.read_u32::<BigEndian>()?;
+                MultipartRespBody::Experimenter {
+                    experimenter: experimenter,
+                    exp_type: exp_type,
+                    body: bytes.fill_buf()?.to_vec(),
+                }
+            }
+            t => return Err(ParseError::UnsupportedType("ofp_multipart_type", t as u64)),
+        };
+        Ok(MultipartResp {
+            more: test_bit(0, flags as u64),
+            body: body,
+        })
+    }
+
+    fn marshal(resp: MultipartResp, bytes: &mut Vec<u8>) {
+        bytes.write_u16::<BigEndian>(MultipartResp::type_code(&resp) as u16).unwrap();
+        bytes.write_u16::<BigEndian>(bit(0, 0, resp.more) as u16).unwrap();
+        write_pad(4, bytes);
+        match resp.body {
+            MultipartRespBody::Desc(desc) => SwitchDesc::marshal(desc, bytes),
+            MultipartRespBody::Flow(flows) => {
+                for fs in flows {
+                    FlowStats::marshal(fs, bytes)
+                }
+            }
+            MultipartRespBody::Aggregate(agg) => AggregateStats::marshal(agg, bytes),
+            MultipartRespBody::Table(tables) => {
+                for ts in tables {
+                    TableStats::marshal(ts, bytes)
+                }
+            }
+            MultipartRespBody::PortStats(ports) => {
+                for ps in ports {
+                    PortStats::marshal(ps, bytes)
+                }
+            }
+            MultipartRespBody::Queue(queues) => {
+                for qs in queues {
+                    QueueStats::marshal(qs, bytes)
+                }
+            }
+            MultipartRespBody::Group(groups) => {
+                for gs in groups {
+                    GroupStats::marshal(gs, bytes)
+                }
+            }
+            MultipartRespBody::GroupDesc(groups) => {
+                for gd in groups {
+                    GroupDesc::marshal(gd, bytes)
+                }
+            }
+            MultipartRespBody::GroupFeatures(gf) => GroupFeatures::marshal(gf, bytes),
+            MultipartRespBody::Meter(meters) => {
+                for ms in meters {
+                    MeterStats::marshal(ms, bytes)
+                }
+            }
+            MultipartRespBody::MeterConfig(meters) => {
+                for mc in meters {
+                    MeterConfig::marshal(mc, bytes)
+                }
+            }
+            MultipartRespBody::MeterFeatures(mf) => MeterFeatures::marshal(mf, bytes),
+            MultipartRespBody::PortDesc(ports) => {
+                for pd in ports {
+                    PortDesc::marshal(pd, bytes)
+                }
+            }
+            MultipartRespBody::Experimenter { experimenter, exp_type, body } => {
+                bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                bytes.write_u32::<BigEndian>(exp_type).unwrap();
+                bytes.write_all(&body).unwrap();
+            }
+        }
+    }
+}
+
+/// Encapsulates handling of messages implementing `MessageType` trait.
+pub mod message {
+    use super::*;
+    use marshal_error::MarshalError;
+    use ofp_header::OfpHeader;
+    use ofp_hello::OFP_VERSION_1_3;
+    use ofp_message::OfpMessage;
+    use packet::Packet;
+
+    /// Abstractions of OpenFlow 1.3 messages mapping to message codes.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Message {
+        Hello,
+        Error(Error),
+        EchoRequest(Vec<u8>),
+        EchoReply(Vec<u8>),
+        /// Experimenter-defined message with the given experimenter id, type, and body.
+        Experimenter {
+            experimenter: u32,
+            exp_type: u32,
+            body: Vec<u8>,
+        },
+        FeaturesReq,
+        FeaturesReply(SwitchFeatures),
+        GetConfigRequest,
+        GetConfigReply(SwitchConfig),
+        SetConfig(SwitchConfig),
+        PacketIn(PacketIn),
+        FlowRemoved(FlowRemoved),
+        PortStatus(PortStatus),
+        PacketOut(PacketOut),
+        FlowMod(FlowMod),
+        GroupMod(GroupMod),
+        PortMod(PortMod),
+        TableMod(TableMod),
+        MeterMod(MeterMod),
+        MultipartRequest(MultipartReq),
+        MultipartReply(MultipartResp),
+        BarrierRequest,
+        BarrierReply,
+    }
+
+    impl Message {
+        /// Map `Message` to associated OpenFlow message type code `MsgCode`.
+        fn msg_code_of_message(msg: &Message) -> MsgCode {
+            match *msg {
+                Message::Hello => MsgCode::Hello,
+                Message::Error(_) => MsgCode::Error,
+                Message::EchoRequest(_) => MsgCode::EchoReq,
+                Message::EchoReply(_) => MsgCode::EchoResp,
+                Message::Experimenter { .. } => MsgCode::Experimenter,
+                Message::FeaturesReq => MsgCode::FeaturesReq,
+                Message::FeaturesReply(_) => MsgCode::FeaturesResp,
+                Message::GetConfigRequest => MsgCode::GetConfigReq,
+                Message::GetConfigReply(_) => MsgCode::GetConfigResp,
+                Message::SetConfig(_) => MsgCode::SetConfig,
+                Message::PacketIn(_) => MsgCode::PacketIn,
+                Message::FlowRemoved(_) => MsgCode::FlowRemoved,
+                Message::PortStatus(_) => MsgCode::PortStatus,
+                Message::PacketOut(_) => MsgCode::PacketOut,
+                Message::FlowMod(_) => MsgCode::FlowMod,
+                Message::GroupMod(_) => MsgCode::GroupMod,
+                Message::PortMod(_) => MsgCode::PortMod,
+                Message::TableMod(_) => MsgCode::TableMod,
+                Message::MeterMod(_) => MsgCode::MeterMod,
+                Message::MultipartRequest(_) => MsgCode::MultipartReq,
+                Message::MultipartReply(_) => MsgCode::MultipartResp,
+                Message::BarrierRequest => MsgCode::BarrierReq,
+                Message::BarrierReply => MsgCode::BarrierResp,
+            }
+        }
+
+        /// Marshal the OpenFlow message `msg`.
+        fn marshal_body(msg: Message, bytes: &mut Vec<u8>) {
+            match msg {
+                Message::Hello => (),
+                Message::Error(buf) => Error::marshal(buf, bytes),
+                Message::EchoReply(buf) => bytes.write_all(&buf).unwrap(),
+                Message::EchoRequest(buf) => bytes.write_all(&buf).unwrap(),
+                Message::Experimenter { experimenter, exp_type, body } => {
+                    bytes.write_u32::<BigEndian>(experimenter).unwrap();
+                    bytes.write_u32::<BigEndian>(exp_type).unwrap();
+                    bytes.write_all(&body).unwrap()
+                }
+                Message::FeaturesReq => (),
+                Message::FeaturesReply(feats) => SwitchFeatures::marshal(feats, bytes),
+                Message::GetConfigRequest => (),
+                Message::GetConfigReply(config) |
+                Message::SetConfig(config) => SwitchConfig::marshal(config, bytes),
+                Message::PacketIn(packet_in) => PacketIn::marshal(packet_in, bytes),
+                Message::FlowRemoved(flow) => FlowRemoved::marshal(flow, bytes),
+                Message::PortStatus(sts) => PortStatus::marshal(sts, bytes),
+                Message::PacketOut(po) => PacketOut::marshal(po, bytes),
+                Message::FlowMod(flow_mod) => FlowMod::marshal(flow_mod, bytes),
+                Message::GroupMod(group_mod) => GroupMod::marshal(group_mod, bytes),
+                Message::PortMod(port_mod) => PortMod::marshal(port_mod, bytes),
+                Message::TableMod(table_mod) => TableMod::marshal(table_mod, bytes),
+                Message::MeterMod(meter_mod) => MeterMod::marshal(meter_mod, bytes),
+                Message::MultipartRequest(req) => MultipartReq::marshal(req, bytes),
+                Message::MultipartReply(resp) => MultipartResp::marshal(resp, bytes),
+                Message::BarrierRequest | Message::BarrierReply => (),
+            }
+        }
+    }
+
+    impl OfpMessage for Message {
+        fn size_of(msg: &Message) -> usize {
+            let body = match *msg {
+                Message::Hello => 0,
+                Message::Error(ref err) => Error::size_of(err),
+                Message::EchoRequest(ref buf) => buf.len(),
+                Message::EchoReply(ref buf) => buf.len(),
+                Message::Experimenter { ref body, .. } => 2 * size_of::<u32>() + body.len(),
+                Message::FeaturesReq => 0,
+                Message::FeaturesReply(ref feats) => SwitchFeatures::size_of(feats),
+                Message::GetConfigRequest => 0,
+                Message::GetConfigReply(ref config) |
+                Message::SetConfig(ref config) => SwitchConfig::size_of(config),
+                Message::PacketIn(ref packet_in) => PacketIn::size_of(packet_in),
+                Message::FlowRemoved(ref flow) => FlowRemoved::size_of(flow),
+                Message::PortStatus(ref ps) => PortStatus::size_of(ps),
+                Message::PacketOut(ref po) => PacketOut::size_of(po),
+                Message::FlowMod(ref flow_mod) => FlowMod::size_of(flow_mod),
+                Message::GroupMod(ref group_mod) => GroupMod::size_of(group_mod),
+                Message::PortMod(ref port_mod) => PortMod::size_of(port_mod),
+                Message::TableMod(ref table_mod) => TableMod::size_of(table_mod),
+                Message::MeterMod(ref meter_mod) => MeterMod::size_of(meter_mod),
+                Message::MultipartRequest(ref req) => MultipartReq::size_of(req),
+                Message::MultipartReply(ref resp) => MultipartResp::size_of(resp),
+                Message::BarrierRequest | Message::BarrierReply => 0,
+            };
+            OfpHeader::size() + body
+        }
+
+        fn header_of(xid: u32, msg: &Message) -> OfpHeader {
+            let sizeof_buf = Self::size_of(msg);
+            OfpHeader::new(OFP_VERSION_1_3,
+                           Self::msg_code_of_message(msg) as u8,
+                           sizeof_buf as u16,
+                           xid)
+        }
+
+        fn marshal(xid: u32, msg: Message) -> Result<Vec<u8>, MarshalError> {
+            let hdr = Self::header_of(xid, &msg);
+            let mut bytes = vec![];
+            OfpHeader::marshal(&mut bytes, hdr);
+            Message::marshal_body(msg, &mut bytes);
+            Ok(bytes)
+        }
+
+        fn parse(header: &OfpHeader, buf: &[u8]) -> Result<(u32, Message), ParseError> {
+            let typ = match MsgCode::of_int(header.type_byte()) {
+                Some(typ) => typ,
+                None => {
+                    return Err(ParseError::UnsupportedType("ofp_type", header.type_byte() as u64))
+                }
+            };
+            let msg = match typ {
+                MsgCode::Hello => {
+                    Message::Hello
+                }
+                MsgCode::Error => {
+                    Message::Error(Error::parse(buf)?)
+                }
+                MsgCode::EchoReq => Message::EchoRequest(buf.to_vec()),
+                MsgCode::EchoResp => Message::EchoReply(buf.to_vec()),
+                MsgCode::Experimenter => {
+                    let mut bytes = Cursor::new(buf.to_vec());
+                    let experimenter = bytes.read_u32::<BigEndian>()?;
+                    let exp_type = bytes.read_u32::<BigEndian>()?;
+                    Message::Experimenter {
+                        experimenter: experimenter,
+                        exp_type: exp_type,
+                        body: bytes.fill_buf()?.to_vec(),
+                    }
+                }
+                MsgCode::FeaturesReq => Message::FeaturesReq,
+                MsgCode::FeaturesResp => {
+                    Message::FeaturesReply(SwitchFeatures::parse(buf)?)
+                }
+                MsgCode::GetConfigReq => Message::GetConfigRequest,
+                MsgCode::GetConfigResp => {
+                    Message::GetConfigReply(SwitchConfig::parse(buf)?)
+                }
+                MsgCode::SetConfig => {
+                    Message::SetConfig(SwitchConfig::parse(buf)?)
+                }
+                MsgCode::PacketIn => {
+                    Message::PacketIn(PacketIn::parse(buf)?)
+                }
+                MsgCode::FlowRemoved => {
+                    Message::FlowRemoved(FlowRemoved::parse(buf)?)
+                }
+                MsgCode::PortStatus => {
+                    Message::PortStatus(PortStatus::parse(buf)?)
+                }
+                MsgCode::PacketOut => {
+                    Message::PacketOut(PacketOut::parse(buf)?)
+                }
+                MsgCode::FlowMod => {
+                    Message::FlowMod(FlowMod::parse(buf)?)
+                }
+                MsgCode::GroupMod => {
+                    Message::GroupMod(GroupMod::parse(buf)?)
+                }
+                MsgCode::PortMod => {
+                    Message::PortMod(PortMod::parse(buf)?)
+                }
+                MsgCode::TableMod => {
+                    Message::TableMod(TableMod::parse(buf)?)
+                }
+                MsgCode::MeterMod => {
+                    Message::MeterMod(MeterMod::parse(buf)?)
+                }
+                MsgCode::MultipartReq => {
+                    Message::MultipartRequest(MultipartReq::parse(buf)?)
+                }
+                MsgCode::MultipartResp => {
+                    Message::MultipartReply(MultipartResp::parse(buf)?)
+                }
+                MsgCode::BarrierReq => Message::BarrierRequest,
+                MsgCode::BarrierResp => Message::BarrierReply,
+                t => return Err(ParseError::UnsupportedType("ofp_type", t as u64)),
+            };
+            Ok((header.xid(), msg))
+        }
+    }
+
+    /// Return a `FlowMod` adding a flow to table 0 parameterized by the given `priority`,
+    /// `pattern`, and `instructions`.
+    pub fn add_flow(prio: u16, pattern: Pattern, instructions: Vec<Instruction>) -> FlowMod {
+        FlowMod {
+            command: FlowModCmd::AddFlow,
+            table_id: 0,
+            pattern: pattern,
+            priority: prio,
+            instructions: instructions,
+            cookie: 0,
+            cookie_mask: 0,
+            idle_timeout: Timeout::Permanent,
+            hard_timeout: Timeout::Permanent,
+            flags: FlowModFlags::none(),
+            apply_to_packet: None,
+            out_port: None,
+            out_group: None,
+        }
+    }
+
+    /// Return a `GroupMod` adding group `group_id` of type `typ` with the given `buckets`.
+    pub fn add_group(typ: GroupType, group_id: u32, buckets: Vec<Bucket>) -> GroupMod {
+        GroupMod {
+            command: GroupModCmd::AddGroup,
+            typ: typ,
+            group_id: group_id,
+            buckets: buckets,
+        }
+    }
+
+    /// Return a `GroupMod` deleting group `group_id`, or every group if it is `OFPG_ALL`.
+    pub fn delete_group(group_id: u32) -> GroupMod {
+        GroupMod {
+            command: GroupModCmd::DeleteGroup,
+            typ: GroupType::All,
+            group_id: group_id,
+            buckets: vec![],
+        }
+    }
+
+    /// Return a `MeterMod` adding meter `meter_id` with the given `flags` and `bands`.
+    pub fn add_meter(meter_id: u32, flags: MeterFlags, bands: Vec<MeterBand>) -> MeterMod {
+        MeterMod {
+            command: MeterModCmd::AddMeter,
+            flags: flags,
+            meter_id: meter_id,
+            bands: bands,
+        }
+    }
+
+    /// Parse a payload buffer into a network level packet.
+    pub fn parse_payload(p: &Payload) -> Result<Packet, ParseError> {
+        match *p {
+            Payload::Buffered(_, ref b) |
+            Payload::NotBuffered(ref b) => Packet::parse(b),
+        }
+    }
+}
@@ -1,17 +1,24 @@
+use std::cmp::min;
 use std::io::{BufRead, Cursor, Read};
 use std::mem::size_of;
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
 
-use bits::test_bit;
+use bits::{bit, test_bit};
+use parse_error::ParseError;
 
 pub fn bytes_of_mac(addr: u64) -> [u8; 6] {
     let mut arr = [0; 6];
     for i in 0..6 {
-        arr[i] = ((addr >> (8 * i)) & 0xff) as u8;
+        arr[i] = ((addr >> (8 * (5 - i))) & 0xff) as u8;
     }
     arr
 }
 
+/// Number of bytes of `bytes` that are yet to be read.
+fn remaining(bytes: &Cursor<Vec<u8>>) -> usize {
+    bytes.get_ref().len().saturating_sub(bytes.position() as usize)
+}
+
 pub fn mac_of_bytes(addr: [u8; 6]) -> u64 {
     fn byte(u: &[u8; 6], i: usize) -> u64 {
         u[i] as u64
@@ -20,8 +27,48 @@ pub fn mac_of_bytes(addr: [u8; 6]) -> u64 {
     (byte(&addr, 3) << 8 * 2) | (byte(&addr, 4) << 8 * 1) | (byte(&addr, 5))
 }
 
+/// Add the big-endian 16-bit words of `data` to the ones' complement sum `sum`. An odd trailing
+/// byte is padded with zero.
+fn sum_words(sum: u32, data: &[u8]) -> u32 {
+    data.chunks(2).fold(sum, |sum, word| {
+        sum + ((word[0] as u32) << 8 | word.get(1).cloned().unwrap_or(0) as u32)
+    })
+}
+
+/// Return the internet checksum of `data`, starting from the partial sum `sum`.
+fn checksum(sum: u32, data: &[u8]) -> u16 {
+    let mut sum = sum_words(sum, data);
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Partial sum of the addresses in the IPv4 pseudo-header covered by transport checksums.
+fn ipv4_addr_sum(src: u32, dst: u32) -> u32 {
+    (src >> 16) + (src & 0xffff) + (dst >> 16) + (dst & 0xffff)
+}
+
+/// Partial sum of the addresses in the IPv6 pseudo-header covered by transport checksums.
+fn ipv6_addr_sum(src: u128, dst: u128) -> u32 {
+    (0..8).fold(0, |sum, i| {
+        sum + ((src >> (16 * i)) as u32 & 0xffff) + ((dst >> (16 * i)) as u32 & 0xffff)
+    })
+}
+
+/// Partial sum of a pseudo-header with address sum `addr_sum`, covering `len` bytes of
+/// protocol `proto`.
+fn pseudo_header_sum(addr_sum: u32, proto: u8, len: usize) -> u32 {
+    addr_sum + proto as u32 + len as u32
+}
+
+/// Number of zero bytes padding `len` bytes of options to a multiple of 4 bytes.
+fn options_pad(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
 /// TCP Header flags.
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TcpFlags {
     /// ECN-nonce concealment protection.
     pub ns: bool,
@@ -46,21 +93,35 @@ pub struct TcpFlags {
 impl TcpFlags {
     fn of_int(d: u16) -> TcpFlags {
         TcpFlags {
-            ns: test_bit(0, d as u64),
-            cwr: test_bit(1, d as u64),
-            ece: test_bit(2, d as u64),
-            urg: test_bit(3, d as u64),
+            ns: test_bit(8, d as u64),
+            cwr: test_bit(7, d as u64),
+            ece: test_bit(6, d as u64),
+            urg: test_bit(5, d as u64),
             ack: test_bit(4, d as u64),
-            psh: test_bit(5, d as u64),
-            rst: test_bit(6, d as u64),
-            syn: test_bit(7, d as u64),
-            fin: test_bit(8, d as u64),
+            psh: test_bit(3, d as u64),
+            rst: test_bit(2, d as u64),
+            syn: test_bit(1, d as u64),
+            fin: test_bit(0, d as u64),
         }
     }
+
+    fn to_int(&self) -> u16 {
+        let ret = 0u64;
+        let ret = bit(8, ret, self.ns);
+        let ret = bit(7, ret, self.cwr);
+        let ret = bit(6, ret, self.ece);
+        let ret = bit(5, ret, self.urg);
+        let ret = bit(4, ret, self.ack);
+        let ret = bit(3, ret, self.psh);
+        let ret = bit(2, ret, self.rst);
+        let ret = bit(1, ret, self.syn);
+        let ret = bit(0, ret, self.fin);
+        ret as u16
+    }
 }
 
 /// TCP frame of a packet.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Tcp {
     pub src: u16,
     pub dst: u16,
@@ -70,7 +131,11 @@ pub struct Tcp {
     pub flags: TcpFlags,
     pub window: u16,
     pub chksum: u16,
+    /// Whether `chksum` is correct, or `None` if it was not checked because the segment was
+    /// not parsed, or was truncated or fragmented.
+    pub chksum_valid: Option<bool>,
     pub urgent: u16,
+    pub options: Vec<u8>,
     pub payload: Vec<u8>,
 }
 
@@ -78,22 +143,59 @@ pub struct Tcp {
 struct TcpNet(u16, u16, u32, u32, u16, u16, u16, u16);
 
 impl Tcp {
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Option<Tcp> {
-        if bytes.get_ref().len() < size_of::<TcpNet>() {
-            return None;
-        }
-        let src = bytes.read_u16::<BigEndian>().unwrap();
-        let dst = bytes.read_u16::<BigEndian>().unwrap();
-        let seq = bytes.read_u32::<BigEndian>().unwrap();
-        let ack = bytes.read_u32::<BigEndian>().unwrap();
-        let offset = bytes.read_u16::<BigEndian>().unwrap();
+    /// Create a TCP segment from port `src` to port `dst`, with an open receive window and no
+    /// urgent data.
+    pub fn new(src: u16, dst: u16, seq: u32, ack: u32, flags: TcpFlags, payload: Vec<u8>) -> Tcp {
+        Tcp {
+            src: src,
+            dst: dst,
+            seq: seq,
+            ack: ack,
+            offset: (size_of::<TcpNet>() / 4) as u8,
+            flags: flags,
+            window: 0xffff,
+            chksum: 0,
+            chksum_valid: None,
+            urgent: 0,
+            options: vec![],
+            payload: payload,
+        }
+    }
+
+    /// Parse the TCP segment filling `bytes`, whose pseudo-header has address sum `addr_sum`.
+    /// The checksum is only verified if the segment is `complete`.
+    fn parse(bytes: &mut Cursor<Vec<u8>>,
+             addr_sum: u32,
+             complete: bool)
+             -> Result<Tcp, ParseError> {
+        if remaining(bytes) < size_of::<TcpNet>() {
+            return Err(ParseError::Truncated);
+        }
+        let src = bytes.read_u16::<BigEndian>()?;
+        let dst = bytes.read_u16::<BigEndian>()?;
+        let seq = bytes.read_u32::<BigEndian>()?;
+        let ack = bytes.read_u32::<BigEndian>()?;
+        let offset = bytes.read_u16::<BigEndian>()?;
         let flags = TcpFlags::of_int(offset);
         let offset = (offset >> 12) as u8 & 0x0f;
-        let window = bytes.read_u16::<BigEndian>().unwrap();
-        let chksum = bytes.read_u16::<BigEndian>().unwrap();
-        let urgent = bytes.read_u16::<BigEndian>().unwrap();
-        let payload = bytes.fill_buf().unwrap().to_vec();
-        Some(Tcp {
+        let window = bytes.read_u16::<BigEndian>()?;
+        let chksum = bytes.read_u16::<BigEndian>()?;
+        let urgent = bytes.read_u16::<BigEndian>()?;
+        let header_len = offset as usize * 4;
+        if header_len < size_of::<TcpNet>() {
+            return Err(ParseError::BadLength("tcp data offset", header_len));
+        }
+        let mut options = vec![0; header_len - size_of::<TcpNet>()];
+        bytes.read_exact(&mut options)?;
+        let payload = bytes.fill_buf()?.to_vec();
+        let chksum_valid = if complete {
+            let seg = bytes.get_ref();
+            let sum = pseudo_header_sum(addr_sum, IpProto::IpTCP as u8, seg.len());
+            Some(checksum(sum, seg) == 0)
+        } else {
+            None
+        };
+        Ok(Tcp {
             src: src,
             dst: dst,
             seq: seq,
@@ -102,87 +204,572 @@ impl Tcp {
             flags: flags,
             window: window,
             chksum: chksum,
+            chksum_valid: chksum_valid,
             urgent: urgent,
+            options: options,
             payload: payload,
         })
     }
+
+    /// Marshal `tcp` with its data offset and checksum computed, for a pseudo-header with
+    /// address sum `addr_sum`. Options are padded with zeros to a multiple of 4 bytes.
+    fn marshal(tcp: &Tcp, addr_sum: u32, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        let pad = options_pad(tcp.options.len());
+        let offset = ((size_of::<TcpNet>() + tcp.options.len() + pad) / 4) as u16;
+        bytes.write_u16::<BigEndian>(tcp.src).unwrap();
+        bytes.write_u16::<BigEndian>(tcp.dst).unwrap();
+        bytes.write_u32::<BigEndian>(tcp.seq).unwrap();
+        bytes.write_u32::<BigEndian>(tcp.ack).unwrap();
+        bytes.write_u16::<BigEndian>(offset << 12 | tcp.flags.to_int()).unwrap();
+        bytes.write_u16::<BigEndian>(tcp.window).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u16::<BigEndian>(tcp.urgent).unwrap();
+        bytes.extend_from_slice(&tcp.options);
+        for _ in 0..pad {
+            bytes.write_u8(0).unwrap();
+        }
+        bytes.extend_from_slice(&tcp.payload);
+        let sum = pseudo_header_sum(addr_sum, IpProto::IpTCP as u8, bytes.len() - start);
+        let chksum = checksum(sum, &bytes[start..]);
+        BigEndian::write_u16(&mut bytes[start + 16..start + 18], chksum);
+    }
 }
 
 /// UDP frame of a packet.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Udp {
     pub src: u16,
     pub dst: u16,
     pub chksum: u16,
+    /// Whether `chksum` is correct, or `None` if it was not checked because the datagram was
+    /// not parsed, was truncated or fragmented, or has no checksum.
+    pub chksum_valid: Option<bool>,
     pub payload: Vec<u8>,
 }
 
 impl Udp {
+    /// Create a UDP datagram from port `src` to port `dst`.
+    pub fn new(src: u16, dst: u16, payload: Vec<u8>) -> Udp {
+        Udp {
+            src: src,
+            dst: dst,
+            chksum: 0,
+            chksum_valid: None,
+            payload: payload,
+        }
+    }
+
     fn size_of() -> usize {
         8
     }
 
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Option<Udp> {
-        if bytes.get_ref().len() < Self::size_of() {
-            return None;
+    /// Parse the UDP datagram filling `bytes`, whose pseudo-header has address sum `addr_sum`.
+    /// The checksum is only verified if the datagram is `complete`.
+    fn parse(bytes: &mut Cursor<Vec<u8>>,
+             addr_sum: u32,
+             complete: bool)
+             -> Result<Udp, ParseError> {
+        if remaining(bytes) < Self::size_of() {
+            return Err(ParseError::Truncated);
         }
-        let src = bytes.read_u16::<BigEndian>().unwrap();
-        let dst = bytes.read_u16::<BigEndian>().unwrap();
-        let chksum = bytes.read_u16::<BigEndian>().unwrap();
-        let payload = bytes.fill_buf().unwrap().to_vec();
-        Some(Udp {
+        let src = bytes.read_u16::<BigEndian>()?;
+        let dst = bytes.read_u16::<BigEndian>()?;
+        let len = bytes.read_u16::<BigEndian>()? as usize;
+        let chksum = bytes.read_u16::<BigEndian>()?;
+        if len < Self::size_of() {
+            return Err(ParseError::BadLength("udp length", len));
+        }
+        let end = min(len, bytes.get_ref().len());
+        let payload = bytes.get_ref()[Self::size_of()..end].to_vec();
+        let chksum_valid = if complete && len <= bytes.get_ref().len() && chksum != 0 {
+            let sum = pseudo_header_sum(addr_sum, IpProto::IpUDP as u8, len);
+            Some(checksum(sum, &bytes.get_ref()[..len]) == 0)
+        } else {
+            None
+        };
+        Ok(Udp {
             src: src,
             dst: dst,
             chksum: chksum,
+            chksum_valid: chksum_valid,
             payload: payload,
         })
     }
+
+    /// Marshal `udp` with its length and checksum computed, for a pseudo-header with address
+    /// sum `addr_sum`.
+    fn marshal(udp: &Udp, addr_sum: u32, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        let len = Self::size_of() + udp.payload.len();
+        bytes.write_u16::<BigEndian>(udp.src).unwrap();
+        bytes.write_u16::<BigEndian>(udp.dst).unwrap();
+        bytes.write_u16::<BigEndian>(len as u16).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.extend_from_slice(&udp.payload);
+        let sum = pseudo_header_sum(addr_sum, IpProto::IpUDP as u8, len);
+        // A computed checksum of zero is sent as all ones, zero meaning no checksum.
+        let chksum = match checksum(sum, &bytes[start..]) {
+            0 => 0xffff,
+            c => c,
+        };
+        BigEndian::write_u16(&mut bytes[start + 6..start + 8], chksum);
+    }
 }
 
 /// ICMP frame of a packet.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Icmp {
     pub typ: u8,
     pub code: u8,
     pub chksum: u16,
+    /// Whether `chksum` is correct, or `None` if it was not checked because the message was
+    /// not parsed, or was truncated or fragmented.
+    pub chksum_valid: Option<bool>,
     pub payload: Vec<u8>,
 }
 
 impl Icmp {
+    /// Create an ICMP message of type `typ` and code `code`. The payload of echo messages starts
+    /// with their identifier and sequence number.
+    pub fn new(typ: u8, code: u8, payload: Vec<u8>) -> Icmp {
+        Icmp {
+            typ: typ,
+            code: code,
+            chksum: 0,
+            chksum_valid: None,
+            payload: payload,
+        }
+    }
+
     fn size_of() -> usize {
         4
     }
 
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Option<Icmp> {
-        if bytes.get_ref().len() < Self::size_of() {
-            return None;
+    /// Parse the ICMP message filling `bytes`. The checksum is only verified if the message is
+    /// `complete`.
+    fn parse(bytes: &mut Cursor<Vec<u8>>, complete: bool) -> Result<Icmp, ParseError> {
+        if remaining(bytes) < Self::size_of() {
+            return Err(ParseError::Truncated);
         }
-        let typ = bytes.read_u8().unwrap();
-        let code = bytes.read_u8().unwrap();
-        let chksum = bytes.read_u16::<BigEndian>().unwrap();
-        let payload = bytes.fill_buf().unwrap().to_vec();
-        Some(Icmp {
+        let typ = bytes.read_u8()?;
+        let code = bytes.read_u8()?;
+        let chksum = bytes.read_u16::<BigEndian>()?;
+        let payload = bytes.fill_buf()?.to_vec();
+        let chksum_valid = if complete {
+            Some(checksum(0, bytes.get_ref()) == 0)
+        } else {
+            None
+        };
+        Ok(Icmp {
             typ: typ,
             code: code,
             chksum: chksum,
+            chksum_valid: chksum_valid,
             payload: payload,
         })
     }
+
+    /// Marshal `icmp` with its checksum computed.
+    fn marshal(icmp: &Icmp, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        bytes.write_u8(icmp.typ).unwrap();
+        bytes.write_u8(icmp.code).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.extend_from_slice(&icmp.payload);
+        let chksum = checksum(0, &bytes[start..]);
+        BigEndian::write_u16(&mut bytes[start + 2..start + 4], chksum);
+    }
+}
+
+/// NDP option of an ICMPv6 neighbor discovery message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NdpOption {
+    SourceLinkLayerAddr(u64),
+    TargetLinkLayerAddr(u64),
+    PrefixInfo {
+        prefix_len: u8,
+        on_link: bool,
+        autonomous: bool,
+        valid_lifetime: u32,
+        preferred_lifetime: u32,
+        prefix: u128,
+    },
+    Mtu(u32),
+    /// Option of the given type with the given body, which follows its type and length.
+    Unparsable(u8, Vec<u8>),
+}
+
+#[repr(u8)]
+enum NdpOptionType {
+    SourceLinkLayerAddr = 1,
+    TargetLinkLayerAddr = 2,
+    PrefixInfo = 3,
+    Mtu = 5,
+}
+
+impl NdpOption {
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<NdpOption, ParseError> {
+        let typ = bytes.read_u8()?;
+        let len = bytes.read_u8()? as usize * 8;
+        if len == 0 {
+            return Err(ParseError::BadLength("ndp option", len));
+        }
+        let mut body = vec![0; len - 2];
+        bytes.read_exact(&mut body)?;
+        let mut body = Cursor::new(body);
+        let opt = match typ {
+            t if t == (NdpOptionType::SourceLinkLayerAddr as u8) && len == 8 => {
+                let mut addr = [0; 6];
+                body.read_exact(&mut addr)?;
+                NdpOption::SourceLinkLayerAddr(mac_of_bytes(addr))
+            }
+            t if t == (NdpOptionType::TargetLinkLayerAddr as u8) && len == 8 => {
+                let mut addr = [0; 6];
+                body.read_exact(&mut addr)?;
+                NdpOption::TargetLinkLayerAddr(mac_of_bytes(addr))
+            }
+            t if t == (NdpOptionType::PrefixInfo as u8) && len == 32 => {
+                let prefix_len = body.read_u8()?;
+                let flags = body.read_u8()?;
+                let valid_lifetime = body.read_u32::<BigEndian>()?;
+                let preferred_lifetime = body.read_u32::<BigEndian>()?;
+                body.consume(4);
+                NdpOption::PrefixInfo {
+                    prefix_len: prefix_len,
+                    on_link: test_bit(7, flags as u64),
+                    autonomous: test_bit(6, flags as u64),
+                    valid_lifetime: valid_lifetime,
+                    preferred_lifetime: preferred_lifetime,
+                    prefix: body.read_u128::<BigEndian>()?,
+                }
+            }
+            t if t == (NdpOptionType::Mtu as u8) && len == 8 => {
+                body.consume(2);
+                NdpOption::Mtu(body.read_u32::<BigEndian>()?)
+            }
+            _ => NdpOption::Unparsable(typ, body.into_inner()),
+        };
+        Ok(opt)
+    }
+
+    fn parse_sequence(bytes: &mut Cursor<Vec<u8>>) -> Result<Vec<NdpOption>, ParseError> {
+        let mut v = vec![];
+        while remaining(bytes) > 0 {
+            v.push(NdpOption::parse(bytes)?)
+        }
+        Ok(v)
+    }
+
+    /// Marshal `opt`, padding the body of an `Unparsable` option with zeros to a multiple of 8
+    /// bytes.
+    fn marshal(opt: &NdpOption, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        let typ = match *opt {
+            NdpOption::SourceLinkLayerAddr(addr) => {
+                bytes.extend_from_slice(&bytes_of_mac(addr));
+                NdpOptionType::SourceLinkLayerAddr as u8
+            }
+            NdpOption::TargetLinkLayerAddr(addr) => {
+                bytes.extend_from_slice(&bytes_of_mac(addr));
+                NdpOptionType::TargetLinkLayerAddr as u8
+            }
+            NdpOption::PrefixInfo { prefix_len,
+                                    on_link,
+                                    autonomous,
+                                    valid_lifetime,
+                                    preferred_lifetime,
+                                    prefix } => {
+                bytes.write_u8(prefix_len).unwrap();
+                bytes.write_u8(bit(7, bit(6, 0, autonomous), on_link) as u8).unwrap();
+                bytes.write_u32::<BigEndian>(valid_lifetime).unwrap();
+                bytes.write_u32::<BigEndian>(preferred_lifetime).unwrap();
+                bytes.write_u32::<BigEndian>(0).unwrap();
+                bytes.write_u128::<BigEndian>(prefix).unwrap();
+                NdpOptionType::PrefixInfo as u8
+            }
+            NdpOption::Mtu(mtu) => {
+                bytes.write_u16::<BigEndian>(0).unwrap();
+                bytes.write_u32::<BigEndian>(mtu).unwrap();
+                NdpOptionType::Mtu as u8
+            }
+            NdpOption::Unparsable(typ, ref body) => {
+                bytes.extend_from_slice(body);
+                while (bytes.len() - start) % 8 != 0 {
+                    bytes.write_u8(0).unwrap();
+                }
+                typ
+            }
+        };
+        bytes[start] = typ;
+        bytes[start + 1] = ((bytes.len() - start) / 8) as u8;
+    }
+}
+
+/// Body of an ICMPv6 router advertisement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouterAdvert {
+    pub hop_limit: u8,
+    /// Addresses are available by DHCPv6.
+    pub managed: bool,
+    /// Configuration other than addresses is available by DHCPv6.
+    pub other_config: bool,
+    /// Seconds the router may be used as a default router for.
+    pub lifetime: u16,
+    pub reachable_time: u32,
+    pub retrans_timer: u32,
+    pub options: Vec<NdpOption>,
+}
+
+/// Body of an ICMPv6 neighbor solicitation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborSolicit {
+    pub target: u128,
+    pub options: Vec<NdpOption>,
+}
+
+/// Body of an ICMPv6 neighbor advertisement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborAdvert {
+    /// The sender is a router.
+    pub router: bool,
+    /// The advertisement answers a solicitation.
+    pub solicited: bool,
+    /// The advertisement should override cached link-layer addresses.
+    pub override_flag: bool,
+    pub target: u128,
+    pub options: Vec<NdpOption>,
+}
+
+/// Message carried by an ICMPv6 frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Icmpv6Msg {
+    RouterAdvert(RouterAdvert),
+    NeighborSolicit(NeighborSolicit),
+    NeighborAdvert(NeighborAdvert),
+    /// Message of the given type and code with the given body, which follows the checksum.
+    Unparsable(u8, u8, Vec<u8>),
+}
+
+#[repr(u8)]
+enum Icmpv6Type {
+    RouterAdvert = 134,
+    NeighborSolicit = 135,
+    NeighborAdvert = 136,
+}
+
+/// ICMPv6 frame of a packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Icmpv6 {
+    pub chksum: u16,
+    /// Whether `chksum` is correct, or `None` if it was not checked because the message was
+    /// not parsed, or was truncated or fragmented.
+    pub chksum_valid: Option<bool>,
+    pub msg: Icmpv6Msg,
+}
+
+impl Icmpv6 {
+    /// Create an ICMPv6 frame carrying `msg`.
+    pub fn new(msg: Icmpv6Msg) -> Icmpv6 {
+        Icmpv6 {
+            chksum: 0,
+            chksum_valid: None,
+            msg: msg,
+        }
+    }
+
+    fn size_of() -> usize {
+        4
+    }
+
+    fn parse_msg(typ: u8, code: u8, bytes: &mut Cursor<Vec<u8>>) -> Result<Icmpv6Msg, ParseError> {
+        let msg = match typ {
+            t if t == (Icmpv6Type::RouterAdvert as u8) && code == 0 => {
+                let hop_limit = bytes.read_u8()?;
+                let flags = bytes.read_u8()?;
+                let lifetime = bytes.read_u16::<BigEndian>()?;
+                let reachable_time = bytes.read_u32::<BigEndian>()?;
+                let retrans_timer = bytes.read_u32::<BigEndian>()?;
+                Icmpv6Msg::RouterAdvert(RouterAdvert {
+                    hop_limit: hop_limit,
+                    managed: test_bit(7, flags as u64),
+                    other_config: test_bit(6, flags as u64),
+                    lifetime: lifetime,
+                    reachable_time: reachable_time,
+                    retrans_timer: retrans_timer,
+                    options: NdpOption::parse_sequence(bytes)?,
+                })
+            }
+            t if t == (Icmpv6Type::NeighborSolicit as u8) && code == 0 => {
+                bytes.consume(4);
+                let target = bytes.read_u128::<BigEndian>()?;
+                Icmpv6Msg::NeighborSolicit(NeighborSolicit {
+                    target: target,
+                    options: NdpOption::parse_sequence(bytes)?,
+                })
+            }
+            t if t == (Icmpv6Type::NeighborAdvert as u8) && code == 0 => {
+                let flags = bytes.read_u32::<BigEndian>()?;
+                let target = bytes.read_u128::<BigEndian>()?;
+                Icmpv6Msg::NeighborAdvert(NeighborAdvert {
+                    router: test_bit(31, flags as u64),
+                    solicited: test_bit(30, flags as u64),
+                    override_flag: test_bit(29, flags as u64),
+                    target: target,
+                    options: NdpOption::parse_sequence(bytes)?,
+                })
+            }
+            _ => return Err(ParseError::UnsupportedType("icmpv6 type", typ as u64)),
+        };
+        Ok(msg)
+    }
+
+    /// Parse the ICMPv6 message filling `bytes`, whose pseudo-header has address sum
+    /// `addr_sum`. The checksum is only verified if the message is `complete`. Neighbor
+    /// discovery messages that cannot be parsed are left `Unparsable`.
+    fn parse(bytes: &mut Cursor<Vec<u8>>,
+             addr_sum: u32,
+             complete: bool)
+             -> Result<Icmpv6, ParseError> {
+        if remaining(bytes) < Self::size_of() {
+            return Err(ParseError::Truncated);
+        }
+        let typ = bytes.read_u8()?;
+        let code = bytes.read_u8()?;
+        let chksum = bytes.read_u16::<BigEndian>()?;
+        let pos = bytes.position();
+        let msg = match Icmpv6::parse_msg(typ, code, bytes) {
+            Ok(msg) => msg,
+            Err(_) => {
+                bytes.set_position(pos);
+                Icmpv6Msg::Unparsable(typ, code, bytes.fill_buf()?.to_vec())
+            }
+        };
+        let chksum_valid = if complete {
+            let msg = bytes.get_ref();
+            let sum = pseudo_header_sum(addr_sum, IpProto::IpICMPv6 as u8, msg.len());
+            Some(checksum(sum, msg) == 0)
+        } else {
+            None
+        };
+        Ok(Icmpv6 {
+            chksum: chksum,
+            chksum_valid: chksum_valid,
+            msg: msg,
+        })
+    }
+
+    /// Marshal `icmp` with its checksum computed, for a pseudo-header with address sum
+    /// `addr_sum`.
+    fn marshal(icmp: &Icmpv6, addr_sum: u32, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        let (typ, code) = match icmp.msg {
+            Icmpv6Msg::RouterAdvert(_) => (Icmpv6Type::RouterAdvert as u8, 0),
+            Icmpv6Msg::NeighborSolicit(_) => (Icmpv6Type::NeighborSolicit as u8, 0),
+            Icmpv6Msg::NeighborAdvert(_) => (Icmpv6Type::NeighborAdvert as u8, 0),
+            Icmpv6Msg::Unparsable(typ, code, _) => (typ, code),
+        };
+        bytes.write_u8(typ).unwrap();
+        bytes.write_u8(code).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        let options = match icmp.msg {
+            Icmpv6Msg::RouterAdvert(ref ra) => {
+                bytes.write_u8(ra.hop_limit).unwrap();
+                bytes.write_u8(bit(7, bit(6, 0, ra.other_config), ra.managed) as u8).unwrap();
+                bytes.write_u16::<BigEndian>(ra.lifetime).unwrap();
+                bytes.write_u32::<BigEndian>(ra.reachable_time).unwrap();
+                bytes.write_u32::<BigEndian>(ra.retrans_timer).unwrap();
+                &ra.options[..]
+            }
+            Icmpv6Msg::NeighborSolicit(ref ns) => {
+                bytes.write_u32::<BigEndian>(0).unwrap();
+                bytes.write_u128::<BigEndian>(ns.target).unwrap();
+                &ns.options[..]
+            }
+            Icmpv6Msg::NeighborAdvert(ref na) => {
+                let flags = bit(31, 0, na.router);
+                let flags = bit(30, flags, na.solicited);
+                let flags = bit(29, flags, na.override_flag);
+                bytes.write_u32::<BigEndian>(flags as u32).unwrap();
+                bytes.write_u128::<BigEndian>(na.target).unwrap();
+                &na.options[..]
+            }
+            Icmpv6Msg::Unparsable(_, _, ref body) => {
+                bytes.extend_from_slice(body);
+                &[]
+            }
+        };
+        for opt in options {
+            NdpOption::marshal(opt, bytes);
+        }
+        let sum = pseudo_header_sum(addr_sum, IpProto::IpICMPv6 as u8, bytes.len() - start);
+        let chksum = checksum(sum, &bytes[start..]);
+        BigEndian::write_u16(&mut bytes[start + 2..start + 4], chksum);
+    }
 }
 
 /// Represents packets at the transport protocol level, which are encapsulated
-/// within the IPv4 payload. At present, we only support TCP, UDP, and ICMP
-/// explicitly; otherwise, the raw bytes and IPv4 protocol number are provided.
-#[derive(Debug)]
+/// within the IPv4 or IPv6 payload. At present, we only support TCP, UDP, ICMP
+/// and ICMPv6 explicitly; otherwise, the raw bytes and IP protocol number are
+/// provided.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Tp {
     Tcp(Tcp),
     Udp(Udp),
     Icmp(Icmp),
+    Icmpv6(Icmpv6),
     Unparsable(u8, Vec<u8>),
 }
 
+impl Tp {
+    /// Parse the transport payload `buf` of protocol `proto`, whose pseudo-header has address
+    /// sum `addr_sum`, leaving it `Unparsable` if it cannot be parsed. Checksums are only
+    /// verified if the payload is `complete`.
+    fn parse(proto: u8, buf: Vec<u8>, addr_sum: u32, complete: bool) -> Tp {
+        let mut bytes = Cursor::new(buf);
+        let tp = match proto {
+            t if t == (IpProto::IpICMP as u8) => Icmp::parse(&mut bytes, complete).map(Tp::Icmp),
+            t if t == (IpProto::IpTCP as u8) => {
+                Tcp::parse(&mut bytes, addr_sum, complete).map(Tp::Tcp)
+            }
+            t if t == (IpProto::IpUDP as u8) => {
+                Udp::parse(&mut bytes, addr_sum, complete).map(Tp::Udp)
+            }
+            t if t == (IpProto::IpICMPv6 as u8) => {
+                Icmpv6::parse(&mut bytes, addr_sum, complete).map(Tp::Icmpv6)
+            }
+            _ => Err(ParseError::UnsupportedType("ip proto", proto as u64)),
+        };
+        match tp {
+            Ok(tp) => tp,
+            Err(_) => Tp::Unparsable(proto, bytes.into_inner()),
+        }
+    }
+
+    fn proto(tp: &Tp) -> u8 {
+        match *tp {
+            Tp::Tcp(_) => IpProto::IpTCP as u8,
+            Tp::Udp(_) => IpProto::IpUDP as u8,
+            Tp::Icmp(_) => IpProto::IpICMP as u8,
+            Tp::Icmpv6(_) => IpProto::IpICMPv6 as u8,
+            Tp::Unparsable(proto, _) => proto,
+        }
+    }
+
+    fn marshal(tp: &Tp, addr_sum: u32, bytes: &mut Vec<u8>) {
+        match *tp {
+            Tp::Tcp(ref tcp) => Tcp::marshal(tcp, addr_sum, bytes),
+            Tp::Udp(ref udp) => Udp::marshal(udp, addr_sum, bytes),
+            Tp::Icmp(ref icmp) => Icmp::marshal(icmp, bytes),
+            Tp::Icmpv6(ref icmp) => Icmpv6::marshal(icmp, addr_sum, bytes),
+            Tp::Unparsable(_, ref buf) => bytes.extend_from_slice(buf),
+        }
+    }
+}
+
 /// The type of IPv4 flags.
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Flags {
     pub dont_fragment: bool,
     pub more_fragments: bool,
@@ -192,20 +779,27 @@ impl Flags {
     fn of_int(flags: u32) -> Flags {
         Flags {
             dont_fragment: test_bit(1, flags as u64),
-            more_fragments: test_bit(2, flags as u64),
+            more_fragments: test_bit(0, flags as u64),
         }
     }
+
+    fn to_int(&self) -> u32 {
+        bit(1, bit(0, 0, self.more_fragments), self.dont_fragment) as u32
+    }
 }
 
 /// IPv4 frame of a packet.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Ip {
     pub tos: u8,
     pub ident: u16,
     pub flags: Flags,
+    /// Fragment offset, in units of 8 bytes.
     pub frag: u16,
     pub ttl: u8,
     pub chksum: u16,
+    /// Whether `chksum` is correct, or `None` if the packet was not parsed.
+    pub chksum_valid: Option<bool>,
     pub src: u32,
     pub dst: u32,
     pub options: Vec<u8>,
@@ -217,78 +811,342 @@ enum IpProto {
     IpICMP = 0x01,
     IpTCP = 0x06,
     IpUDP = 0x11,
+    IpICMPv6 = 0x3a,
 }
 
 #[repr(packed)]
 struct IpNet(u8, u8, u16, u16, u16, u8, u8, u16, u32, u32);
 
 impl Ip {
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Option<Ip> {
-        if bytes.get_ref().len() < size_of::<IpNet>() {
-            return None;
+    /// Create an unfragmented IPv4 packet from `src` to `dst` carrying `tp`, with a TTL of 64.
+    pub fn new(src: u32, dst: u32, tp: Tp) -> Ip {
+        Ip {
+            tos: 0,
+            ident: 0,
+            flags: Flags::default(),
+            frag: 0,
+            ttl: 64,
+            chksum: 0,
+            chksum_valid: None,
+            src: src,
+            dst: dst,
+            options: vec![],
+            tp: tp,
         }
-        let vhl = bytes.read_u8().unwrap();
+    }
+
+    /// Parse an IPv4 packet from `bytes`, up to its total length. A packet cut short by the
+    /// end of `bytes`, as when a switch sends only the start of a packet to the controller, is
+    /// parsed as far as it goes with its transport checksum left unchecked.
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Ip, ParseError> {
+        if remaining(bytes) < size_of::<IpNet>() {
+            return Err(ParseError::Truncated);
+        }
+        let start = bytes.position() as usize;
+        let vhl = bytes.read_u8()?;
         if (vhl >> 4) != 4 {
-            return None;
+            return Err(ParseError::UnknownValue("ip version", (vhl >> 4) as u64));
         }
         let ihl = vhl & 0x0f;
-        let tos = bytes.read_u8().unwrap();
-        bytes.consume(2);
-        let ident = bytes.read_u16::<BigEndian>().unwrap();
-        let frag = bytes.read_u16::<BigEndian>().unwrap();
+        let tos = bytes.read_u8()?;
+        let total_len = bytes.read_u16::<BigEndian>()? as usize;
+        let ident = bytes.read_u16::<BigEndian>()?;
+        let frag = bytes.read_u16::<BigEndian>()?;
         let flags = Flags::of_int((frag as u32) >> 13);
-        let ttl = bytes.read_u8().unwrap();
-        let proto = bytes.read_u8().unwrap();
-        let chksum = bytes.read_u16::<BigEndian>().unwrap();
-        let src = bytes.read_u32::<BigEndian>().unwrap();
-        let dst = bytes.read_u32::<BigEndian>().unwrap();
-        let options_len = (ihl * 4) as usize - size_of::<IpNet>();
-        let mut options = vec![0; options_len];
-        bytes.read_exact(&mut options).unwrap();
-        let tp = match proto {
-            t if t == (IpProto::IpICMP as u8) => {
-                let icmp = Icmp::parse(bytes);
-                if icmp.is_some() {
-                    Tp::Icmp(icmp.unwrap())
-                } else {
-                    Tp::Unparsable(proto, bytes.fill_buf().unwrap().to_vec())
-                }
-            }
-            t if t == (IpProto::IpTCP as u8) => {
-                let tcp = Tcp::parse(bytes);
-                if tcp.is_some() {
-                    Tp::Tcp(tcp.unwrap())
-                } else {
-                    Tp::Unparsable(proto, bytes.fill_buf().unwrap().to_vec())
-                }
-            }
-            t if t == (IpProto::IpUDP as u8) => {
-                let udp = Udp::parse(bytes);
-                if udp.is_some() {
-                    Tp::Udp(udp.unwrap())
-                } else {
-                    Tp::Unparsable(proto, bytes.fill_buf().unwrap().to_vec())
-                }
-            }
-            _ => Tp::Unparsable(proto, bytes.fill_buf().unwrap().to_vec()),
+        let ttl = bytes.read_u8()?;
+        let proto = bytes.read_u8()?;
+        let chksum = bytes.read_u16::<BigEndian>()?;
+        let src = bytes.read_u32::<BigEndian>()?;
+        let dst = bytes.read_u32::<BigEndian>()?;
+        let header_len = ihl as usize * 4;
+        if header_len < size_of::<IpNet>() {
+            return Err(ParseError::BadLength("ip ihl", ihl as usize));
+        }
+        if total_len < header_len {
+            return Err(ParseError::BadLength("ip total length", total_len));
+        }
+        let mut options = vec![0; header_len - size_of::<IpNet>()];
+        bytes.read_exact(&mut options)?;
+        let chksum_valid = checksum(0, &bytes.get_ref()[start..start + header_len]) == 0;
+        // Anything past the total length is ethernet padding.
+        let end = min(start + total_len, bytes.get_ref().len());
+        let data = bytes.get_ref()[start + header_len..end].to_vec();
+        bytes.set_position(end as u64);
+        let frag = frag & 0x1fff;
+        let tp = if frag == 0 {
+            let complete = end == start + total_len && !flags.more_fragments;
+            Tp::parse(proto, data, ipv4_addr_sum(src, dst), complete)
+        } else {
+            // Only the first fragment starts with the transport header.
+            Tp::Unparsable(proto, data)
         };
-        Some(Ip {
+        Ok(Ip {
             tos: tos,
             ident: ident,
             flags: flags,
             frag: frag,
             ttl: ttl,
             chksum: chksum,
+            chksum_valid: Some(chksum_valid),
             src: src,
             dst: dst,
             options: options,
             tp: tp,
         })
     }
+
+    /// Marshal `ip` with its header length, total length and all checksums computed. Options
+    /// are padded with zeros to a multiple of 4 bytes.
+    fn marshal(ip: &Ip, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        let options_len = ip.options.len() + options_pad(ip.options.len());
+        let ihl = (size_of::<IpNet>() + options_len) / 4;
+        bytes.write_u8(0x40 | ihl as u8).unwrap();
+        bytes.write_u8(ip.tos).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u16::<BigEndian>(ip.ident).unwrap();
+        bytes.write_u16::<BigEndian>((ip.flags.to_int() << 13) as u16 | (ip.frag & 0x1fff))
+            .unwrap();
+        bytes.write_u8(ip.ttl).unwrap();
+        bytes.write_u8(Tp::proto(&ip.tp)).unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u32::<BigEndian>(ip.src).unwrap();
+        bytes.write_u32::<BigEndian>(ip.dst).unwrap();
+        bytes.extend_from_slice(&ip.options);
+        for _ in ip.options.len()..options_len {
+            bytes.write_u8(0).unwrap();
+        }
+        let header_end = bytes.len();
+        Tp::marshal(&ip.tp, ipv4_addr_sum(ip.src, ip.dst), bytes);
+        let total_len = bytes.len() - start;
+        BigEndian::write_u16(&mut bytes[start + 2..start + 4], total_len as u16);
+        let chksum = checksum(0, &bytes[start..header_end]);
+        BigEndian::write_u16(&mut bytes[start + 10..start + 12], chksum);
+    }
+}
+
+/// IPv6 extension header.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ipv6Ext {
+    /// Hop-by-hop options, following the header's next header and length fields.
+    HopByHop(Vec<u8>),
+    /// Routing header body, following its next header and length fields.
+    Routing(Vec<u8>),
+    Fragment {
+        /// Fragment offset, in units of 8 bytes.
+        offset: u16,
+        more_fragments: bool,
+        ident: u32,
+    },
+    /// Destination options, following the header's next header and length fields.
+    DestOpts(Vec<u8>),
+    /// Authentication header body, following its next header and length fields.
+    Auth(Vec<u8>),
+}
+
+#[repr(u8)]
+enum Ipv6ExtType {
+    HopByHop = 0,
+    Routing = 43,
+    Fragment = 44,
+    Auth = 51,
+    DestOpts = 60,
+}
+
+impl Ipv6Ext {
+    fn typ(ext: &Ipv6Ext) -> u8 {
+        match *ext {
+            Ipv6Ext::HopByHop(_) => Ipv6ExtType::HopByHop as u8,
+            Ipv6Ext::Routing(_) => Ipv6ExtType::Routing as u8,
+            Ipv6Ext::Fragment { .. } => Ipv6ExtType::Fragment as u8,
+            Ipv6Ext::DestOpts(_) => Ipv6ExtType::DestOpts as u8,
+            Ipv6Ext::Auth(_) => Ipv6ExtType::Auth as u8,
+        }
+    }
+
+    /// Read the next header and length fields of an extension header whose length counts
+    /// units of `unit` bytes beyond the first `skip` units, then the rest of its body.
+    fn read_header(bytes: &mut Cursor<Vec<u8>>,
+                   unit: usize,
+                   skip: usize)
+                   -> Result<(u8, Vec<u8>), ParseError> {
+        let next = bytes.read_u8()?;
+        let len = (bytes.read_u8()? as usize + skip) * unit;
+        let mut body = vec![0; len - 2];
+        bytes.read_exact(&mut body)?;
+        Ok((next, body))
+    }
+
+    /// Parse the extension header of type `typ` from `bytes`, returning it along with the type
+    /// of the header following it, or `None` if `typ` is not an extension header.
+    fn parse(typ: u8,
+             bytes: &mut Cursor<Vec<u8>>)
+             -> Result<Option<(Ipv6Ext, u8)>, ParseError> {
+        let ext = match typ {
+            t if t == (Ipv6ExtType::HopByHop as u8) => {
+                let (next, body) = Ipv6Ext::read_header(bytes, 8, 1)?;
+                (Ipv6Ext::HopByHop(body), next)
+            }
+            t if t == (Ipv6ExtType::Routing as u8) => {
+                let (next, body) = Ipv6Ext::read_header(bytes, 8, 1)?;
+                (Ipv6Ext::Routing(body), next)
+            }
+            t if t == (Ipv6ExtType::Fragment as u8) => {
+                let next = bytes.read_u8()?;
+                bytes.consume(1);
+                let offset = bytes.read_u16::<BigEndian>()?;
+                let ext = Ipv6Ext::Fragment {
+                    offset: offset >> 3,
+                    more_fragments: test_bit(0, offset as u64),
+                    ident: bytes.read_u32::<BigEndian>()?,
+                };
+                (ext, next)
+            }
+            t if t == (Ipv6ExtType::DestOpts as u8) => {
+                let (next, body) = Ipv6Ext::read_header(bytes, 8, 1)?;
+                (Ipv6Ext::DestOpts(body), next)
+            }
+            t if t == (Ipv6ExtType::Auth as u8) => {
+                let (next, body) = Ipv6Ext::read_header(bytes, 4, 2)?;
+                (Ipv6Ext::Auth(body), next)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(ext))
+    }
+
+    /// Marshal `ext`, followed by a header of type `next`. Bodies are padded with zeros to a
+    /// multiple of 8 bytes.
+    fn marshal(ext: &Ipv6Ext, next: u8, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        bytes.write_u8(next).unwrap();
+        bytes.write_u8(0).unwrap();
+        match *ext {
+            Ipv6Ext::HopByHop(ref body) |
+            Ipv6Ext::Routing(ref body) |
+            Ipv6Ext::DestOpts(ref body) |
+            Ipv6Ext::Auth(ref body) => bytes.extend_from_slice(body),
+            Ipv6Ext::Fragment { offset, more_fragments, ident } => {
+                bytes.write_u16::<BigEndian>(offset << 3 | more_fragments as u16).unwrap();
+                bytes.write_u32::<BigEndian>(ident).unwrap();
+            }
+        }
+        while (bytes.len() - start) % 8 != 0 {
+            bytes.write_u8(0).unwrap();
+        }
+        let len = bytes.len() - start;
+        bytes[start + 1] = match *ext {
+            Ipv6Ext::Fragment { .. } => 0,
+            Ipv6Ext::Auth(_) => (len / 4 - 2) as u8,
+            _ => (len / 8 - 1) as u8,
+        };
+    }
+}
+
+/// IPv6 frame of a packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ipv6 {
+    pub traffic_class: u8,
+    pub flow_label: u32,
+    pub hop_limit: u8,
+    pub src: u128,
+    pub dst: u128,
+    /// Extension headers, in the order they appear before the transport header.
+    pub exts: Vec<Ipv6Ext>,
+    pub tp: Tp,
+}
+
+#[repr(packed)]
+struct Ipv6Net(u32, u16, u8, u8, u128, u128);
+
+impl Ipv6 {
+    /// Create an IPv6 packet from `src` to `dst` carrying `tp`, with a hop limit of 64.
+    pub fn new(src: u128, dst: u128, tp: Tp) -> Ipv6 {
+        Ipv6 {
+            traffic_class: 0,
+            flow_label: 0,
+            hop_limit: 64,
+            src: src,
+            dst: dst,
+            exts: vec![],
+            tp: tp,
+        }
+    }
+
+    /// Parse an IPv6 packet from `bytes`, up to its payload length, walking any extension
+    /// headers to the transport header. As for IPv4, a packet cut short by the end of `bytes`
+    /// is parsed as far as it goes with its transport checksum left unchecked.
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Ipv6, ParseError> {
+        if remaining(bytes) < size_of::<Ipv6Net>() {
+            return Err(ParseError::Truncated);
+        }
+        let vtcfl = bytes.read_u32::<BigEndian>()?;
+        if (vtcfl >> 28) != 6 {
+            return Err(ParseError::UnknownValue("ipv6 version", (vtcfl >> 28) as u64));
+        }
+        let payload_len = bytes.read_u16::<BigEndian>()? as usize;
+        let mut next = bytes.read_u8()?;
+        let hop_limit = bytes.read_u8()?;
+        let src = bytes.read_u128::<BigEndian>()?;
+        let dst = bytes.read_u128::<BigEndian>()?;
+        let start = bytes.position() as usize;
+        let end = min(start + payload_len, bytes.get_ref().len());
+        let mut payload = Cursor::new(bytes.get_ref()[start..end].to_vec());
+        bytes.set_position(end as u64);
+        let mut complete = end == start + payload_len;
+        let mut first_fragment = true;
+        let mut exts = vec![];
+        while let Some((ext, ext_next)) = Ipv6Ext::parse(next, &mut payload)? {
+            if let Ipv6Ext::Fragment { offset, more_fragments, .. } = ext {
+                first_fragment = offset == 0;
+                complete = complete && !more_fragments;
+            }
+            exts.push(ext);
+            next = ext_next;
+        }
+        let data = payload.fill_buf()?.to_vec();
+        let tp = if first_fragment {
+            Tp::parse(next, data, ipv6_addr_sum(src, dst), complete)
+        } else {
+            // Only the first fragment starts with the transport header.
+            Tp::Unparsable(next, data)
+        };
+        Ok(Ipv6 {
+            traffic_class: (vtcfl >> 20) as u8,
+            flow_label: vtcfl & 0xfffff,
+            hop_limit: hop_limit,
+            src: src,
+            dst: dst,
+            exts: exts,
+            tp: tp,
+        })
+    }
+
+    /// Marshal `ip` with its payload length, extension header chain and transport checksums
+    /// computed.
+    fn marshal(ip: &Ipv6, bytes: &mut Vec<u8>) {
+        let next = ip.exts.first().map(Ipv6Ext::typ).unwrap_or(Tp::proto(&ip.tp));
+        bytes.write_u32::<BigEndian>(6 << 28 | (ip.traffic_class as u32) << 20 |
+                                      (ip.flow_label & 0xfffff))
+            .unwrap();
+        bytes.write_u16::<BigEndian>(0).unwrap();
+        bytes.write_u8(next).unwrap();
+        bytes.write_u8(ip.hop_limit).unwrap();
+        bytes.write_u128::<BigEndian>(ip.src).unwrap();
+        bytes.write_u128::<BigEndian>(ip.dst).unwrap();
+        let start = bytes.len();
+        for (i, ext) in ip.exts.iter().enumerate() {
+            let next = ip.exts.get(i + 1).map(Ipv6Ext::typ).unwrap_or(Tp::proto(&ip.tp));
+            Ipv6Ext::marshal(ext, next, bytes);
+        }
+        Tp::marshal(&ip.tp, ipv6_addr_sum(ip.src, ip.dst), bytes);
+        let payload_len = bytes.len() - start;
+        BigEndian::write_u16(&mut bytes[start - 36..start - 34], payload_len as u16);
+    }
 }
 
 /// Address resolution protocol (ARP) packet payload.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Arp {
     Query(u64, u32, u32),
     Reply(u64, u32, u64, u32),
@@ -298,40 +1156,59 @@ pub enum Arp {
 struct ArpNet(u16, u16, u8, u8, u16, [u8; 6], u32, [u8; 6], u32);
 
 impl Arp {
-    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Option<Arp> {
-        if bytes.get_ref().len() < size_of::<ArpNet>() {
-            return None;
+    fn parse(bytes: &mut Cursor<Vec<u8>>) -> Result<Arp, ParseError> {
+        if remaining(bytes) < size_of::<ArpNet>() {
+            return Err(ParseError::Truncated);
         }
         bytes.consume(6);
-        let oper = bytes.read_u16::<BigEndian>().unwrap();
+        let oper = bytes.read_u16::<BigEndian>()?;
         let mut sha: [u8; 6] = [0; 6];
         for i in 0..6 {
-            sha[i] = bytes.read_u8().unwrap();
+            sha[i] = bytes.read_u8()?;
         }
-        let spa = bytes.read_u32::<BigEndian>().unwrap();
+        let spa = bytes.read_u32::<BigEndian>()?;
         let mut tha: [u8; 6] = [0; 6];
         for i in 0..6 {
-            tha[i] = bytes.read_u8().unwrap();
+            tha[i] = bytes.read_u8()?;
         }
-        let tpa = bytes.read_u32::<BigEndian>().unwrap();
+        let tpa = bytes.read_u32::<BigEndian>()?;
         match oper {
-            0x0001 => Some(Arp::Query(mac_of_bytes(sha), spa, tpa)),
-            0x0002 => Some(Arp::Reply(mac_of_bytes(sha), spa, mac_of_bytes(tha), tpa)),
-            _ => None,
+            0x0001 => Ok(Arp::Query(mac_of_bytes(sha), spa, tpa)),
+            0x0002 => Ok(Arp::Reply(mac_of_bytes(sha), spa, mac_of_bytes(tha), tpa)),
+            _ => Err(ParseError::UnknownValue("arp oper", oper as u64)),
         }
     }
+
+    /// Marshal `arp` as an ethernet/IPv4 ARP message. Queries have an all-zero target hardware
+    /// address.
+    fn marshal(arp: &Arp, bytes: &mut Vec<u8>) {
+        let (oper, sha, spa, tha, tpa) = match *arp {
+            Arp::Query(sha, spa, tpa) => (0x0001, sha, spa, 0, tpa),
+            Arp::Reply(sha, spa, tha, tpa) => (0x0002, sha, spa, tha, tpa),
+        };
+        bytes.write_u16::<BigEndian>(0x0001).unwrap();
+        bytes.write_u16::<BigEndian>(EthTyp::EthTypIP as u16).unwrap();
+        bytes.write_u8(6).unwrap();
+        bytes.write_u8(4).unwrap();
+        bytes.write_u16::<BigEndian>(oper).unwrap();
+        bytes.extend_from_slice(&bytes_of_mac(sha));
+        bytes.write_u32::<BigEndian>(spa).unwrap();
+        bytes.extend_from_slice(&bytes_of_mac(tha));
+        bytes.write_u32::<BigEndian>(tpa).unwrap();
+    }
 }
 
 /// Represents a packet at the network protocol level.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Nw {
     Ip(Ip),
+    Ipv6(Ipv6),
     Arp(Arp),
     Unparsable(u16, Vec<u8>),
 }
 
 /// Represents a packet at the ethernet protocol level.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Packet {
     pub dl_src: u64,
     pub dl_dst: u64,
@@ -346,57 +1223,165 @@ enum EthTyp {
     EthTypIP = 0x0800,
     EthTypARP = 0x0806,
     EthTypVLAN = 0x8100,
+    EthTypIPv6 = 0x86dd,
 }
 
 impl Packet {
-    pub fn parse(buf: &[u8]) -> Packet {
+    /// Parse an ethernet frame `buf` into a `Packet`. Network and transport headers that cannot
+    /// be parsed are left `Unparsable`; only a truncated ethernet header is an error.
+    pub fn parse(buf: &[u8]) -> Result<Packet, ParseError> {
         let mut bytes = Cursor::new(buf.to_vec());
         let mut dst: [u8; 6] = [0; 6];
         let mut src: [u8; 6] = [0; 6];
-        for i in 0..6 {
-            dst[i] = bytes.read_u8().unwrap();
-        }
-        for i in 0..6 {
-            src[i] = bytes.read_u8().unwrap();
-        }
-        let typ = bytes.read_u16::<BigEndian>().unwrap();
+        bytes.read_exact(&mut dst)?;
+        bytes.read_exact(&mut src)?;
+        let typ = bytes.read_u16::<BigEndian>()?;
         let (tag, dei, pcp, typ) = match typ {
             t if t == (EthTyp::EthTypVLAN as u16) => {
-                let tag_and_pcp = bytes.read_u16::<BigEndian>().unwrap();
+                let tag_and_pcp = bytes.read_u16::<BigEndian>()?;
                 let tag = tag_and_pcp & 0xfff;
                 let dei = (tag_and_pcp & 0x1000) > 0;
                 let pcp = tag_and_pcp >> 13;
-                let typ = bytes.read_u16::<BigEndian>().unwrap();
+                let typ = bytes.read_u16::<BigEndian>()?;
                 (Some(tag), dei, pcp as u8, typ)
             }
             _ => (None, false, 0x0, typ),
         };
+        let pos = bytes.position();
         let nw_header = match typ {
-            t if t == (EthTyp::EthTypIP as u16) => {
-                let ip = Ip::parse(&mut bytes);
-                if ip.is_some() {
-                    Nw::Ip(ip.unwrap())
-                } else {
-                    Nw::Unparsable(typ, bytes.fill_buf().unwrap().to_vec())
-                }
-            }
-            t if t == (EthTyp::EthTypARP as u16) => {
-                let arp = Arp::parse(&mut bytes);
-                if arp.is_some() {
-                    Nw::Arp(arp.unwrap())
-                } else {
-                    Nw::Unparsable(typ, bytes.fill_buf().unwrap().to_vec())
-                }
+            t if t == (EthTyp::EthTypIP as u16) => Ip::parse(&mut bytes).map(Nw::Ip),
+            t if t == (EthTyp::EthTypIPv6 as u16) => Ipv6::parse(&mut bytes).map(Nw::Ipv6),
+            t if t == (EthTyp::EthTypARP as u16) => Arp::parse(&mut bytes).map(Nw::Arp),
+            _ => Err(ParseError::UnsupportedType("ethertype", typ as u64)),
+        };
+        let nw_header = match nw_header {
+            Ok(nw) => nw,
+            Err(_) => {
+                bytes.set_position(pos);
+                Nw::Unparsable(typ, bytes.fill_buf()?.to_vec())
             }
-            _ => Nw::Unparsable(typ, bytes.fill_buf().unwrap().to_vec()),
         };
-        Packet {
+        Ok(Packet {
             dl_src: mac_of_bytes(src),
             dl_dst: mac_of_bytes(dst),
             dl_vlan: tag,
             dl_vlan_dei: dei,
             dl_vlan_pcp: pcp,
             nw: nw_header,
+        })
+    }
+
+    /// Marshal `pk` into an ethernet frame, computing the lengths and checksums of its IPv4
+    /// and transport headers. The frame is not padded to the ethernet minimum length.
+    pub fn marshal(pk: &Packet) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&bytes_of_mac(pk.dl_dst));
+        bytes.extend_from_slice(&bytes_of_mac(pk.dl_src));
+        if let Some(vid) = pk.dl_vlan {
+            let tci = (pk.dl_vlan_pcp as u16) << 13 | (if pk.dl_vlan_dei { 0x1000 } else { 0 }) |
+                      (vid & 0xfff);
+            bytes.write_u16::<BigEndian>(EthTyp::EthTypVLAN as u16).unwrap();
+            bytes.write_u16::<BigEndian>(tci).unwrap();
+        }
+        match pk.nw {
+            Nw::Ip(ref ip) => {
+                bytes.write_u16::<BigEndian>(EthTyp::EthTypIP as u16).unwrap();
+                Ip::marshal(ip, &mut bytes)
+            }
+            Nw::Ipv6(ref ip) => {
+                bytes.write_u16::<BigEndian>(EthTyp::EthTypIPv6 as u16).unwrap();
+                Ipv6::marshal(ip, &mut bytes)
+            }
+            Nw::Arp(ref arp) => {
+                bytes.write_u16::<BigEndian>(EthTyp::EthTypARP as u16).unwrap();
+                Arp::marshal(arp, &mut bytes)
+            }
+            Nw::Unparsable(typ, ref buf) => {
+                bytes.write_u16::<BigEndian>(typ).unwrap();
+                bytes.extend_from_slice(buf)
+            }
+        }
+        bytes
+    }
+}
+
+/// Packet Builder
+///
+/// Builds a `Packet` from its ethernet header and a network or transport payload, filling in
+/// the defaults of every header in between.
+#[derive(Clone, Debug)]
+pub struct PacketBuilder {
+    dl_src: u64,
+    dl_dst: u64,
+    dl_vlan: Option<u16>,
+    dl_vlan_dei: bool,
+    dl_vlan_pcp: u8,
+}
+
+impl PacketBuilder {
+    /// Start building an untagged frame from `dl_src` to `dl_dst`.
+    pub fn new(dl_src: u64, dl_dst: u64) -> PacketBuilder {
+        PacketBuilder {
+            dl_src: dl_src,
+            dl_dst: dl_dst,
+            dl_vlan: None,
+            dl_vlan_dei: false,
+            dl_vlan_pcp: 0,
+        }
+    }
+
+    /// Tag the frame with VLAN id `vid` and priority `pcp`.
+    pub fn vlan(mut self, vid: u16, pcp: u8) -> PacketBuilder {
+        self.dl_vlan = Some(vid);
+        self.dl_vlan_pcp = pcp;
+        self
+    }
+
+    /// Finish the frame with network payload `nw`.
+    pub fn nw(self, nw: Nw) -> Packet {
+        Packet {
+            dl_src: self.dl_src,
+            dl_dst: self.dl_dst,
+            dl_vlan: self.dl_vlan,
+            dl_vlan_dei: self.dl_vlan_dei,
+            dl_vlan_pcp: self.dl_vlan_pcp,
+            nw: nw,
         }
     }
+
+    /// Finish the frame with an ethertype `typ` this module does not model, such as LLDP, and
+    /// its raw payload.
+    pub fn raw(self, typ: u16, payload: Vec<u8>) -> Packet {
+        self.nw(Nw::Unparsable(typ, payload))
+    }
+
+    /// Finish the frame with ARP message `arp`.
+    pub fn arp(self, arp: Arp) -> Packet {
+        self.nw(Nw::Arp(arp))
+    }
+
+    /// Finish the frame with IPv4 packet `ip`.
+    pub fn ip(self, ip: Ip) -> Packet {
+        self.nw(Nw::Ip(ip))
+    }
+
+    /// Finish the frame with IPv6 packet `ip`.
+    pub fn ipv6(self, ip: Ipv6) -> Packet {
+        self.nw(Nw::Ipv6(ip))
+    }
+
+    /// Finish the frame with TCP segment `tcp` in a default IPv4 packet from `src` to `dst`.
+    pub fn tcp(self, src: u32, dst: u32, tcp: Tcp) -> Packet {
+        self.ip(Ip::new(src, dst, Tp::Tcp(tcp)))
+    }
+
+    /// Finish the frame with UDP datagram `udp` in a default IPv4 packet from `src` to `dst`.
+    pub fn udp(self, src: u32, dst: u32, udp: Udp) -> Packet {
+        self.ip(Ip::new(src, dst, Tp::Udp(udp)))
+    }
+
+    /// Finish the frame with ICMP message `icmp` in a default IPv4 packet from `src` to `dst`.
+    pub fn icmp(self, src: u32, dst: u32, icmp: Icmp) -> Packet {
+        self.ip(Ip::new(src, dst, Tp::Icmp(icmp)))
+    }
 }
@@ -0,0 +1,37 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Parse Error
+///
+/// Reasons a buffer received from the network could not be parsed into a message or packet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The buffer ended before the value being parsed was complete.
+    Truncated,
+    /// A field named by the first argument held a value outside of its defined range.
+    UnknownValue(&'static str, u64),
+    /// A length field named by the first argument is inconsistent with the data it describes.
+    BadLength(&'static str, usize),
+    /// A well-formed type named by the first argument that is not supported by this crate.
+    UnsupportedType(&'static str, u64),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Truncated => write!(f, "buffer truncated"),
+            ParseError::UnknownValue(field, v) => write!(f, "unknown value {} for {}", v, field),
+            ParseError::BadLength(field, len) => write!(f, "bad length {} for {}", len, field),
+            ParseError::UnsupportedType(typ, v) => write!(f, "unsupported {} {}", typ, v),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl From<io::Error> for ParseError {
+    fn from(_: io::Error) -> ParseError {
+        ParseError::Truncated
+    }
+}
@@ -0,0 +1,103 @@
+extern crate rust_ofp;
+
+use std::io;
+use std::io::Read;
+
+use rust_ofp::ofp_codec::{decode_frame, MessageReader};
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::parse_error::ParseError;
+
+/// Reader returning the chunks of its data in turn, one chunk per call to `read`.
+struct Chunked {
+    chunks: Vec<Vec<u8>>,
+}
+
+impl Chunked {
+    fn new(chunks: &[&[u8]]) -> Chunked {
+        Chunked { chunks: chunks.iter().rev().map(|chunk| chunk.to_vec()).collect() }
+    }
+
+    /// Reader returning `data` at most `size` bytes at a time.
+    fn trickle(data: &[u8], size: usize) -> Chunked {
+        let chunks: Vec<&[u8]> = data.chunks(size).collect();
+        Chunked::new(&chunks)
+    }
+}
+
+impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.chunks.pop() {
+            Some(chunk) => {
+                buf[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+/// Marshaled frame of message type `typ` with transaction ID `xid` and body `body`.
+fn frame(typ: u8, xid: u32, body: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    OfpHeader::marshal(&mut bytes,
+                       OfpHeader::new(0x01, typ, (OfpHeader::size() + body.len()) as u16, xid));
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+fn read_frame<R: Read>(reader: &mut MessageReader<R>) -> (u8, u32, Vec<u8>) {
+    let (header, body) = reader.read_frame().unwrap().unwrap();
+    (header.type_byte(), header.xid(), body)
+}
+
+#[test]
+fn header_split_across_reads() {
+    let bytes = frame(2, 7, &[1, 2, 3, 4, 5]);
+    let mut reader = MessageReader::new(Chunked::trickle(&bytes, 3));
+    assert_eq!(read_frame(&mut reader), (2, 7, vec![1, 2, 3, 4, 5]));
+    assert!(reader.read_frame().unwrap().is_none());
+}
+
+#[test]
+fn two_frames_in_one_read() {
+    let mut bytes = frame(2, 1, &[9, 9]);
+    bytes.extend(frame(18, 2, &[]));
+    bytes.extend(frame(3, 3, &[8])[..5].to_vec());
+    let rest = frame(3, 3, &[8])[5..].to_vec();
+    let mut reader = MessageReader::new(Chunked::new(&[&bytes, &rest]));
+    assert_eq!(read_frame(&mut reader), (2, 1, vec![9, 9]));
+    assert_eq!(read_frame(&mut reader), (18, 2, vec![]));
+    assert_eq!(read_frame(&mut reader), (3, 3, vec![8]));
+    assert!(reader.read_frame().unwrap().is_none());
+}
+
+#[test]
+fn header_length_too_small() {
+    let mut bytes = frame(0, 1, &[]);
+    bytes[3] = 7;
+    let mut buf = bytes.clone();
+    assert_eq!(decode_frame(&mut buf).err(),
+               Some(ParseError::BadLength("ofp_header", 7)));
+
+    let mut reader = MessageReader::new(Chunked::trickle(&bytes, 2));
+    assert_eq!(reader.read_frame().err().map(|e| e.kind()),
+               Some(io::ErrorKind::InvalidData));
+}
+
+#[test]
+fn eof_in_body() {
+    let bytes = frame(2, 7, &[1, 2, 3, 4, 5]);
+    let mut reader = MessageReader::new(Chunked::trickle(&bytes[..10], 4));
+    assert_eq!(reader.read_frame().err().map(|e| e.kind()),
+               Some(io::ErrorKind::UnexpectedEof));
+}
+
+#[test]
+fn decode_leaves_partial_frame() {
+    let mut buf = frame(2, 7, &[1, 2, 3]);
+    buf.extend(frame(2, 8, &[4])[..6].to_vec());
+    let (header, body) = decode_frame(&mut buf).unwrap().unwrap();
+    assert_eq!((header.xid(), body), (7, vec![1, 2, 3]));
+    assert_eq!(decode_frame(&mut buf).unwrap().map(|(header, _)| header.xid()), None);
+    assert_eq!(buf.len(), 6);
+}
@@ -0,0 +1,671 @@
+extern crate rust_ofp;
+
+use std::any::Any;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_ofp::ofp_controller::openflow0x01::{Keepalive, OF0x01Controller, PendingTransaction,
+                                             ReplyError, Runtime, Switches, TransactionError};
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_hello::{Hello, OFP_VERSION_1_3};
+use rust_ofp::ofp_message::OfpMessage;
+use rust_ofp::openflow0x01::{BadRequest, Error, ErrorType, HelloFailed, PacketIn, Pattern,
+                             PortReason, PortStatus, StatsReq, StatsResp, StatsRespBody,
+                             SwitchFeatures, TableStats};
+use rust_ofp::openflow0x01::message::{add_flow, Message};
+use rust_ofp::openflow0x01::vendor::{VendorKind, VendorRegistry, VendorValue};
+use rust_ofp::parse_error::ParseError;
+
+/// Errors passed to `Recorder::error_message`, as datapath id and transaction ID.
+static ERRORS: Mutex<Vec<(u64, u32)>> = Mutex::new(Vec::new());
+
+/// Port changes passed to `Recorder::port_status`, as datapath id, transaction ID, port
+/// number, and reason.
+static PORT_CHANGES: Mutex<Vec<(u64, u32, u16, PortReason)>> = Mutex::new(Vec::new());
+
+/// Controller recording the errors no pending request was waiting for, and port changes.
+struct Recorder;
+
+impl OF0x01Controller for Recorder {
+    fn new() -> Recorder {
+        Recorder
+    }
+
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
+
+    fn switch_disconnected(&mut self, _: u64) {}
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn error_message(&mut self, sw: u64, xid: u32, _: Error, _: &Switches) {
+        ERRORS.lock().unwrap().push((sw, xid))
+    }
+
+    fn port_status(&mut self, sw: u64, xid: u32, port: PortStatus, _: &Switches) {
+        PORT_CHANGES.lock().unwrap().push((sw, xid, port.desc.port_no, port.reason))
+    }
+}
+
+/// Controller probing switches with an echo request every 20 milliseconds.
+struct Prober;
+
+impl OF0x01Controller for Prober {
+    fn new() -> Prober {
+        Prober
+    }
+
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
+
+    fn switch_disconnected(&mut self, _: u64) {}
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn keepalive(&self) -> Option<Keepalive> {
+        Some(Keepalive {
+            interval: Duration::from_millis(20),
+            timeout: Duration::from_secs(5),
+            max_missed: 3,
+        })
+    }
+}
+
+/// Switches connecting to and disconnecting from `Tracker`, in order.
+static LIFECYCLE: Mutex<Vec<(u64, &'static str)>> = Mutex::new(Vec::new());
+
+/// Controller recording the switches that connect and disconnect.
+struct Tracker;
+
+impl OF0x01Controller for Tracker {
+    fn new() -> Tracker {
+        Tracker
+    }
+
+    fn switch_connected(&mut self, sw: u64, _: SwitchFeatures, _: &Switches) {
+        LIFECYCLE.lock().unwrap().push((sw, "connected"))
+    }
+
+    fn switch_disconnected(&mut self, sw: u64) {
+        LIFECYCLE.lock().unwrap().push((sw, "disconnected"))
+    }
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+}
+
+/// Return the events `Tracker` recorded for switch `dpid`.
+fn lifecycle(dpid: u64) -> Vec<&'static str> {
+    LIFECYCLE.lock().unwrap().iter().filter(|&&(sw, _)| sw == dpid).map(|&(_, e)| e).collect()
+}
+
+/// Echo round-trip times passed to `Watchdog`, with the datapath id of the switch.
+static LATENCIES: Mutex<Vec<(u64, Duration)>> = Mutex::new(Vec::new());
+
+/// Controller probing switches every 20 milliseconds, and giving up on a switch after two
+/// echo requests go unanswered for 100 milliseconds.
+struct Watchdog;
+
+impl OF0x01Controller for Watchdog {
+    fn new() -> Watchdog {
+        Watchdog
+    }
+
+    fn switch_connected(&mut self, sw: u64, _: SwitchFeatures, _: &Switches) {
+        LIFECYCLE.lock().unwrap().push((sw, "connected"))
+    }
+
+    fn switch_disconnected(&mut self, sw: u64) {
+        LIFECYCLE.lock().unwrap().push((sw, "disconnected"))
+    }
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn keepalive(&self) -> Option<Keepalive> {
+        Some(Keepalive {
+            interval: Duration::from_millis(20),
+            timeout: Duration::from_millis(100),
+            max_missed: 2,
+        })
+    }
+
+    fn echo_latency(&mut self, sw: u64, rtt: Duration) {
+        LATENCIES.lock().unwrap().push((sw, rtt))
+    }
+}
+
+/// Controller allowing switches 50 milliseconds to complete their handshake.
+struct Impatient;
+
+impl OF0x01Controller for Impatient {
+    fn new() -> Impatient {
+        Impatient
+    }
+
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
+
+    fn switch_disconnected(&mut self, _: u64) {}
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn handshake_timeout(&self) -> Duration {
+        Duration::from_millis(50)
+    }
+}
+
+/// Vendor messages passed to `Nicira`, parsed or raw.
+static VENDOR_MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Controller coding vendor messages and statistics from Nicira as a big-endian word.
+struct Nicira;
+
+impl OF0x01Controller for Nicira {
+    fn new() -> Nicira {
+        Nicira
+    }
+
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
+
+    fn switch_disconnected(&mut self, _: u64) {}
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn vendor_registry(&self) -> VendorRegistry {
+        let mut registry = VendorRegistry::default();
+        for &kind in &[VendorKind::Message, VendorKind::Stats] {
+            let parser = Box::new(|body: &[u8]| if body.len() == 4 {
+                let word = body.iter().fold(0u32, |acc, &b| acc << 8 | b as u32);
+                Ok(Box::new(word) as VendorValue)
+            } else {
+                Err(ParseError::BadLength("nicira word", body.len()))
+            });
+            let marshaller = Box::new(|value: &dyn Any| {
+                value.downcast_ref::<u32>().map(|&word| {
+                    vec![(word >> 24) as u8, (word >> 16) as u8, (word >> 8) as u8, word as u8]
+                })
+            });
+            registry.register(kind, 0x2320, parser, marshaller);
+        }
+        registry
+    }
+
+    fn vendor_message(&mut self,
+                      sw: Option<u64>,
+                      _: u32,
+                      vendor_id: u32,
+                      body: Vec<u8>,
+                      _: &Switches) {
+        VENDOR_MESSAGES.lock().unwrap().push(format!("{:?} {:x} raw {:?}", sw, vendor_id, body))
+    }
+
+    fn vendor_value(&mut self,
+                    sw: Option<u64>,
+                    _: u32,
+                    kind: VendorKind,
+                    vendor_id: u32,
+                    value: VendorValue,
+                    _: &Switches) {
+        let word = value.downcast::<u32>().unwrap();
+        VENDOR_MESSAGES.lock()
+            .unwrap()
+            .push(format!("{:?} {:?} {:x} parsed {}", sw, kind, vendor_id, word))
+    }
+}
+
+/// Read the next message the controller sent on `stream`.
+fn read_msg(stream: &mut TcpStream) -> (u32, Message) {
+    let mut buf = [0; 8];
+    stream.read_exact(&mut buf).unwrap();
+    let header = OfpHeader::parse(buf);
+    let mut body = vec![0; header.length() - OfpHeader::size()];
+    stream.read_exact(&mut body).unwrap();
+    Message::parse(&header, &body).unwrap()
+}
+
+fn send_msg(stream: &mut TcpStream, xid: u32, msg: Message) {
+    stream.write_all(&Message::marshal(xid, msg).unwrap()).unwrap()
+}
+
+/// Open a connection to `rt`, returning the switch end once the controller's hello is read.
+fn accept<Cntl: OF0x01Controller + Send + 'static>(rt: &Runtime<Cntl>) -> TcpStream {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    rt.spawn_connection(listener.accept().unwrap().0);
+    match read_msg(&mut stream) {
+        (_, Message::Hello) => stream,
+        (_, msg) => panic!("expected hello, got {:?}", msg),
+    }
+}
+
+/// Marshaled features reply with transaction ID `xid` from a switch with datapath id `dpid`
+/// and no ports.
+fn features_reply(xid: u32, dpid: u64) -> Vec<u8> {
+    let mut bytes = vec![0x01, 6, 0, 32];
+    for shift in &[24, 16, 8, 0] {
+        bytes.push((xid >> shift) as u8)
+    }
+    for shift in &[56, 48, 40, 32, 24, 16, 8, 0] {
+        bytes.push((dpid >> shift) as u8)
+    }
+    bytes.extend_from_slice(&[0; 16]);
+    bytes
+}
+
+/// Marshaled port status with transaction ID `xid` reporting that port `port_no` of a switch
+/// was modified.
+fn port_modified(xid: u32, port_no: u16) -> Vec<u8> {
+    let mut bytes = vec![0x01, 12, 0, 64];
+    for shift in &[24, 16, 8, 0] {
+        bytes.push((xid >> shift) as u8)
+    }
+    bytes.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0]);
+    bytes.extend_from_slice(&[(port_no >> 8) as u8, port_no as u8]);
+    bytes.extend_from_slice(&[0; 46]);
+    bytes
+}
+
+/// Connect a switch with datapath id `dpid` to `rt`, completing the handshake.
+fn connect<Cntl: OF0x01Controller + Send + 'static>(rt: &Runtime<Cntl>, dpid: u64) -> TcpStream {
+    let mut stream = accept(rt);
+    send_msg(&mut stream, 1, Message::Hello);
+    let (xid, _) = read_msg(&mut stream);
+    stream.write_all(&features_reply(xid, dpid)).unwrap();
+    wait_until(|| rt.switches().is_connected(dpid));
+    stream
+}
+
+fn wait_until<F: Fn() -> bool>(cond: F) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+        thread::sleep(Duration::from_millis(10))
+    }
+}
+
+fn bad_request() -> Message {
+    Message::Error(Error::Error(ErrorType::BadRequest(BadRequest::BadType), vec![]))
+}
+
+/// Answer every request in `xids` with an error, and wait for the errors to reach the
+/// controller, which they only do if no request is still waiting for them.
+fn assert_forgotten(stream: &mut TcpStream, dpid: u64, xids: &[u32]) {
+    for &xid in xids {
+        send_msg(stream, xid, bad_request())
+    }
+    wait_until(|| {
+        let errors = ERRORS.lock().unwrap();
+        xids.iter().all(|&xid| errors.contains(&(dpid, xid)))
+    })
+}
+
+/// Send a transaction of two flowmods to switch `dpid`, returning the transaction ID of each
+/// message read from the switch end of the connection.
+fn send_transaction(rt: &Runtime<Recorder>,
+                    stream: &mut TcpStream,
+                    dpid: u64)
+                    -> (PendingTransaction, Vec<u32>) {
+    let mut transaction = rt.switches().transaction(dpid);
+    transaction.flow_mod(add_flow(1, Pattern::match_all(), vec![]))
+        .flow_mod(add_flow(2, Pattern::match_all(), vec![]));
+    let pending = transaction.commit().unwrap();
+    let xids = (0..3).map(|_| read_msg(stream).0).collect();
+    (pending, xids)
+}
+
+/// Statistics of table `table_id`, with no entries.
+fn table_stats(table_id: u8) -> TableStats {
+    TableStats {
+        table_id: table_id,
+        name: format!("table {}", table_id),
+        wildcards: 0,
+        max_entries: 0,
+        active_count: 0,
+        lookup_count: 0,
+        matched_count: 0,
+    }
+}
+
+#[test]
+fn matching_reply_resolves_request() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 16);
+    let reply = rt.switches().send_request(16, Message::BarrierRequest).unwrap();
+    let (xid, _) = read_msg(&mut stream);
+    assert_eq!(xid, reply.xid());
+    send_msg(&mut stream, xid + 1, Message::BarrierReply);
+    send_msg(&mut stream, xid, Message::BarrierReply);
+    assert_eq!(reply.wait_timeout(Duration::from_secs(5)).unwrap(), Message::BarrierReply);
+}
+
+#[test]
+fn matching_error_resolves_request() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 17);
+    let reply = rt.switches().send_request(17, Message::GetConfigRequest).unwrap();
+    let (xid, _) = read_msg(&mut stream);
+    send_msg(&mut stream, xid, bad_request());
+    match reply.wait_timeout(Duration::from_secs(5)) {
+        Err(ReplyError::Switch(Error::Error(ErrorType::BadRequest(BadRequest::BadType), _))) => (),
+        other => panic!("unexpected reply {:?}", other),
+    }
+    assert!(!ERRORS.lock().unwrap().contains(&(17, xid)));
+}
+
+#[test]
+fn multipart_stats_reply_merged() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 18);
+    let reply = rt.switches().send_request(18, Message::StatsRequest(StatsReq::Table)).unwrap();
+    let (xid, _) = read_msg(&mut stream);
+    let part = |more, table_id| {
+        Message::StatsReply(StatsResp {
+            more: more,
+            body: StatsRespBody::Table(vec![table_stats(table_id)]),
+        })
+    };
+    send_msg(&mut stream, xid, part(true, 0));
+    send_msg(&mut stream, xid, part(true, 1));
+    send_msg(&mut stream, xid + 1, bad_request());
+    wait_until(|| ERRORS.lock().unwrap().contains(&(18, xid + 1)));
+    assert!(reply.try_wait().is_none());
+    send_msg(&mut stream, xid, part(false, 2));
+    let stats = StatsResp {
+        more: false,
+        body: StatsRespBody::Table(vec![table_stats(0), table_stats(1), table_stats(2)]),
+    };
+    assert_eq!(reply.wait_timeout(Duration::from_secs(5)).unwrap(),
+               Message::StatsReply(stats));
+}
+
+#[test]
+fn dropped_reply_forgets_xid() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 1);
+    let reply = rt.switches().send_request(1, Message::BarrierRequest).unwrap();
+    let (xid, _) = read_msg(&mut stream);
+    drop(reply);
+    assert_forgotten(&mut stream, 1, &[xid]);
+}
+
+#[test]
+fn timed_out_reply_forgets_xid() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 2);
+    let reply = rt.switches().send_request(2, Message::BarrierRequest).unwrap();
+    let (xid, _) = read_msg(&mut stream);
+    match reply.wait_timeout(Duration::from_millis(20)) {
+        Err(ReplyError::Timeout) => (),
+        other => panic!("unexpected reply {:?}", other.map(|_| ())),
+    }
+    assert_forgotten(&mut stream, 2, &[xid]);
+}
+
+#[test]
+fn dropped_transaction_forgets_xids() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 3);
+    let (pending, xids) = send_transaction(&rt, &mut stream, 3);
+    assert_eq!(pending.xids(), &xids[..2]);
+    drop(pending);
+    assert_forgotten(&mut stream, 3, &xids);
+}
+
+#[test]
+fn timed_out_transaction_forgets_xids() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 4);
+    let (pending, xids) = send_transaction(&rt, &mut stream, 4);
+    match pending.wait_timeout(Duration::from_millis(20)) {
+        Err(TransactionError::Incomplete(ReplyError::Timeout)) => (),
+        other => panic!("unexpected outcome {:?}", other),
+    }
+    assert_forgotten(&mut stream, 4, &xids);
+}
+
+#[test]
+fn keepalive_starts_after_handshake() {
+    let rt = Runtime::<Prober>::new();
+    let mut stream = accept(&rt);
+    stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    assert_eq!(stream.read(&mut [0; 8]).map_err(|e| e.kind()).err(),
+               Some(io::ErrorKind::WouldBlock));
+    send_msg(&mut stream, 1, Message::Hello);
+    let (xid, msg) = read_msg(&mut stream);
+    match msg {
+        Message::FeaturesReq => (),
+        msg => panic!("expected features request, got {:?}", msg),
+    }
+    assert_eq!(stream.read(&mut [0; 8]).map_err(|e| e.kind()).err(),
+               Some(io::ErrorKind::WouldBlock));
+    stream.set_read_timeout(None).unwrap();
+    stream.write_all(&features_reply(xid, 5)).unwrap();
+    match read_msg(&mut stream) {
+        (_, Message::EchoRequest(_)) => (),
+        (_, msg) => panic!("expected echo request, got {:?}", msg),
+    }
+}
+
+#[test]
+fn message_before_hello_fails_handshake() {
+    let rt = Runtime::<Recorder>::new();
+    let echo = Message::marshal(2, Message::EchoRequest(vec![])).unwrap();
+    for bytes in [features_reply(1, 6), echo] {
+        let mut stream = accept(&rt);
+        stream.write_all(&bytes).unwrap();
+        match read_msg(&mut stream) {
+            (_, Message::Error(Error::Error(ErrorType::HelloFailed(HelloFailed::Incompatible),
+                                            _))) => (),
+            (_, msg) => panic!("expected hello failed error, got {:?}", msg),
+        }
+        assert_eq!(stream.read(&mut [0; 8]).unwrap(), 0);
+        assert!(!rt.switches().is_connected(6));
+    }
+}
+
+#[test]
+fn openflow_1_3_switch_fails_handshake() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = accept(&rt);
+    stream.write_all(&Hello::marshal(1, &Hello::new(&[OFP_VERSION_1_3]))).unwrap();
+    match read_msg(&mut stream) {
+        (_, Message::Error(Error::Error(ErrorType::HelloFailed(HelloFailed::Incompatible),
+                                        _))) => (),
+        (_, msg) => panic!("expected hello failed error, got {:?}", msg),
+    }
+    assert_eq!(stream.read(&mut [0; 8]).unwrap(), 0);
+}
+
+#[test]
+fn registered_vendor_messages_parsed() {
+    let rt = Runtime::<Nicira>::new();
+    let mut stream = connect(&rt, 8);
+    let vendor = |vendor_id, body| {
+        Message::Vendor {
+            vendor_id: vendor_id,
+            body: body,
+        }
+    };
+    send_msg(&mut stream, 1, vendor(0x2320, vec![0, 0, 1, 2]));
+    send_msg(&mut stream, 2, vendor(0x2320, vec![1]));
+    send_msg(&mut stream, 3, vendor(0x2321, vec![1]));
+    let stats = StatsResp {
+        more: false,
+        body: StatsRespBody::Vendor(0x2320, vec![0, 0, 0, 3]),
+    };
+    send_msg(&mut stream, 4, Message::StatsReply(stats));
+    wait_until(|| VENDOR_MESSAGES.lock().unwrap().len() == 3);
+    assert_eq!(*VENDOR_MESSAGES.lock().unwrap(),
+               vec!["Some(8) Message 2320 parsed 258".to_string(),
+                    "Some(8) 2321 raw [1]".to_string(),
+                    "Some(8) Stats 2320 parsed 3".to_string()]);
+}
+
+#[test]
+fn vendor_values_marshaled() {
+    let rt = Runtime::<Nicira>::new();
+    let mut stream = connect(&rt, 9);
+    rt.switches().send_vendor_value(9, 5, 0x2320, &258u32).unwrap();
+    assert_eq!(read_msg(&mut stream),
+               (5,
+                Message::Vendor {
+                    vendor_id: 0x2320,
+                    body: vec![0, 0, 1, 2],
+                }));
+    assert_eq!(rt.switches().send_vendor_value(9, 6, 0x2320, &"258").unwrap_err().kind(),
+               io::ErrorKind::InvalidInput);
+    assert_eq!(rt.switches().send_vendor_value(9, 7, 0x2321, &258u32).unwrap_err().kind(),
+               io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn incomplete_handshake_times_out() {
+    let rt = Runtime::<Impatient>::new();
+    let silent = accept(&rt);
+    let mut greeted = accept(&rt);
+    send_msg(&mut greeted, 1, Message::Hello);
+    match read_msg(&mut greeted) {
+        (_, Message::FeaturesReq) => (),
+        (_, msg) => panic!("expected features request, got {:?}", msg),
+    }
+    for mut stream in [silent, greeted] {
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(stream.read(&mut [0; 8]).unwrap(), 0);
+    }
+}
+
+#[test]
+fn reconnecting_switch_replaces_connection() {
+    let rt = Runtime::<Tracker>::new();
+    let mut first = connect(&rt, 10);
+    let mut second = connect(&rt, 10);
+    wait_until(|| lifecycle(10).len() == 3);
+    assert_eq!(lifecycle(10), vec!["connected", "disconnected", "connected"]);
+    first.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(first.read(&mut [0; 8]).unwrap(), 0);
+    rt.switches().send_message(10, 7, Message::BarrierRequest).unwrap();
+    assert_eq!(read_msg(&mut second), (7, Message::BarrierRequest));
+}
+
+#[test]
+fn switch_disconnected_once() {
+    let rt = Runtime::<Tracker>::new();
+    let only = connect(&rt, 11);
+    drop(only);
+    wait_until(|| lifecycle(11).len() == 2);
+    let first = connect(&rt, 12);
+    let second = connect(&rt, 12);
+    wait_until(|| lifecycle(12).len() == 3);
+    drop(first);
+    thread::sleep(Duration::from_millis(100));
+    assert!(rt.switches().is_connected(12));
+    drop(second);
+    wait_until(|| lifecycle(12).len() == 4);
+    assert!(!rt.switches().is_connected(12));
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(lifecycle(11), vec!["connected", "disconnected"]);
+    assert_eq!(lifecycle(12), vec!["connected", "disconnected", "connected", "disconnected"]);
+}
+
+#[test]
+fn keepalive_off_by_default() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 13);
+    stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    assert_eq!(stream.read(&mut [0; 8]).map_err(|e| e.kind()).err(),
+               Some(io::ErrorKind::WouldBlock));
+}
+
+#[test]
+fn missed_echoes_disconnect_switch() {
+    let rt = Runtime::<Watchdog>::new();
+    let mut stream = connect(&rt, 14);
+    let start = Instant::now();
+    let mut echoes = 0;
+    loop {
+        let mut buf = [0; 8];
+        if stream.read(&mut buf[..1]).unwrap() == 0 {
+            break;
+        }
+        stream.read_exact(&mut buf[1..]).unwrap();
+        assert_eq!(buf[1], 2, "expected echo request");
+        echoes += 1;
+    }
+    assert_eq!(echoes, 2);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    wait_until(|| lifecycle(14).len() == 2);
+    assert_eq!(lifecycle(14), vec!["connected", "disconnected"]);
+    assert!(!rt.switches().is_connected(14));
+}
+
+#[test]
+fn echo_latency_reported() {
+    let rt = Runtime::<Watchdog>::new();
+    let mut stream = connect(&rt, 15);
+    for _ in 0..3 {
+        let (xid, msg) = read_msg(&mut stream);
+        let bytes = match msg {
+            Message::EchoRequest(bytes) => bytes,
+            msg => panic!("expected echo request, got {:?}", msg),
+        };
+        thread::sleep(Duration::from_millis(30));
+        send_msg(&mut stream, xid, Message::EchoReply(bytes));
+    }
+    wait_until(|| LATENCIES.lock().unwrap().iter().filter(|&&(sw, _)| sw == 15).count() == 3);
+    for &(sw, rtt) in LATENCIES.lock().unwrap().iter() {
+        if sw == 15 {
+            assert!(rtt >= Duration::from_millis(30) && rtt < Duration::from_millis(100));
+        }
+    }
+    assert!(rt.switches().is_connected(15));
+}
+
+#[test]
+fn unknown_message_type_rejected() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 19);
+    let frame = [0x01, 0x1a, 0, 8, 0, 0, 0, 0x99];
+    stream.write_all(&frame).unwrap();
+    match read_msg(&mut stream) {
+        (0x99, Message::Error(Error::Error(ErrorType::BadRequest(BadRequest::BadType), data))) => {
+            assert_eq!(data, frame)
+        }
+        (xid, msg) => panic!("expected bad type error, got {:?} with xid {}", msg, xid),
+    }
+    send_msg(&mut stream, 2, Message::EchoRequest(vec![4]));
+    assert_eq!(read_msg(&mut stream), (2, Message::EchoReply(vec![4])));
+    assert!(rt.switches().is_connected(19));
+}
+
+#[test]
+fn two_switches_share_runtime() {
+    let rt = Runtime::<Recorder>::new();
+    let mut first = connect(&rt, 20);
+    let mut second = connect(&rt, 21);
+    let ids = rt.switches().switch_ids();
+    assert!(ids.contains(&20) && ids.contains(&21));
+    send_msg(&mut first, 0x20, bad_request());
+    send_msg(&mut second, 0x21, bad_request());
+    wait_until(|| {
+        let errors = ERRORS.lock().unwrap();
+        errors.contains(&(20, 0x20)) && errors.contains(&(21, 0x21))
+    });
+    assert!(!ERRORS.lock().unwrap().contains(&(21, 0x20)));
+    assert!(!ERRORS.lock().unwrap().contains(&(20, 0x21)));
+    rt.switches().send_message(20, 42, Message::BarrierRequest).unwrap();
+    assert_eq!(read_msg(&mut first), (42, Message::BarrierRequest));
+    second.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+    assert_eq!(second.read(&mut [0; 8]).map_err(|e| e.kind()).err(),
+               Some(io::ErrorKind::WouldBlock));
+}
+
+#[test]
+fn port_status_delivered() {
+    let rt = Runtime::<Recorder>::new();
+    let mut stream = connect(&rt, 22);
+    stream.write_all(&port_modified(3, 7)).unwrap();
+    wait_until(|| PORT_CHANGES.lock().unwrap().iter().any(|change| change.0 == 22));
+    let changes = PORT_CHANGES.lock().unwrap();
+    assert_eq!(changes.iter().filter(|change| change.0 == 22).collect::<Vec<_>>(),
+               vec![&(22, 3, 7, PortReason::PortModify)]);
+}
@@ -0,0 +1,103 @@
+extern crate rust_ofp;
+
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_hello::{Hello, OFPT_HELLO, OFP_VERSION_1_0, OFP_VERSION_1_3};
+use rust_ofp::parse_error::ParseError;
+
+/// Parse the marshaled hello `bytes`.
+fn parse(bytes: &[u8]) -> Result<Hello, ParseError> {
+    let mut header = [0; 8];
+    header.copy_from_slice(&bytes[..8]);
+    Hello::parse(&OfpHeader::parse(header), &bytes[8..])
+}
+
+/// Marshaled hello of version `version` with body `body`.
+fn frame(version: u8, body: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    OfpHeader::marshal(&mut bytes,
+                       OfpHeader::new(version,
+                                      OFPT_HELLO,
+                                      (OfpHeader::size() + body.len()) as u16,
+                                      0));
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+fn hello(version: u8, versions: Option<Vec<u8>>) -> Hello {
+    Hello {
+        version: version,
+        versions: versions,
+    }
+}
+
+#[test]
+fn negotiate_with_bitmap() {
+    let theirs = hello(OFP_VERSION_1_3, Some(vec![OFP_VERSION_1_0, OFP_VERSION_1_3]));
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0]), Some(OFP_VERSION_1_0));
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0, OFP_VERSION_1_3]),
+               Some(OFP_VERSION_1_3));
+    assert_eq!(theirs.negotiate(&[0x02, OFP_VERSION_1_3]), Some(OFP_VERSION_1_3));
+}
+
+#[test]
+fn negotiate_without_bitmap() {
+    let theirs = hello(OFP_VERSION_1_3, None);
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0]), Some(OFP_VERSION_1_0));
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0, OFP_VERSION_1_3]),
+               Some(OFP_VERSION_1_3));
+    let theirs = hello(OFP_VERSION_1_0, None);
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0, OFP_VERSION_1_3]),
+               Some(OFP_VERSION_1_0));
+}
+
+#[test]
+fn negotiate_no_common_version() {
+    let theirs = hello(OFP_VERSION_1_3, Some(vec![OFP_VERSION_1_3]));
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0]), None);
+    let theirs = hello(0x02, None);
+    assert_eq!(theirs.negotiate(&[OFP_VERSION_1_0, OFP_VERSION_1_3]), None);
+    assert_eq!(theirs.negotiate(&[]), None);
+}
+
+#[test]
+fn marshal_single_word_bitmap() {
+    let bytes = Hello::marshal(7, &Hello::new(&[OFP_VERSION_1_3, OFP_VERSION_1_0]));
+    assert_eq!(bytes,
+               vec![0x04, 0, 0, 16, 0, 0, 0, 7, 0, 1, 0, 8, 0, 0, 0, 0x12]);
+}
+
+#[test]
+fn marshal_multi_word_bitmap() {
+    let bytes = Hello::marshal(0, &Hello::new(&[1, 33, 70]));
+    assert_eq!(bytes,
+               vec![70, 0, 0, 24, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0,
+                    0x40]);
+    assert_eq!(parse(&bytes), Ok(hello(70, Some(vec![1, 33, 70]))));
+}
+
+#[test]
+fn parse_without_bitmap() {
+    assert_eq!(parse(&frame(OFP_VERSION_1_0, &[])),
+               Ok(hello(OFP_VERSION_1_0, None)));
+}
+
+#[test]
+fn parse_skips_unknown_elements() {
+    let body = [0xff, 0xff, 0, 5, 9, 0, 0, 0, 0, 1, 0, 8, 0, 0, 0, 0x12];
+    assert_eq!(parse(&frame(OFP_VERSION_1_3, &body)),
+               Ok(hello(OFP_VERSION_1_3, Some(vec![OFP_VERSION_1_0, OFP_VERSION_1_3]))));
+}
+
+#[test]
+fn parse_bad_element_length() {
+    assert_eq!(parse(&frame(OFP_VERSION_1_3, &[0, 1, 0, 3, 0, 0, 0, 0])),
+               Err(ParseError::BadLength("ofp_hello_elem", 3)));
+}
+
+#[test]
+fn roundtrip() {
+    for versions in [vec![1], vec![1, 4], vec![0, 31, 32, 63, 64, 255]] {
+        let sent = Hello::new(&versions);
+        assert_eq!(parse(&Hello::marshal(3, &sent)), Ok(sent));
+    }
+}
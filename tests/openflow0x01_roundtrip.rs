@@ -0,0 +1,756 @@
+extern crate rust_ofp;
+extern crate proptest;
+
+use std::any::Any;
+
+use proptest::prelude::*;
+
+use rust_ofp::marshal_error::MarshalError;
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_message::OfpMessage;
+use rust_ofp::openflow0x01::*;
+use rust_ofp::openflow0x01::message::Message;
+use rust_ofp::openflow0x01::vendor::{VendorKind, VendorRegistry, VendorValue};
+use rust_ofp::parse_error::ParseError;
+
+/// Marshal `msg`, then parse it back from the resulting buffer.
+fn roundtrip(msg: Message) -> Message {
+    let bytes = Message::marshal(0x1234_5678, msg).unwrap();
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[..OfpHeader::size()]);
+    let header = OfpHeader::parse(raw);
+    assert_eq!(header.length(), bytes.len());
+    let (xid, msg) = Message::parse(&header, &bytes[OfpHeader::size()..]).unwrap();
+    assert_eq!(xid, 0x1234_5678);
+    msg
+}
+
+fn mac() -> BoxedStrategy<u64> {
+    (0..1u64 << 48).boxed()
+}
+
+fn bytes(max: usize) -> BoxedStrategy<Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..max).boxed()
+}
+
+/// A body whose length is a multiple of 8 bytes, as vendor actions and queue properties are
+/// padded to.
+fn padded_bytes(max_words: usize) -> BoxedStrategy<Vec<u8>> {
+    (0..max_words)
+        .prop_flat_map(|words| prop::collection::vec(any::<u8>(), words * 8))
+        .boxed()
+}
+
+/// A printable string short enough to leave room for a null terminator in a field of `len`
+/// bytes.
+fn fixed_string(len: usize) -> BoxedStrategy<String> {
+    prop::string::string_regex(&format!("[a-zA-Z0-9 ._-]{{0,{}}}", len - 1)).unwrap().boxed()
+}
+
+fn nw_addr() -> BoxedStrategy<Option<Mask<u32>>> {
+    prop::option::of((any::<u32>(), prop::option::of(1..32u32)).prop_map(|(value, mask)| {
+            Mask {
+                value: value,
+                mask: mask,
+            }
+        }))
+        .boxed()
+}
+
+fn pattern() -> BoxedStrategy<Pattern> {
+    let dl = (prop::option::of(mac()),
+              prop::option::of(mac()),
+              prop::option::of(any::<u16>()),
+              prop::option::of(prop::option::of(0..0xffffu16)),
+              prop::option::of(any::<u8>()),
+              prop::option::of(any::<u16>()));
+    let nw = (nw_addr(),
+              nw_addr(),
+              prop::option::of(any::<u8>()),
+              prop::option::of(any::<u8>()),
+              prop::option::of(any::<u16>()),
+              prop::option::of(any::<u16>()));
+    (dl, nw)
+        .prop_map(|((dl_src, dl_dst, dl_typ, dl_vlan, dl_vlan_pcp, in_port),
+                    (nw_src, nw_dst, nw_proto, nw_tos, tp_src, tp_dst))| {
+            Pattern {
+                dl_src: dl_src,
+                dl_dst: dl_dst,
+                dl_typ: dl_typ,
+                dl_vlan: dl_vlan,
+                dl_vlan_pcp: dl_vlan_pcp,
+                nw_src: nw_src,
+                nw_dst: nw_dst,
+                nw_proto: nw_proto,
+                nw_tos: nw_tos,
+                tp_src: tp_src,
+                tp_dst: tp_dst,
+                in_port: in_port,
+            }
+        })
+        .boxed()
+}
+
+/// A port other than the controller, whose maximum length is only carried by output actions.
+fn port_no_controller() -> BoxedStrategy<PseudoPort> {
+    prop_oneof![(0..0xff01u16).prop_map(PseudoPort::PhysicalPort),
+                Just(PseudoPort::InPort),
+                Just(PseudoPort::Table),
+                Just(PseudoPort::Normal),
+                Just(PseudoPort::Flood),
+                Just(PseudoPort::AllPorts),
+                Just(PseudoPort::Local)]
+        .boxed()
+}
+
+fn port() -> BoxedStrategy<PseudoPort> {
+    prop_oneof![port_no_controller(), (0..0x10000u64).prop_map(PseudoPort::Controller)].boxed()
+}
+
+fn action() -> BoxedStrategy<Action> {
+    prop_oneof![port().prop_map(Action::Output),
+                prop::option::of(0..0xffffu16).prop_map(Action::SetDlVlan),
+                any::<u8>().prop_map(Action::SetDlVlanPcp),
+                mac().prop_map(Action::SetDlSrc),
+                mac().prop_map(Action::SetDlDst),
+                any::<u32>().prop_map(Action::SetNwSrc),
+                any::<u32>().prop_map(Action::SetNwDst),
+                any::<u8>().prop_map(Action::SetNwTos),
+                any::<u16>().prop_map(Action::SetTpSrc),
+                any::<u16>().prop_map(Action::SetTpDst),
+                (port_no_controller(), any::<u32>())
+                    .prop_map(|(port, queue)| Action::Enqueue(port, queue)),
+                (any::<u32>(), padded_bytes(4))
+                    .prop_map(|(vendor, body)| Action::Vendor(vendor, body))]
+        .boxed()
+}
+
+/// A sequence of actions in the order they are marshaled: outputs to the controller last.
+fn actions() -> BoxedStrategy<Vec<Action>> {
+    prop::collection::vec(action(), 0..8)
+        .prop_map(|acts| {
+            let (mut to_ctrl, mut rest): (Vec<Action>, Vec<Action>) = acts.into_iter()
+                .partition(|act| matches!(*act, Action::Output(PseudoPort::Controller(_))));
+            rest.append(&mut to_ctrl);
+            rest
+        })
+        .boxed()
+}
+
+fn timeout() -> BoxedStrategy<Timeout> {
+    prop_oneof![Just(Timeout::Permanent), (1..=0xffffu16).prop_map(Timeout::ExpiresAfter)].boxed()
+}
+
+fn buffer_id() -> BoxedStrategy<Option<u32>> {
+    prop::option::of(0..0xffff_ffffu32).boxed()
+}
+
+fn payload() -> BoxedStrategy<Payload> {
+    (buffer_id(), bytes(64))
+        .prop_map(|(id, data)| match id {
+            Some(id) => Payload::Buffered(id, data),
+            None => Payload::NotBuffered(data),
+        })
+        .boxed()
+}
+
+fn flow_mod() -> BoxedStrategy<FlowMod> {
+    let cmd = prop_oneof![Just(FlowModCmd::AddFlow),
+                          Just(FlowModCmd::ModFlow),
+                          Just(FlowModCmd::ModStrictFlow),
+                          Just(FlowModCmd::DeleteFlow),
+                          Just(FlowModCmd::DeleteStrictFlow)];
+    let acts = actions().prop_map(|acts| {
+        acts.into_iter().filter(|act| *act != Action::Output(PseudoPort::Table)).collect()
+    });
+    let out_port = prop::option::of(prop_oneof![port_no_controller(),
+                                                Just(PseudoPort::Controller(0))]);
+    ((cmd, pattern(), any::<u16>(), acts, any::<u64>()),
+     (timeout(), timeout(), any::<bool>(), buffer_id(), out_port, any::<bool>()))
+        .prop_map(|((command, pattern, priority, actions, cookie),
+                    (idle, hard, notify, apply_to_packet, out_port, check_overlap))| {
+            FlowMod {
+                command: command,
+                pattern: pattern,
+                priority: priority,
+                actions: actions,
+                cookie: cookie,
+                idle_timeout: idle,
+                hard_timeout: hard,
+                notify_when_removed: notify,
+                apply_to_packet: apply_to_packet,
+                out_port: out_port,
+                check_overlap: check_overlap,
+            }
+        })
+        .boxed()
+}
+
+fn capabilities() -> BoxedStrategy<Capabilities> {
+    prop::array::uniform7(any::<bool>())
+        .prop_map(|b| {
+            Capabilities {
+                flow_stats: b[0],
+                table_stats: b[1],
+                port_stats: b[2],
+                stp: b[3],
+                ip_reasm: b[4],
+                queue_stats: b[5],
+                arp_match_ip: b[6],
+            }
+        })
+        .boxed()
+}
+
+fn supported_actions() -> BoxedStrategy<SupportedActions> {
+    prop::array::uniform13(any::<bool>())
+        .prop_map(|b| {
+            SupportedActions {
+                output: b[0],
+                set_vlan_id: b[1],
+                set_vlan_pcp: b[2],
+                strip_vlan: b[3],
+                set_dl_src: b[4],
+                set_dl_dst: b[5],
+                set_nw_src: b[6],
+                set_nw_dst: b[7],
+                set_nw_tos: b[8],
+                set_tp_src: b[9],
+                set_tp_dst: b[10],
+                enqueue: b[11],
+                vendor: b[12],
+            }
+        })
+        .boxed()
+}
+
+fn port_config() -> BoxedStrategy<PortConfig> {
+    prop::array::uniform7(any::<bool>())
+        .prop_map(|b| {
+            PortConfig {
+                down: b[0],
+                no_stp: b[1],
+                no_recv: b[2],
+                no_recv_stp: b[3],
+                no_flood: b[4],
+                no_fwd: b[5],
+                no_packet_in: b[6],
+            }
+        })
+        .boxed()
+}
+
+fn port_features() -> BoxedStrategy<PortFeatures> {
+    prop::array::uniform12(any::<bool>())
+        .prop_map(|b| {
+            PortFeatures {
+                f_10mbhd: b[0],
+                f_10mbfd: b[1],
+                f_100mbhd: b[2],
+                f_100mbfd: b[3],
+                f_1gbhd: b[4],
+                f_1gbfd: b[5],
+                f_10gbfd: b[6],
+                copper: b[7],
+                fiber: b[8],
+                autoneg: b[9],
+                pause: b[10],
+                pause_asym: b[11],
+            }
+        })
+        .boxed()
+}
+
+fn port_state() -> BoxedStrategy<PortState> {
+    let stp = prop_oneof![Just(StpState::Listen),
+                          Just(StpState::Learn),
+                          Just(StpState::Forward),
+                          Just(StpState::Block)];
+    (any::<bool>(), stp)
+        .prop_map(|(down, stp_state)| {
+            PortState {
+                down: down,
+                stp_state: stp_state,
+            }
+        })
+        .boxed()
+}
+
+fn port_desc() -> BoxedStrategy<PortDesc> {
+    ((any::<u16>(), mac(), fixed_string(16), port_config(), port_state()),
+     (port_features(), port_features(), port_features(), port_features()))
+        .prop_map(|((port_no, hw_addr, name, config, state),
+                    (curr, advertised, supported, peer))| {
+            PortDesc {
+                port_no: port_no,
+                hw_addr: hw_addr,
+                name: name,
+                config: config,
+                state: state,
+                curr: curr,
+                advertised: advertised,
+                supported: supported,
+                peer: peer,
+            }
+        })
+        .boxed()
+}
+
+fn switch_config() -> BoxedStrategy<SwitchConfig> {
+    let frag = prop_oneof![Just(FragHandling::Normal),
+                           Just(FragHandling::Drop),
+                           Just(FragHandling::Reassemble)];
+    (frag, any::<u16>())
+        .prop_map(|(frag, miss_send_len)| {
+            SwitchConfig {
+                frag: frag,
+                miss_send_len: miss_send_len,
+            }
+        })
+        .boxed()
+}
+
+fn error() -> BoxedStrategy<Error> {
+    let typ = prop_oneof![prop_oneof![Just(HelloFailed::Incompatible), Just(HelloFailed::EPerm)]
+                              .prop_map(ErrorType::HelloFailed),
+                          prop_oneof![Just(BadRequest::BadVersion),
+                                      Just(BadRequest::BadType),
+                                      Just(BadRequest::BadStat),
+                                      Just(BadRequest::BadVendor),
+                                      Just(BadRequest::BadSubType),
+                                      Just(BadRequest::EPerm),
+                                      Just(BadRequest::BadLen),
+                                      Just(BadRequest::BufferEmpty),
+                                      Just(BadRequest::BufferUnknown)]
+                              .prop_map(ErrorType::BadRequest),
+                          prop_oneof![Just(BadAction::BadType),
+                                      Just(BadAction::BadLen),
+                                      Just(BadAction::BadVendor),
+                                      Just(BadAction::BadVendorType),
+                                      Just(BadAction::BadOutPort),
+                                      Just(BadAction::BadArgument),
+                                      Just(BadAction::EPerm),
+                                      Just(BadAction::TooMany),
+                                      Just(BadAction::BadQueue)]
+                              .prop_map(ErrorType::BadAction),
+                          prop_oneof![Just(FlowModFailed::AllTablesFull),
+                                      Just(FlowModFailed::Overlap),
+                                      Just(FlowModFailed::EPerm),
+                                      Just(FlowModFailed::BadEmergTimeout),
+                                      Just(FlowModFailed::BadCommand),
+                                      Just(FlowModFailed::Unsupported)]
+                              .prop_map(ErrorType::FlowModFailed),
+                          prop_oneof![Just(PortModFailed::BadPort),
+                                      Just(PortModFailed::BadHwAddr)]
+                              .prop_map(ErrorType::PortModFailed),
+                          prop_oneof![Just(QueueOpFailed::BadPort),
+                                      Just(QueueOpFailed::BadQueue),
+                                      Just(QueueOpFailed::EPerm)]
+                              .prop_map(ErrorType::QueueOpFailed)];
+    (typ, bytes(64)).prop_map(|(typ, data)| Error::Error(typ, data)).boxed()
+}
+
+fn flow_stats_request() -> BoxedStrategy<FlowStatsRequest> {
+    let out_port = prop::option::of(prop_oneof![port_no_controller(),
+                                                Just(PseudoPort::Controller(0))]);
+    (pattern(), any::<u8>(), out_port)
+        .prop_map(|(pattern, table_id, out_port)| {
+            FlowStatsRequest {
+                pattern: pattern,
+                table_id: table_id,
+                out_port: out_port,
+            }
+        })
+        .boxed()
+}
+
+fn stats_req() -> BoxedStrategy<StatsReq> {
+    prop_oneof![Just(StatsReq::Desc),
+                flow_stats_request().prop_map(StatsReq::Flow),
+                flow_stats_request().prop_map(StatsReq::Aggregate),
+                Just(StatsReq::Table),
+                prop::option::of(0..0xffffu16)
+                    .prop_map(|port_no| StatsReq::Port(PortStatsRequest { port_no: port_no })),
+                (prop::option::of(0..0xfffcu16), prop::option::of(0..0xffff_ffffu32))
+                    .prop_map(|(port_no, queue_id)| {
+                        StatsReq::Queue(QueueStatsRequest {
+                            port_no: port_no,
+                            queue_id: queue_id,
+                        })
+                    }),
+                (any::<u32>(), bytes(32)).prop_map(|(vendor, body)| StatsReq::Vendor(vendor, body))]
+        .boxed()
+}
+
+fn flow_stats() -> BoxedStrategy<FlowStats> {
+    ((any::<u8>(), pattern(), any::<u32>(), any::<u32>(), any::<u16>()),
+     (timeout(), timeout(), any::<u64>(), any::<u64>(), any::<u64>(), actions()))
+        .prop_map(|((table_id, pattern, duration_sec, duration_nsec, priority),
+                    (idle, hard, cookie, packet_count, byte_count, actions))| {
+            FlowStats {
+                table_id: table_id,
+                pattern: pattern,
+                duration_sec: duration_sec,
+                duration_nsec: duration_nsec,
+                priority: priority,
+                idle_timeout: idle,
+                hard_timeout: hard,
+                cookie: cookie,
+                packet_count: packet_count,
+                byte_count: byte_count,
+                actions: actions,
+            }
+        })
+        .boxed()
+}
+
+fn table_stats() -> BoxedStrategy<TableStats> {
+    (any::<u8>(), fixed_string(32), any::<u32>(), any::<u32>(), any::<u32>(), any::<u64>(),
+     any::<u64>())
+        .prop_map(|(table_id, name, wildcards, max_entries, active_count, lookup_count,
+                    matched_count)| {
+            TableStats {
+                table_id: table_id,
+                name: name,
+                wildcards: wildcards,
+                max_entries: max_entries,
+                active_count: active_count,
+                lookup_count: lookup_count,
+                matched_count: matched_count,
+            }
+        })
+        .boxed()
+}
+
+fn port_stats() -> BoxedStrategy<PortStats> {
+    (any::<u16>(), prop::array::uniform12(any::<u64>()))
+        .prop_map(|(port_no, c)| {
+            PortStats {
+                port_no: port_no,
+                rx_packets: c[0],
+                tx_packets: c[1],
+                rx_bytes: c[2],
+                tx_bytes: c[3],
+                rx_dropped: c[4],
+                tx_dropped: c[5],
+                rx_errors: c[6],
+                tx_errors: c[7],
+                rx_frame_err: c[8],
+                rx_over_err: c[9],
+                rx_crc_err: c[10],
+                collisions: c[11],
+            }
+        })
+        .boxed()
+}
+
+fn stats_resp() -> BoxedStrategy<StatsResp> {
+    let desc = (fixed_string(256),
+                fixed_string(256),
+                fixed_string(256),
+                fixed_string(32),
+                fixed_string(256))
+        .prop_map(|(mfr_desc, hw_desc, sw_desc, serial_num, dp_desc)| {
+            StatsRespBody::Desc(SwitchDesc {
+                mfr_desc: mfr_desc,
+                hw_desc: hw_desc,
+                sw_desc: sw_desc,
+                serial_num: serial_num,
+                dp_desc: dp_desc,
+            })
+        });
+    let aggregate = (any::<u64>(), any::<u64>(), any::<u32>())
+        .prop_map(|(packet_count, byte_count, flow_count)| {
+            StatsRespBody::Aggregate(AggregateStats {
+                packet_count: packet_count,
+                byte_count: byte_count,
+                flow_count: flow_count,
+            })
+        });
+    let queue = (any::<u16>(), any::<u32>(), any::<u64>(), any::<u64>(), any::<u64>())
+        .prop_map(|(port_no, queue_id, tx_bytes, tx_packets, tx_errors)| {
+            QueueStats {
+                port_no: port_no,
+                queue_id: queue_id,
+                tx_bytes: tx_bytes,
+                tx_packets: tx_packets,
+                tx_errors: tx_errors,
+            }
+        });
+    let body = prop_oneof![desc,
+                           prop::collection::vec(flow_stats(), 0..4).prop_map(StatsRespBody::Flow),
+                           aggregate,
+                           prop::collection::vec(table_stats(), 0..4)
+                               .prop_map(StatsRespBody::Table),
+                           prop::collection::vec(port_stats(), 0..4).prop_map(StatsRespBody::Port),
+                           prop::collection::vec(queue, 0..4).prop_map(StatsRespBody::Queue),
+                           (any::<u32>(), bytes(32))
+                               .prop_map(|(vendor, body)| StatsRespBody::Vendor(vendor, body))];
+    (any::<bool>(), body)
+        .prop_map(|(more, body)| {
+            StatsResp {
+                more: more,
+                body: body,
+            }
+        })
+        .boxed()
+}
+
+fn queue_property() -> BoxedStrategy<QueueProperty> {
+    prop_oneof![any::<u16>().prop_map(QueueProperty::MinRate),
+                (2..0xffffu16, padded_bytes(4))
+                    .prop_map(|(typ, body)| QueueProperty::Unparsable(typ, body))]
+        .boxed()
+}
+
+fn queue_get_config_resp() -> BoxedStrategy<QueueGetConfigResp> {
+    let queue = (any::<u32>(), prop::collection::vec(queue_property(), 0..4))
+        .prop_map(|(queue_id, properties)| {
+            PacketQueue {
+                queue_id: queue_id,
+                properties: properties,
+            }
+        });
+    (any::<u16>(), prop::collection::vec(queue, 0..4))
+        .prop_map(|(port, queues)| {
+            QueueGetConfigResp {
+                port: port,
+                queues: queues,
+            }
+        })
+        .boxed()
+}
+
+fn message() -> BoxedStrategy<Message> {
+    let features = ((any::<u64>(), any::<u32>(), any::<u8>()),
+                    (capabilities(),
+                     supported_actions(),
+                     prop::collection::vec(port_desc(), 0..4)))
+        .prop_map(|((datapath_id, num_buffers, num_tables),
+                    (supported_capabilities, supported_actions, ports))| {
+            Message::FeaturesReply(SwitchFeatures {
+                datapath_id: datapath_id,
+                num_buffers: num_buffers,
+                num_tables: num_tables,
+                supported_capabilities: supported_capabilities,
+                supported_actions: supported_actions,
+                ports: ports,
+            })
+        });
+    let port_mod = (any::<u16>(), mac(), port_config(), port_config(), port_features())
+        .prop_map(|(port_no, hw_addr, config, mask, advertise)| {
+            Message::PortMod(PortMod {
+                port_no: port_no,
+                hw_addr: hw_addr,
+                config: config,
+                mask: mask,
+                advertise: advertise,
+            })
+        });
+    let reason = prop_oneof![Just(PacketInReason::NoMatch), Just(PacketInReason::ExplicitSend)];
+    let packet_in = (payload(), any::<u16>(), any::<u16>(), reason)
+        .prop_map(|(input_payload, total_len, port, reason)| {
+            Message::PacketIn(PacketIn {
+                input_payload: input_payload,
+                total_len: total_len,
+                port: port,
+                reason: reason,
+            })
+        });
+    let reason = prop_oneof![Just(FlowRemovedReason::IdleTimeout),
+                             Just(FlowRemovedReason::HardTimeout),
+                             Just(FlowRemovedReason::Delete)];
+    let flow_removed = ((pattern(), any::<i64>(), any::<u16>(), reason),
+                        (any::<u32>(), any::<u32>(), timeout(), any::<u64>(), any::<u64>()))
+        .prop_map(|((pattern, cookie, priority, reason),
+                    (duration_sec, duration_nsec, idle_timeout, packet_count, byte_count))| {
+            Message::FlowRemoved(FlowRemoved {
+                pattern: pattern,
+                cookie: cookie,
+                priority: priority,
+                reason: reason,
+                duration_sec: duration_sec,
+                duration_nsec: duration_nsec,
+                idle_timeout: idle_timeout,
+                packet_count: packet_count,
+                byte_count: byte_count,
+            })
+        });
+    let reason = prop_oneof![Just(PortReason::PortAdd),
+                             Just(PortReason::PortDelete),
+                             Just(PortReason::PortModify)];
+    let port_status = (reason, port_desc()).prop_map(|(reason, desc)| {
+        Message::PortStatus(PortStatus {
+            reason: reason,
+            desc: desc,
+        })
+    });
+    let packet_out = (payload(), prop::option::of(0..0xffffu16), actions())
+        .prop_map(|(output_payload, port_id, apply_actions)| {
+            Message::PacketOut(PacketOut {
+                output_payload: output_payload,
+                port_id: port_id,
+                apply_actions: apply_actions,
+            })
+        });
+    prop_oneof![Just(Message::Hello),
+                error().prop_map(Message::Error),
+                bytes(64).prop_map(Message::EchoRequest),
+                bytes(64).prop_map(Message::EchoReply),
+                (any::<u32>(), bytes(64)).prop_map(|(vendor_id, body)| {
+                    Message::Vendor {
+                        vendor_id: vendor_id,
+                        body: body,
+                    }
+                }),
+                Just(Message::FeaturesReq),
+                features,
+                Just(Message::GetConfigRequest),
+                switch_config().prop_map(Message::GetConfigReply),
+                switch_config().prop_map(Message::SetConfig),
+                flow_mod().prop_map(Message::FlowMod),
+                port_mod,
+                packet_in,
+                flow_removed,
+                port_status,
+                packet_out,
+                stats_req().prop_map(Message::StatsRequest),
+                stats_resp().prop_map(Message::StatsReply),
+                Just(Message::BarrierRequest),
+                Just(Message::BarrierReply),
+                any::<u16>().prop_map(|port| {
+                    Message::QueueGetConfigRequest(QueueGetConfigReq { port: port })
+                }),
+                queue_get_config_resp().prop_map(Message::QueueGetConfigReply)]
+        .boxed()
+}
+
+#[test]
+fn flow_mod_output_to_table_rejected() {
+    let actions = vec![Action::Output(PseudoPort::Flood), Action::Output(PseudoPort::Table)];
+    let msg = Message::FlowMod(message::add_flow(0, Pattern::match_all(), actions));
+    assert_eq!(Message::marshal(0, msg),
+               Err(MarshalError::InvalidValue("ofp_action_output port", 0xfff9)));
+}
+
+/// Return a packet out applying vendor action `body` with vendor id `0x2320`.
+fn vendor_packet_out(body: Vec<u8>) -> Message {
+    Message::PacketOut(PacketOut {
+        output_payload: Payload::NotBuffered(vec![]),
+        port_id: None,
+        apply_actions: vec![Action::Vendor(0x2320, body)],
+    })
+}
+
+#[test]
+fn vendor_action_roundtrip() {
+    assert_eq!(Message::marshal(0, vendor_packet_out(vec![1, 2, 3])),
+               Err(MarshalError::InvalidValue("ofp_action_vendor_header body length", 3)));
+    let body = vec![1, 2, 3, 0, 0, 0, 0, 0];
+    let bytes = Message::marshal(0, vendor_packet_out(body.clone())).unwrap();
+    assert_eq!(&bytes[OfpHeader::size() + 8..],
+               &[0xff, 0xff, 0, 16, 0, 0, 0x23, 0x20, 1, 2, 3, 0, 0, 0, 0, 0]);
+    assert_eq!(roundtrip(vendor_packet_out(body.clone())), vendor_packet_out(body));
+}
+
+#[test]
+fn vendor_action_unaligned_length_rejected() {
+    let mut bytes = Message::marshal(0, vendor_packet_out(vec![1, 2, 3, 0, 0, 0, 0, 0])).unwrap();
+    bytes.truncate(bytes.len() - 5);
+    let len = bytes.len() as u16;
+    bytes[2..4].copy_from_slice(&len.to_be_bytes());
+    bytes[OfpHeader::size() + 6..OfpHeader::size() + 8].copy_from_slice(&11u16.to_be_bytes());
+    bytes[OfpHeader::size() + 10..OfpHeader::size() + 12].copy_from_slice(&11u16.to_be_bytes());
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[..OfpHeader::size()]);
+    assert_eq!(Message::parse(&OfpHeader::parse(raw), &bytes[OfpHeader::size()..]),
+               Err(ParseError::BadLength("ofp_action_vendor_header", 11)));
+}
+
+/// Registry coding vendor actions and statistics with vendor id `0x2320` as a single byte
+/// padded to 8 bytes.
+fn byte_registry() -> VendorRegistry {
+    let mut registry = VendorRegistry::default();
+    for &kind in &[VendorKind::Action, VendorKind::Stats] {
+        let parser = Box::new(|body: &[u8]| match body.first() {
+            Some(&b) if body[1..].iter().all(|&pad| pad == 0) => Ok(Box::new(b) as VendorValue),
+            _ => Err(ParseError::BadLength("vendor byte", body.len())),
+        });
+        let marshaller = Box::new(|value: &dyn Any| {
+            value.downcast_ref::<u8>().map(|&b| vec![b, 0, 0, 0, 0, 0, 0, 0])
+        });
+        registry.register(kind, 0x2320, parser, marshaller);
+    }
+    registry
+}
+
+/// Marshal `msg`, then parse it back from the resulting buffer with `registry`, returning the
+/// message and the bytes its vendor bodies decoded to.
+fn parse_with(registry: &VendorRegistry, msg: Message) -> Result<(Message, Vec<u8>), ParseError> {
+    let bytes = Message::marshal(0, msg).unwrap();
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[..OfpHeader::size()]);
+    let (_, msg, values) =
+        Message::parse_with(registry, &OfpHeader::parse(raw), &bytes[OfpHeader::size()..])?;
+    let values = values.into_iter()
+        .map(|(_, vendor_id, value)| {
+            assert_eq!(vendor_id, 0x2320);
+            *value.downcast::<u8>().unwrap()
+        })
+        .collect();
+    Ok((msg, values))
+}
+
+#[test]
+fn parse_with_decodes_registered_vendor_bodies() {
+    let registry = byte_registry();
+    let flow_mod = |actions| {
+        Message::FlowMod(message::add_flow(0, Pattern::match_all(), actions))
+    };
+    let actions = vec![registry.action(0x2320, &7u8).unwrap(),
+                       Action::Vendor(0x2321, vec![]),
+                       registry.action(0x2320, &9u8).unwrap()];
+    assert_eq!(actions[0], Action::Vendor(0x2320, vec![7, 0, 0, 0, 0, 0, 0, 0]));
+    assert_eq!(parse_with(&registry, flow_mod(actions.clone())),
+               Ok((flow_mod(actions), vec![7, 9])));
+    assert_eq!(parse_with(&registry, flow_mod(vec![Action::Vendor(0x2320, vec![])])),
+               Err(ParseError::BadLength("vendor byte", 0)));
+    assert_eq!(registry.action(0x2320, &7u32), None);
+    let stats = |vendor_id, body| Message::StatsRequest(StatsReq::Vendor(vendor_id, body));
+    let req = registry.stats_request(0x2320, &3u8).unwrap();
+    assert_eq!(parse_with(&registry, Message::StatsRequest(req.clone())),
+               Ok((Message::StatsRequest(req), vec![3])));
+    assert_eq!(parse_with(&registry, stats(0x2320, vec![1, 1])),
+               Err(ParseError::BadLength("vendor byte", 2)));
+    assert_eq!(parse_with(&registry, stats(0x2321, vec![1, 1])),
+               Ok((stats(0x2321, vec![1, 1]), vec![])));
+}
+
+proptest! {
+    #[test]
+    fn message_roundtrip(msg in message()) {
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn pattern_roundtrip(pattern in pattern()) {
+        let msg = Message::FlowMod(message::add_flow(0, pattern, vec![]));
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn action_roundtrip(act in action()) {
+        let msg = Message::PacketOut(PacketOut {
+            output_payload: Payload::NotBuffered(vec![]),
+            port_id: None,
+            apply_actions: vec![act],
+        });
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn message_parse_does_not_panic(typ in 0..22u8, body in bytes(256)) {
+        let header = OfpHeader::new(0x01, typ, (OfpHeader::size() + body.len()) as u16, 0);
+        let _ = Message::parse(&header, &body);
+    }
+}
@@ -0,0 +1,981 @@
+extern crate rust_ofp;
+extern crate proptest;
+
+use proptest::prelude::*;
+use proptest::strategy::Union;
+
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_message::OfpMessage;
+use rust_ofp::openflow0x04::*;
+use rust_ofp::openflow0x04::message::Message;
+use rust_ofp::parse_error::ParseError;
+
+/// Marshal `msg`, then parse it back from the resulting buffer.
+fn roundtrip(msg: Message) -> Message {
+    let bytes = Message::marshal(0x1234_5678, msg).unwrap();
+    let header = header_of(&bytes);
+    assert_eq!(header.version(), 0x04);
+    assert_eq!(header.length(), bytes.len());
+    let (xid, msg) = Message::parse(&header, &bytes[OfpHeader::size()..]).unwrap();
+    assert_eq!(xid, 0x1234_5678);
+    msg
+}
+
+fn header_of(bytes: &[u8]) -> OfpHeader {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[..OfpHeader::size()]);
+    OfpHeader::parse(raw)
+}
+
+/// Parse the marshaled message `bytes`.
+fn parse(bytes: &[u8]) -> Result<Message, ParseError> {
+    Message::parse(&header_of(bytes), &bytes[OfpHeader::size()..]).map(|(_, msg)| msg)
+}
+
+fn mac() -> BoxedStrategy<u64> {
+    (0..1u64 << 48).boxed()
+}
+
+fn bytes(max: usize) -> BoxedStrategy<Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..max).boxed()
+}
+
+/// A body whose length is a multiple of 8 bytes, as experimenter actions are padded to.
+fn padded_bytes(max_words: usize) -> BoxedStrategy<Vec<u8>> {
+    (0..max_words)
+        .prop_flat_map(|words| prop::collection::vec(any::<u8>(), words * 8))
+        .boxed()
+}
+
+/// A printable string short enough to leave room for a null terminator in a field of `len`
+/// bytes.
+fn fixed_string(len: usize) -> BoxedStrategy<String> {
+    prop::string::string_regex(&format!("[a-zA-Z0-9 ._-]{{0,{}}}", len - 1)).unwrap().boxed()
+}
+
+fn mask<T: Clone + std::fmt::Debug + 'static>(value: BoxedStrategy<T>) -> BoxedStrategy<Mask<T>> {
+    (value.clone(), prop::option::of(value))
+        .prop_map(|(value, mask)| {
+            Mask {
+                value: value,
+                mask: mask,
+            }
+        })
+        .boxed()
+}
+
+/// A field of a class other than OpenFlow basic, or a basic field unknown to this crate.
+fn oxm_other() -> BoxedStrategy<Oxm> {
+    let class = prop_oneof![any::<u16>().prop_filter("basic class", |&c| c != 0x8000),
+                            Just(0x8000)];
+    (class, 0..0x80u8, any::<bool>(), bytes(16))
+        .prop_filter("known basic field",
+                     |&(class, field, _, _)| class != 0x8000 || field >= 40)
+        .prop_map(|(class, field, hasmask, body)| {
+            Oxm::Other {
+                class: class,
+                field: field,
+                hasmask: hasmask,
+                body: body,
+            }
+        })
+        .boxed()
+}
+
+fn oxm() -> BoxedStrategy<Oxm> {
+    Union::new(vec![any::<u32>().prop_map(Oxm::InPort).boxed(),
+                    any::<u32>().prop_map(Oxm::InPhyPort).boxed(),
+                    mask(any::<u64>().boxed()).prop_map(Oxm::Metadata).boxed(),
+                    mask(mac()).prop_map(Oxm::EthDst).boxed(),
+                    mask(mac()).prop_map(Oxm::EthSrc).boxed(),
+                    any::<u16>().prop_map(Oxm::EthType).boxed(),
+                    mask(any::<u16>().boxed()).prop_map(Oxm::VlanVid).boxed(),
+                    any::<u8>().prop_map(Oxm::VlanPcp).boxed(),
+                    any::<u8>().prop_map(Oxm::IpDscp).boxed(),
+                    any::<u8>().prop_map(Oxm::IpEcn).boxed(),
+                    any::<u8>().prop_map(Oxm::IpProto).boxed(),
+                    mask(any::<u32>().boxed()).prop_map(Oxm::Ipv4Src).boxed(),
+                    mask(any::<u32>().boxed()).prop_map(Oxm::Ipv4Dst).boxed(),
+                    any::<u16>().prop_map(Oxm::TcpSrc).boxed(),
+                    any::<u16>().prop_map(Oxm::TcpDst).boxed(),
+                    any::<u16>().prop_map(Oxm::UdpSrc).boxed(),
+                    any::<u16>().prop_map(Oxm::UdpDst).boxed(),
+                    any::<u16>().prop_map(Oxm::SctpSrc).boxed(),
+                    any::<u16>().prop_map(Oxm::SctpDst).boxed(),
+                    any::<u8>().prop_map(Oxm::Icmpv4Type).boxed(),
+                    any::<u8>().prop_map(Oxm::Icmpv4Code).boxed(),
+                    any::<u16>().prop_map(Oxm::ArpOp).boxed(),
+                    mask(any::<u32>().boxed()).prop_map(Oxm::ArpSpa).boxed(),
+                    mask(any::<u32>().boxed()).prop_map(Oxm::ArpTpa).boxed(),
+                    mask(mac()).prop_map(Oxm::ArpSha).boxed(),
+                    mask(mac()).prop_map(Oxm::ArpTha).boxed(),
+                    mask(any::<u128>().boxed()).prop_map(Oxm::Ipv6Src).boxed(),
+                    mask(any::<u128>().boxed()).prop_map(Oxm::Ipv6Dst).boxed(),
+                    mask(any::<u32>().boxed()).prop_map(Oxm::Ipv6Flabel).boxed(),
+                    any::<u8>().prop_map(Oxm::Icmpv6Type).boxed(),
+                    any::<u8>().prop_map(Oxm::Icmpv6Code).boxed(),
+                    any::<u128>().prop_map(Oxm::Ipv6NdTarget).boxed(),
+                    mac().prop_map(Oxm::Ipv6NdSll).boxed(),
+                    mac().prop_map(Oxm::Ipv6NdTll).boxed(),
+                    any::<u32>().prop_map(Oxm::MplsLabel).boxed(),
+                    any::<u8>().prop_map(Oxm::MplsTc).boxed(),
+                    any::<u8>().prop_map(Oxm::MplsBos).boxed(),
+                    mask((0..1u32 << 24).boxed()).prop_map(Oxm::PbbIsid).boxed(),
+                    mask(any::<u64>().boxed()).prop_map(Oxm::TunnelId).boxed(),
+                    mask(any::<u16>().boxed()).prop_map(Oxm::Ipv6Exthdr).boxed(),
+                    oxm_other()])
+        .boxed()
+}
+
+fn pattern() -> BoxedStrategy<Pattern> {
+    prop::collection::vec(oxm(), 0..6).prop_map(|fields| Pattern { fields: fields }).boxed()
+}
+
+/// A port other than the controller, whose maximum length is only carried by output actions.
+fn port_no_controller() -> BoxedStrategy<PseudoPort> {
+    prop_oneof![(0..=0xffff_ff00u32).prop_map(PseudoPort::PhysicalPort),
+                Just(PseudoPort::InPort),
+                Just(PseudoPort::Table),
+                Just(PseudoPort::Normal),
+                Just(PseudoPort::Flood),
+                Just(PseudoPort::AllPorts),
+                Just(PseudoPort::Local)]
+        .boxed()
+}
+
+fn port() -> BoxedStrategy<PseudoPort> {
+    prop_oneof![port_no_controller(), any::<u16>().prop_map(PseudoPort::Controller)].boxed()
+}
+
+fn action() -> BoxedStrategy<Action> {
+    Union::new(vec![port().prop_map(Action::Output).boxed(),
+                    Just(Action::CopyTtlOut).boxed(),
+                    Just(Action::CopyTtlIn).boxed(),
+                    any::<u8>().prop_map(Action::SetMplsTtl).boxed(),
+                    Just(Action::DecMplsTtl).boxed(),
+                    any::<u16>().prop_map(Action::PushVlan).boxed(),
+                    Just(Action::PopVlan).boxed(),
+                    any::<u16>().prop_map(Action::PushMpls).boxed(),
+                    any::<u16>().prop_map(Action::PopMpls).boxed(),
+                    any::<u32>().prop_map(Action::SetQueue).boxed(),
+                    any::<u32>().prop_map(Action::Group).boxed(),
+                    any::<u8>().prop_map(Action::SetNwTtl).boxed(),
+                    Just(Action::DecNwTtl).boxed(),
+                    oxm().prop_map(Action::SetField).boxed(),
+                    any::<u16>().prop_map(Action::PushPbb).boxed(),
+                    Just(Action::PopPbb).boxed(),
+                    (any::<u32>(), padded_bytes(4))
+                        .prop_map(|(experimenter, body)| Action::Experimenter(experimenter, body))
+                        .boxed()])
+        .boxed()
+}
+
+fn actions() -> BoxedStrategy<Vec<Action>> {
+    prop::collection::vec(action(), 0..6).boxed()
+}
+
+fn instruction() -> BoxedStrategy<Instruction> {
+    prop_oneof![any::<u8>().prop_map(Instruction::GotoTable),
+                (any::<u64>(), any::<u64>()).prop_map(|(metadata, mask)| {
+                    Instruction::WriteMetadata {
+                        metadata: metadata,
+                        mask: mask,
+                    }
+                }),
+                actions().prop_map(Instruction::WriteActions),
+                actions().prop_map(Instruction::ApplyActions),
+                Just(Instruction::ClearActions),
+                any::<u32>().prop_map(Instruction::Meter),
+                (any::<u32>(), padded_bytes(4)).prop_map(|(experimenter, body)| {
+                    Instruction::Experimenter(experimenter, body)
+                })]
+        .boxed()
+}
+
+fn timeout() -> BoxedStrategy<Timeout> {
+    prop_oneof![Just(Timeout::Permanent), (1..=0xffffu16).prop_map(Timeout::ExpiresAfter)].boxed()
+}
+
+fn buffer_id() -> BoxedStrategy<Option<u32>> {
+    prop::option::of(0..0xffff_ffffu32).boxed()
+}
+
+fn payload() -> BoxedStrategy<Payload> {
+    (buffer_id(), bytes(64))
+        .prop_map(|(id, data)| match id {
+            Some(id) => Payload::Buffered(id, data),
+            None => Payload::NotBuffered(data),
+        })
+        .boxed()
+}
+
+fn flow_mod_flags() -> BoxedStrategy<FlowModFlags> {
+    prop::array::uniform5(any::<bool>())
+        .prop_map(|b| {
+            FlowModFlags {
+                send_flow_rem: b[0],
+                check_overlap: b[1],
+                reset_counts: b[2],
+                no_packet_counts: b[3],
+                no_byte_counts: b[4],
+            }
+        })
+        .boxed()
+}
+
+fn flow_mod() -> BoxedStrategy<FlowMod> {
+    let cmd = prop_oneof![Just(FlowModCmd::AddFlow),
+                          Just(FlowModCmd::ModFlow),
+                          Just(FlowModCmd::ModStrictFlow),
+                          Just(FlowModCmd::DeleteFlow),
+                          Just(FlowModCmd::DeleteStrictFlow)];
+    let out_port = prop::option::of(prop_oneof![port_no_controller(),
+                                                Just(PseudoPort::Controller(0))]);
+    let instructions = prop::collection::vec(instruction(), 0..4);
+    ((cmd, any::<u8>(), pattern(), any::<u16>(), instructions, any::<u64>(), any::<u64>()),
+     (timeout(), timeout(), flow_mod_flags(), buffer_id(), out_port,
+      prop::option::of(0..0xffff_ffffu32)))
+        .prop_map(|((command, table_id, pattern, priority, instructions, cookie, cookie_mask),
+                    (idle, hard, flags, apply_to_packet, out_port, out_group))| {
+            FlowMod {
+                command: command,
+                table_id: table_id,
+                pattern: pattern,
+                priority: priority,
+                instructions: instructions,
+                cookie: cookie,
+                cookie_mask: cookie_mask,
+                idle_timeout: idle,
+                hard_timeout: hard,
+                flags: flags,
+                apply_to_packet: apply_to_packet,
+                out_port: out_port,
+                out_group: out_group,
+            }
+        })
+        .boxed()
+}
+
+fn packet_in() -> BoxedStrategy<PacketIn> {
+    let reason = prop_oneof![Just(PacketInReason::NoMatch),
+                             Just(PacketInReason::Action),
+                             Just(PacketInReason::InvalidTtl)];
+    (payload(), any::<u16>(), reason, any::<u8>(), any::<u64>(), pattern())
+        .prop_map(|(input_payload, total_len, reason, table_id, cookie, pattern)| {
+            PacketIn {
+                input_payload: input_payload,
+                total_len: total_len,
+                reason: reason,
+                table_id: table_id,
+                cookie: cookie,
+                pattern: pattern,
+            }
+        })
+        .boxed()
+}
+
+fn port_features() -> BoxedStrategy<PortFeatures> {
+    prop::array::uniform16(any::<bool>())
+        .prop_map(|b| {
+            PortFeatures {
+                f_10mbhd: b[0],
+                f_10mbfd: b[1],
+                f_100mbhd: b[2],
+                f_100mbfd: b[3],
+                f_1gbhd: b[4],
+                f_1gbfd: b[5],
+                f_10gbfd: b[6],
+                f_40gbfd: b[7],
+                f_100gbfd: b[8],
+                f_1tbfd: b[9],
+                other: b[10],
+                copper: b[11],
+                fiber: b[12],
+                autoneg: b[13],
+                pause: b[14],
+                pause_asym: b[15],
+            }
+        })
+        .boxed()
+}
+
+fn port_desc() -> BoxedStrategy<PortDesc> {
+    let config = prop::array::uniform4(any::<bool>()).prop_map(|b| {
+        PortConfig {
+            down: b[0],
+            no_recv: b[1],
+            no_fwd: b[2],
+            no_packet_in: b[3],
+        }
+    });
+    let state = prop::array::uniform3(any::<bool>()).prop_map(|b| {
+        PortState {
+            link_down: b[0],
+            blocked: b[1],
+            live: b[2],
+        }
+    });
+    ((any::<u32>(), mac(), fixed_string(16), config, state),
+     (port_features(), port_features(), port_features(), port_features()),
+     (any::<u32>(), any::<u32>()))
+        .prop_map(|((port_no, hw_addr, name, config, state),
+                    (curr, advertised, supported, peer),
+                    (curr_speed, max_speed))| {
+            PortDesc {
+                port_no: port_no,
+                hw_addr: hw_addr,
+                name: name,
+                config: config,
+                state: state,
+                curr: curr,
+                advertised: advertised,
+                supported: supported,
+                peer: peer,
+                curr_speed: curr_speed,
+                max_speed: max_speed,
+            }
+        })
+        .boxed()
+}
+
+fn multipart_resp(body: BoxedStrategy<MultipartRespBody>) -> BoxedStrategy<Message> {
+    (any::<bool>(), body)
+        .prop_map(|(more, body)| {
+            Message::MultipartReply(MultipartResp {
+                more: more,
+                body: body,
+            })
+        })
+        .boxed()
+}
+
+fn group_type() -> BoxedStrategy<GroupType> {
+    prop_oneof![Just(GroupType::All),
+                Just(GroupType::Select),
+                Just(GroupType::Indirect),
+                Just(GroupType::FastFailover)]
+        .boxed()
+}
+
+fn bucket() -> BoxedStrategy<Bucket> {
+    (any::<u16>(),
+     prop::option::of(0..0xffff_ffffu32),
+     prop::option::of(0..0xffff_ffffu32),
+     actions())
+        .prop_map(|(weight, watch_port, watch_group, actions)| {
+            Bucket {
+                weight: weight,
+                watch_port: watch_port,
+                watch_group: watch_group,
+                actions: actions,
+            }
+        })
+        .boxed()
+}
+
+/// Between `min` and 3 buckets.
+fn buckets(min: usize) -> BoxedStrategy<Vec<Bucket>> {
+    prop::collection::vec(bucket(), min..4).boxed()
+}
+
+fn group_mod() -> BoxedStrategy<GroupMod> {
+    let cmd = prop_oneof![Just(GroupModCmd::AddGroup),
+                          Just(GroupModCmd::ModifyGroup),
+                          Just(GroupModCmd::DeleteGroup)];
+    (cmd, group_type(), any::<u32>(), buckets(0))
+        .prop_map(|(command, typ, group_id, buckets)| {
+            GroupMod {
+                command: command,
+                typ: typ,
+                group_id: group_id,
+                buckets: buckets,
+            }
+        })
+        .boxed()
+}
+
+/// Statistics of a group with at least `min_buckets` buckets.
+fn group_stats(min_buckets: usize) -> BoxedStrategy<GroupStats> {
+    let counter = (any::<u64>(), any::<u64>()).prop_map(|(packet_count, byte_count)| {
+        BucketCounter {
+            packet_count: packet_count,
+            byte_count: byte_count,
+        }
+    });
+    ((any::<u32>(), any::<u32>(), any::<u64>(), any::<u64>()),
+     (any::<u32>(), any::<u32>(), prop::collection::vec(counter, min_buckets..4)))
+        .prop_map(|((group_id, ref_count, packet_count, byte_count),
+                    (duration_sec, duration_nsec, bucket_stats))| {
+            GroupStats {
+                group_id: group_id,
+                ref_count: ref_count,
+                packet_count: packet_count,
+                byte_count: byte_count,
+                duration_sec: duration_sec,
+                duration_nsec: duration_nsec,
+                bucket_stats: bucket_stats,
+            }
+        })
+        .boxed()
+}
+
+/// Description of a group with at least `min_buckets` buckets.
+fn group_desc(min_buckets: usize) -> BoxedStrategy<GroupDesc> {
+    (group_type(), any::<u32>(), buckets(min_buckets))
+        .prop_map(|(typ, group_id, buckets)| {
+            GroupDesc {
+                typ: typ,
+                group_id: group_id,
+                buckets: buckets,
+            }
+        })
+        .boxed()
+}
+
+fn group_features() -> BoxedStrategy<GroupFeatures> {
+    let capabilities = prop::array::uniform4(any::<bool>()).prop_map(|b| {
+        GroupCapabilities {
+            select_weight: b[0],
+            select_liveness: b[1],
+            chaining: b[2],
+            chaining_checks: b[3],
+        }
+    });
+    (any::<u32>(), capabilities, any::<[u32; 4]>(), any::<[u32; 4]>())
+        .prop_map(|(types, capabilities, max_groups, actions)| {
+            GroupFeatures {
+                types: types,
+                capabilities: capabilities,
+                max_groups: max_groups,
+                actions: actions,
+            }
+        })
+        .boxed()
+}
+
+/// A multipart reply carrying statistics, descriptions, or features of groups, with at least
+/// `min` groups of at least `min` buckets each.
+fn group_multipart_resp(min: usize) -> BoxedStrategy<Message> {
+    multipart_resp(prop_oneof![prop::collection::vec(group_stats(min), min..4)
+                                   .prop_map(MultipartRespBody::Group),
+                               prop::collection::vec(group_desc(min), min..4)
+                                   .prop_map(MultipartRespBody::GroupDesc),
+                               group_features().prop_map(MultipartRespBody::GroupFeatures)]
+        .boxed())
+}
+
+fn meter_flags() -> BoxedStrategy<MeterFlags> {
+    prop::array::uniform4(any::<bool>())
+        .prop_map(|b| {
+            MeterFlags {
+                kbps: b[0],
+                pktps: b[1],
+                burst: b[2],
+                stats: b[3],
+            }
+        })
+        .boxed()
+}
+
+fn meter_band() -> BoxedStrategy<MeterBand> {
+    prop_oneof![(any::<u32>(), any::<u32>()).prop_map(|(rate, burst_size)| {
+                    MeterBand::Drop {
+                        rate: rate,
+                        burst_size: burst_size,
+                    }
+                }),
+                (any::<u32>(), any::<u32>(), any::<u8>())
+                    .prop_map(|(rate, burst_size, prec_level)| {
+                        MeterBand::DscpRemark {
+                            rate: rate,
+                            burst_size: burst_size,
+                            prec_level: prec_level,
+                        }
+                    }),
+                (any::<u32>(), any::<u32>(), any::<u32>())
+                    .prop_map(|(rate, burst_size, experimenter)| {
+                        MeterBand::Experimenter {
+                            rate: rate,
+                            burst_size: burst_size,
+                            experimenter: experimenter,
+                        }
+                    })]
+        .boxed()
+}
+
+/// Between `min` and 3 bands.
+fn meter_bands(min: usize) -> BoxedStrategy<Vec<MeterBand>> {
+    prop::collection::vec(meter_band(), min..4).boxed()
+}
+
+fn meter_mod() -> BoxedStrategy<MeterMod> {
+    let cmd = prop_oneof![Just(MeterModCmd::AddMeter),
+                          Just(MeterModCmd::ModifyMeter),
+                          Just(MeterModCmd::DeleteMeter)];
+    (cmd, meter_flags(), any::<u32>(), meter_bands(0))
+        .prop_map(|(command, flags, meter_id, bands)| {
+            MeterMod {
+                command: command,
+                flags: flags,
+                meter_id: meter_id,
+                bands: bands,
+            }
+        })
+        .boxed()
+}
+
+/// Statistics of a meter with at least `min_bands` bands.
+fn meter_stats(min_bands: usize) -> BoxedStrategy<MeterStats> {
+    let counter = (any::<u64>(), any::<u64>()).prop_map(|(packets, bytes)| {
+        MeterBandStats {
+            packet_band_count: packets,
+            byte_band_count: bytes,
+        }
+    });
+    ((any::<u32>(), any::<u32>(), any::<u64>(), any::<u64>()),
+     (any::<u32>(), any::<u32>(), prop::collection::vec(counter, min_bands..4)))
+        .prop_map(|((meter_id, flow_count, packet_in_count, byte_in_count),
+                    (duration_sec, duration_nsec, band_stats))| {
+            MeterStats {
+                meter_id: meter_id,
+                flow_count: flow_count,
+                packet_in_count: packet_in_count,
+                byte_in_count: byte_in_count,
+                duration_sec: duration_sec,
+                duration_nsec: duration_nsec,
+                band_stats: band_stats,
+            }
+        })
+        .boxed()
+}
+
+/// Configuration of a meter with at least `min_bands` bands.
+fn meter_config(min_bands: usize) -> BoxedStrategy<MeterConfig> {
+    (meter_flags(), any::<u32>(), meter_bands(min_bands))
+        .prop_map(|(flags, meter_id, bands)| {
+            MeterConfig {
+                flags: flags,
+                meter_id: meter_id,
+                bands: bands,
+            }
+        })
+        .boxed()
+}
+
+fn meter_features() -> BoxedStrategy<MeterFeatures> {
+    (any::<u32>(), any::<u32>(), meter_flags(), any::<u8>(), any::<u8>())
+        .prop_map(|(max_meter, band_types, capabilities, max_bands, max_color)| {
+            MeterFeatures {
+                max_meter: max_meter,
+                band_types: band_types,
+                capabilities: capabilities,
+                max_bands: max_bands,
+                max_color: max_color,
+            }
+        })
+        .boxed()
+}
+
+/// A multipart reply carrying statistics, configuration, or features of meters, with at least
+/// `min` meters of at least `min` bands each.
+fn meter_multipart_resp(min: usize) -> BoxedStrategy<Message> {
+    multipart_resp(prop_oneof![prop::collection::vec(meter_stats(min), min..4)
+                                   .prop_map(MultipartRespBody::Meter),
+                               prop::collection::vec(meter_config(min), min..4)
+                                   .prop_map(MultipartRespBody::MeterConfig),
+                               meter_features().prop_map(MultipartRespBody::MeterFeatures)]
+        .boxed())
+}
+
+fn multipart_req(body: BoxedStrategy<MultipartReqBody>) -> BoxedStrategy<Message> {
+    (any::<bool>(), body)
+        .prop_map(|(more, body)| {
+            Message::MultipartRequest(MultipartReq {
+                more: more,
+                body: body,
+            })
+        })
+        .boxed()
+}
+
+fn message() -> BoxedStrategy<Message> {
+    let port_descs = prop::collection::vec(port_desc(), 0..4)
+        .prop_map(MultipartRespBody::PortDesc)
+        .boxed();
+    let group_reqs = prop_oneof![Just(MultipartReqBody::PortDesc),
+                                 prop::option::of(0..0xffff_fffcu32).prop_map(|group_id| {
+                                     MultipartReqBody::Group(GroupStatsRequest {
+                                         group_id: group_id,
+                                     })
+                                 }),
+                                 Just(MultipartReqBody::GroupDesc),
+                                 Just(MultipartReqBody::GroupFeatures)]
+        .boxed();
+    let meter_request = || {
+        prop::option::of(0..0xffff_ffffu32)
+            .prop_map(|meter_id| MeterMultipartRequest { meter_id: meter_id })
+    };
+    let meter_reqs = prop_oneof![meter_request().prop_map(MultipartReqBody::Meter),
+                                 meter_request().prop_map(MultipartReqBody::MeterConfig),
+                                 Just(MultipartReqBody::MeterFeatures)]
+        .boxed();
+    prop_oneof![Just(Message::Hello),
+                bytes(64).prop_map(Message::EchoRequest),
+                bytes(64).prop_map(Message::EchoReply),
+                Just(Message::FeaturesReq),
+                flow_mod().prop_map(Message::FlowMod),
+                packet_in().prop_map(Message::PacketIn),
+                group_mod().prop_map(Message::GroupMod),
+                meter_mod().prop_map(Message::MeterMod),
+                multipart_req(group_reqs),
+                multipart_req(meter_reqs),
+                multipart_resp(port_descs),
+                group_multipart_resp(0),
+                meter_multipart_resp(0),
+                Just(Message::BarrierRequest),
+                Just(Message::BarrierReply)]
+        .boxed()
+}
+
+#[test]
+fn header_version() {
+    let bytes = Message::marshal(7, Message::Hello).unwrap();
+    assert_eq!(bytes, vec![0x04, 0, 0, 8, 0, 0, 0, 7]);
+    let msg = Message::FlowMod(message::add_flow(0, Pattern::match_all(), vec![]));
+    assert_eq!(Message::marshal(0, msg).unwrap()[0], 0x04);
+}
+
+/// Offset of the match in a marshaled flow mod, past the header and fixed fields.
+const FLOW_MOD_MATCH: usize = 48;
+
+#[test]
+fn oxm_tlv_encoding() {
+    let pattern = Pattern {
+        fields: vec![Oxm::InPort(3),
+                     Oxm::EthDst(Mask {
+                         value: 0x0a0b_0c0d_0e0f,
+                         mask: Some(0xffff_ffff_ff00),
+                     }),
+                     Oxm::Ipv4Src(Mask {
+                         value: 0x0a00_0001,
+                         mask: None,
+                     }),
+                     Oxm::Other {
+                         class: 0xffff,
+                         field: 1,
+                         hasmask: false,
+                         body: vec![9, 9],
+                     }],
+    };
+    let msg = Message::FlowMod(message::add_flow(0, pattern, vec![]));
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[FLOW_MOD_MATCH..],
+               &[0, 1, 0, 42,
+                 0x80, 0, 0, 4, 0, 0, 0, 3,
+                 0x80, 0, 7, 12, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                 0xff, 0xff, 0xff, 0xff, 0xff, 0,
+                 0x80, 0, 0x16, 4, 0x0a, 0, 0, 1,
+                 0xff, 0xff, 2, 2, 9, 9,
+                 0, 0, 0, 0, 0, 0][..]);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+#[test]
+fn oxm_mask_on_unmaskable_field_rejected() {
+    let pattern = Pattern { fields: vec![Oxm::InPort(3)] };
+    let msg = Message::FlowMod(message::add_flow(0, pattern, vec![]));
+    let mut bytes = Message::marshal(0, msg).unwrap();
+    bytes[FLOW_MOD_MATCH + 6] |= 1;
+    assert_eq!(parse(&bytes), Err(ParseError::UnknownValue("oxm_hasmask", 0)));
+}
+
+#[test]
+fn instruction_padding() {
+    let instructions = vec![Instruction::GotoTable(3),
+                            Instruction::ApplyActions(vec![Action::SetField(Oxm::EthType(0x0800))]),
+                            Instruction::Meter(5)];
+    let msg = Message::FlowMod(message::add_flow(0, Pattern::match_all(), instructions));
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[FLOW_MOD_MATCH + 8..],
+               &[0, 1, 0, 8, 3, 0, 0, 0,
+                 0, 4, 0, 24, 0, 0, 0, 0,
+                 0, 25, 0, 16, 0x80, 0, 10, 2, 0x08, 0, 0, 0, 0, 0, 0, 0,
+                 0, 6, 0, 8, 0, 0, 0, 5][..]);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+/// Bucket outputting to `port`, 32 bytes long once marshaled.
+fn output_bucket(port: u32) -> Bucket {
+    Bucket {
+        weight: 1,
+        watch_port: None,
+        watch_group: None,
+        actions: vec![Action::Output(PseudoPort::PhysicalPort(port))],
+    }
+}
+
+/// Offset of the first element of a multipart reply body, past the header and multipart
+/// header.
+const MULTIPART_BODY: usize = 16;
+
+#[test]
+fn group_desc_reply_with_several_groups() {
+    let desc = |group_id, buckets| {
+        GroupDesc {
+            typ: GroupType::Select,
+            group_id: group_id,
+            buckets: buckets,
+        }
+    };
+    let msg = Message::MultipartReply(MultipartResp {
+        more: false,
+        body: MultipartRespBody::GroupDesc(vec![desc(1, vec![output_bucket(1),
+                                                             output_bucket(2)]),
+                                                desc(2, vec![output_bucket(3),
+                                                             output_bucket(4),
+                                                             output_bucket(5)])]),
+    });
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[8..10], &[0, 7]);
+    let first = MULTIPART_BODY;
+    assert_eq!(&bytes[first..first + 8], &[0, 72, 1, 0, 0, 0, 0, 1]);
+    assert_eq!(&bytes[first + 8..first + 12], &[0, 32, 0, 1]);
+    assert_eq!(&bytes[first + 40..first + 44], &[0, 32, 0, 1]);
+    let second = first + 72;
+    assert_eq!(&bytes[second..second + 8], &[0, 104, 1, 0, 0, 0, 0, 2]);
+    assert_eq!(bytes.len(), second + 104);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+#[test]
+fn group_stats_reply_with_several_groups() {
+    let stats = |group_id, buckets| {
+        GroupStats {
+            group_id: group_id,
+            ref_count: 2,
+            packet_count: 10,
+            byte_count: 1000,
+            duration_sec: 3,
+            duration_nsec: 4,
+            bucket_stats: (0..buckets)
+                .map(|n| {
+                    BucketCounter {
+                        packet_count: n,
+                        byte_count: 100 * n,
+                    }
+                })
+                .collect(),
+        }
+    };
+    let msg = Message::MultipartReply(MultipartResp {
+        more: true,
+        body: MultipartRespBody::Group(vec![stats(1, 2), stats(2, 3), stats(3, 0)]),
+    });
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[8..12], &[0, 6, 0, 1]);
+    let lengths = [72, 88, 40];
+    let mut offset = MULTIPART_BODY;
+    for &len in &lengths {
+        assert_eq!(&bytes[offset..offset + 2], &[0, len]);
+        offset += len as usize;
+    }
+    assert_eq!(bytes.len(), offset);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+#[test]
+fn group_stats_bad_bucket_length_rejected() {
+    let msg = Message::MultipartReply(MultipartResp {
+        more: false,
+        body: MultipartRespBody::Group(vec![GroupStats {
+                                                group_id: 1,
+                                                ref_count: 0,
+                                                packet_count: 0,
+                                                byte_count: 0,
+                                                duration_sec: 0,
+                                                duration_nsec: 0,
+                                                bucket_stats: vec![],
+                                            }]),
+    });
+    let mut bytes = Message::marshal(0, msg).unwrap();
+    bytes[MULTIPART_BODY + 1] = 44;
+    assert_eq!(parse(&bytes), Err(ParseError::BadLength("ofp_group_stats", 44)));
+}
+
+#[test]
+fn group_features_reply() {
+    let features = GroupFeatures {
+        types: 0xf,
+        capabilities: GroupCapabilities {
+            select_weight: true,
+            select_liveness: false,
+            chaining: true,
+            chaining_checks: false,
+        },
+        max_groups: [1, 2, 3, 4],
+        actions: [5, 6, 7, 8],
+    };
+    let msg = Message::MultipartReply(MultipartResp {
+        more: false,
+        body: MultipartRespBody::GroupFeatures(features),
+    });
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[8..10], &[0, 8]);
+    assert_eq!(&bytes[MULTIPART_BODY..MULTIPART_BODY + 12],
+               &[0, 0, 0, 0xf, 0, 0, 0, 5, 0, 0, 0, 1]);
+    assert_eq!(bytes.len(), MULTIPART_BODY + 40);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+/// Meter flags with rates in kb/s and burst sizes applied.
+fn kbps_burst() -> MeterFlags {
+    MeterFlags {
+        kbps: true,
+        pktps: false,
+        burst: true,
+        stats: false,
+    }
+}
+
+#[test]
+fn meter_band_layouts() {
+    let bands = vec![MeterBand::Drop {
+                         rate: 1000,
+                         burst_size: 10,
+                     },
+                     MeterBand::DscpRemark {
+                         rate: 2000,
+                         burst_size: 20,
+                         prec_level: 3,
+                     },
+                     MeterBand::Experimenter {
+                         rate: 3000,
+                         burst_size: 30,
+                         experimenter: 0x2320,
+                     }];
+    let msg = Message::MeterMod(message::add_meter(1, kbps_burst(), bands));
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[..16], &[0x04, 29, 0, 64, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 1]);
+    assert_eq!(&bytes[16..],
+               &[0, 1, 0, 16, 0, 0, 0x03, 0xe8, 0, 0, 0, 10, 0, 0, 0, 0,
+                 0, 2, 0, 16, 0, 0, 0x07, 0xd0, 0, 0, 0, 20, 3, 0, 0, 0,
+                 0xff, 0xff, 0, 16, 0, 0, 0x0b, 0xb8, 0, 0, 0, 30, 0, 0, 0x23, 0x20][..]);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+#[test]
+fn meter_band_bad_length_rejected() {
+    let band = MeterBand::Experimenter {
+        rate: 1,
+        burst_size: 1,
+        experimenter: 0x2320,
+    };
+    let msg = Message::MeterMod(message::add_meter(1, kbps_burst(), vec![band]));
+    let mut bytes = Message::marshal(0, msg).unwrap();
+    bytes[19] = 24;
+    assert_eq!(parse(&bytes), Err(ParseError::BadLength("ofp_meter_band", 24)));
+}
+
+#[test]
+fn meter_stats_reply_with_several_meters() {
+    let stats = |meter_id, bands| {
+        MeterStats {
+            meter_id: meter_id,
+            flow_count: 2,
+            packet_in_count: 10,
+            byte_in_count: 1000,
+            duration_sec: 3,
+            duration_nsec: 4,
+            band_stats: (0..bands)
+                .map(|n| {
+                    MeterBandStats {
+                        packet_band_count: n,
+                        byte_band_count: 100 * n,
+                    }
+                })
+                .collect(),
+        }
+    };
+    let msg = Message::MultipartReply(MultipartResp {
+        more: false,
+        body: MultipartRespBody::Meter(vec![stats(1, 3), stats(2, 0), stats(3, 1)]),
+    });
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[8..10], &[0, 9]);
+    let mut offset = MULTIPART_BODY;
+    for &(meter_id, len) in &[(1, 88), (2, 40), (3, 56)] {
+        assert_eq!(&bytes[offset..offset + 6], &[0, 0, 0, meter_id, 0, len]);
+        offset += len as usize;
+    }
+    assert_eq!(bytes.len(), offset);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+#[test]
+fn meter_config_reply_with_several_meters() {
+    let drop = |rate| {
+        MeterBand::Drop {
+            rate: rate,
+            burst_size: 0,
+        }
+    };
+    let config = |meter_id, bands| {
+        MeterConfig {
+            flags: kbps_burst(),
+            meter_id: meter_id,
+            bands: bands,
+        }
+    };
+    let msg = Message::MultipartReply(MultipartResp {
+        more: false,
+        body: MultipartRespBody::MeterConfig(vec![config(1, vec![drop(1), drop(2)]),
+                                                  config(2, vec![]),
+                                                  config(3, vec![drop(3)])]),
+    });
+    let bytes = Message::marshal(0, msg.clone()).unwrap();
+    assert_eq!(&bytes[8..10], &[0, 10]);
+    let mut offset = MULTIPART_BODY;
+    for &(meter_id, len) in &[(1, 40), (2, 8), (3, 24)] {
+        assert_eq!(&bytes[offset..offset + 8], &[0, len, 0, 5, 0, 0, 0, meter_id]);
+        offset += len as usize;
+    }
+    assert_eq!(bytes.len(), offset);
+    assert_eq!(parse(&bytes), Ok(msg));
+}
+
+proptest! {
+    #[test]
+    fn message_roundtrip(msg in message()) {
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn pattern_roundtrip(pattern in pattern()) {
+        let msg = Message::FlowMod(message::add_flow(0, pattern, vec![]));
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn instruction_roundtrip(ins in instruction()) {
+        let msg = Message::FlowMod(message::add_flow(0, Pattern::match_all(), vec![ins]));
+        let bytes = Message::marshal(0, msg.clone()).unwrap();
+        prop_assert_eq!(bytes.len() % 8, 0);
+        prop_assert_eq!(parse(&bytes), Ok(msg));
+    }
+
+    #[test]
+    fn group_multipart_roundtrip(msg in group_multipart_resp(2)) {
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn meter_multipart_roundtrip(msg in meter_multipart_resp(2)) {
+        prop_assert_eq!(roundtrip(msg.clone()), msg);
+    }
+
+    #[test]
+    fn message_parse_does_not_panic(typ in 0..30u8, body in bytes(256)) {
+        let header = OfpHeader::new(0x04, typ, (OfpHeader::size() + body.len()) as u16, 0);
+        let _ = Message::parse(&header, &body);
+    }
+}
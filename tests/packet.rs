@@ -0,0 +1,419 @@
+extern crate proptest;
+extern crate rust_ofp;
+
+use proptest::prelude::*;
+
+use rust_ofp::packet::*;
+
+const MAC_A: u64 = 0x0000_0000_0001;
+const MAC_B: u64 = 0x0000_0000_0002;
+const IP_A: u32 = 0x0a00_0001;
+const IP_B: u32 = 0x0a00_0002;
+
+/// Ones' complement sum of `data`, which is 0xffff over data including a valid checksum.
+fn ones_sum(sum: u32, data: &[u8]) -> u16 {
+    let mut sum = data.chunks(2).fold(sum, |sum, w| {
+        sum + ((w[0] as u32) << 8 | w.get(1).cloned().unwrap_or(0) as u32)
+    });
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+fn pseudo_header(proto: u8, len: usize) -> u32 {
+    (IP_A >> 16) + (IP_A & 0xffff) + (IP_B >> 16) + (IP_B & 0xffff) + proto as u32 + len as u32
+}
+
+#[test]
+fn tcp_rst() {
+    let flags = TcpFlags {
+        rst: true,
+        ack: true,
+        ..TcpFlags::default()
+    };
+    let pk = PacketBuilder::new(MAC_A, MAC_B)
+        .tcp(IP_A, IP_B, Tcp::new(80, 41000, 7, 9, flags.clone(), vec![1, 2, 3]));
+    let frame = Packet::marshal(&pk);
+    assert_eq!(frame.len(), 14 + 20 + 20 + 3);
+    assert_eq!(&frame[12..14], &[0x08, 0x00]);
+    // RST and ACK are bits 2 and 4 of the byte following the data offset.
+    assert_eq!(&frame[46..48], &[0x50, 0x14]);
+    assert_eq!(ones_sum(0, &frame[14..34]), 0xffff);
+    assert_eq!(ones_sum(pseudo_header(6, 23), &frame[34..]), 0xffff);
+
+    let parsed = Packet::parse(&frame).unwrap();
+    match parsed.nw {
+        Nw::Ip(ref ip) => {
+            assert_eq!((ip.src, ip.dst, ip.ttl), (IP_A, IP_B, 64));
+            match ip.tp {
+                Tp::Tcp(ref tcp) => {
+                    assert_eq!((tcp.src, tcp.dst, tcp.seq, tcp.ack), (80, 41000, 7, 9));
+                    assert_eq!(tcp.flags, flags);
+                    assert_eq!(tcp.payload, vec![1, 2, 3]);
+                }
+                ref tp => panic!("expected TCP, got {:?}", tp),
+            }
+        }
+        ref nw => panic!("expected IPv4, got {:?}", nw),
+    }
+    assert_eq!(Packet::marshal(&parsed), frame);
+}
+
+#[test]
+fn udp_checksum_and_length() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B).udp(IP_A, IP_B, Udp::new(68, 67, vec![0xab; 5]));
+    let frame = Packet::marshal(&pk);
+    assert_eq!(&frame[16..18], &[0, 20 + 8 + 5]);
+    assert_eq!(&frame[38..40], &[0, 8 + 5]);
+    assert_eq!(ones_sum(0, &frame[14..34]), 0xffff);
+    assert_eq!(ones_sum(pseudo_header(17, 13), &frame[34..]), 0xffff);
+    match Packet::parse(&frame).unwrap().nw {
+        Nw::Ip(Ip { tp: Tp::Udp(ref udp), .. }) => {
+            assert_eq!((udp.src, udp.dst), (68, 67));
+            assert_eq!(udp.payload, vec![0xab; 5]);
+        }
+        ref nw => panic!("expected UDP, got {:?}", nw),
+    }
+}
+
+#[test]
+fn icmp_echo_reply() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B)
+        .icmp(IP_A, IP_B, Icmp::new(0, 0, vec![0, 1, 0, 2, 0xde, 0xad, 0xbe]));
+    let frame = Packet::marshal(&pk);
+    assert_eq!(frame[23], 1);
+    assert_eq!(ones_sum(0, &frame[34..]), 0xffff);
+    assert_eq!(Packet::marshal(&Packet::parse(&frame).unwrap()), frame);
+}
+
+#[test]
+fn ip_flags_and_options() {
+    let mut ip = Ip::new(IP_A, IP_B, Tp::Unparsable(0x59, vec![9; 4]));
+    ip.flags.more_fragments = true;
+    ip.frag = 0x123;
+    ip.options = vec![1, 1, 1];
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).ip(ip));
+    assert_eq!(frame[14], 0x46);
+    assert_eq!(&frame[20..22], &[0x21, 0x23]);
+    assert_eq!(ones_sum(0, &frame[14..38]), 0xffff);
+    match Packet::parse(&frame).unwrap().nw {
+        Nw::Ip(ref ip) => {
+            assert!(ip.flags.more_fragments && !ip.flags.dont_fragment);
+            assert_eq!(ip.frag, 0x123);
+            assert_eq!(ip.options, vec![1, 1, 1, 0]);
+            assert_eq!(ip.tp, Tp::Unparsable(0x59, vec![9; 4]));
+        }
+        ref nw => panic!("expected IPv4, got {:?}", nw),
+    }
+}
+
+#[test]
+fn vlan_arp_reply() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B)
+        .vlan(42, 5)
+        .arp(Arp::Reply(MAC_A, IP_A, MAC_B, IP_B));
+    let frame = Packet::marshal(&pk);
+    assert_eq!(&frame[12..18], &[0x81, 0x00, 0xa0, 42, 0x08, 0x06]);
+    assert_eq!(frame.len(), 18 + 28);
+    assert_eq!(Packet::parse(&frame).unwrap(), pk);
+}
+
+#[test]
+fn raw_ethertype() {
+    let pk = PacketBuilder::new(MAC_A, 0x0180_c200_000e).raw(0x88cc, vec![2, 7, 4]);
+    let frame = Packet::marshal(&pk);
+    assert_eq!(&frame[..6], &[0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e]);
+    assert_eq!(&frame[12..], &[0x88, 0xcc, 2, 7, 4]);
+    assert_eq!(Packet::parse(&frame).unwrap(), pk);
+}
+
+fn tcp_of(pk: &Packet) -> (&Ip, &Tcp) {
+    match pk.nw {
+        Nw::Ip(ref ip) => {
+            match ip.tp {
+                Tp::Tcp(ref tcp) => (ip, tcp),
+                ref tp => panic!("expected TCP, got {:?}", tp),
+            }
+        }
+        ref nw => panic!("expected IPv4, got {:?}", nw),
+    }
+}
+
+#[test]
+fn checksums_validated() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B)
+        .tcp(IP_A, IP_B, Tcp::new(80, 41000, 1, 2, TcpFlags::default(), vec![1, 2, 3]));
+    let mut frame = Packet::marshal(&pk);
+    {
+        let parsed = Packet::parse(&frame).unwrap();
+        let (ip, tcp) = tcp_of(&parsed);
+        assert_eq!((ip.chksum_valid, tcp.chksum_valid), (Some(true), Some(true)));
+    }
+    frame[56] ^= 0xff;
+    {
+        let parsed = Packet::parse(&frame).unwrap();
+        let (ip, tcp) = tcp_of(&parsed);
+        assert_eq!((ip.chksum_valid, tcp.chksum_valid), (Some(true), Some(false)));
+    }
+    frame[22] -= 1;
+    let parsed = Packet::parse(&frame).unwrap();
+    assert_eq!(tcp_of(&parsed).0.chksum_valid, Some(false));
+
+    let udp = PacketBuilder::new(MAC_A, MAC_B).udp(IP_A, IP_B, Udp::new(1, 2, vec![5; 7]));
+    let mut frame = Packet::marshal(&udp);
+    frame[40] = 0;
+    frame[41] = 0;
+    match Packet::parse(&frame).unwrap().nw {
+        Nw::Ip(Ip { tp: Tp::Udp(ref udp), .. }) => assert_eq!(udp.chksum_valid, None),
+        ref nw => panic!("expected UDP, got {:?}", nw),
+    }
+}
+
+#[test]
+fn tcp_options() {
+    let mut tcp = Tcp::new(80, 41000, 1, 2, TcpFlags::default(), vec![7; 4]);
+    tcp.options = vec![2, 4, 5, 0xb4, 1];
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).tcp(IP_A, IP_B, tcp));
+    assert_eq!(frame[46] >> 4, 7);
+    let parsed = Packet::parse(&frame).unwrap();
+    let (_, tcp) = tcp_of(&parsed);
+    assert_eq!(tcp.offset, 7);
+    assert_eq!(tcp.options, vec![2, 4, 5, 0xb4, 1, 0, 0, 0]);
+    assert_eq!(tcp.payload, vec![7; 4]);
+    assert_eq!(tcp.chksum_valid, Some(true));
+}
+
+#[test]
+fn ethernet_padding_ignored() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B).udp(IP_A, IP_B, Udp::new(1, 2, vec![5; 2]));
+    let mut frame = Packet::marshal(&pk);
+    frame.resize(60, 0);
+    match Packet::parse(&frame).unwrap().nw {
+        Nw::Ip(Ip { tp: Tp::Udp(ref udp), .. }) => {
+            assert_eq!(udp.payload, vec![5; 2]);
+            assert_eq!(udp.chksum_valid, Some(true));
+        }
+        ref nw => panic!("expected UDP, got {:?}", nw),
+    }
+}
+
+#[test]
+fn truncated_capture() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B)
+        .tcp(IP_A, IP_B, Tcp::new(80, 41000, 1, 2, TcpFlags::default(), vec![9; 100]));
+    let frame = Packet::marshal(&pk);
+    let parsed = Packet::parse(&frame[..64]).unwrap();
+    let (ip, tcp) = tcp_of(&parsed);
+    assert_eq!(ip.chksum_valid, Some(true));
+    assert_eq!(tcp.chksum_valid, None);
+    assert_eq!(tcp.payload, vec![9; 10]);
+
+    match Packet::parse(&frame[..40]).unwrap().nw {
+        Nw::Ip(Ip { tp: Tp::Unparsable(6, ref buf), .. }) => assert_eq!(buf.len(), 6),
+        ref nw => panic!("expected an unparsable transport, got {:?}", nw),
+    }
+    match Packet::parse(&frame[..20]).unwrap().nw {
+        Nw::Unparsable(0x0800, ref buf) => assert_eq!(buf.len(), 6),
+        ref nw => panic!("expected an unparsable network header, got {:?}", nw),
+    }
+    assert_eq!(Packet::parse(&frame[..10]), Err(rust_ofp::parse_error::ParseError::Truncated));
+}
+
+#[test]
+fn bad_ip_lengths() {
+    let pk = PacketBuilder::new(MAC_A, MAC_B).udp(IP_A, IP_B, Udp::new(1, 2, vec![]));
+    let frame = Packet::marshal(&pk);
+    let mut short_ihl = frame.clone();
+    short_ihl[14] = 0x44;
+    let mut short_total = frame.clone();
+    short_total[17] = 19;
+    for frame in &[short_ihl, short_total] {
+        match Packet::parse(frame).unwrap().nw {
+            Nw::Unparsable(0x0800, _) => (),
+            ref nw => panic!("expected an unparsable network header, got {:?}", nw),
+        }
+    }
+}
+
+const IP6_A: u128 = 0xfe80_0000_0000_0000_0000_0000_0000_0001;
+const IP6_B: u128 = 0xff02_0000_0000_0000_0000_0001_ff00_0002;
+
+fn pseudo_header_v6(proto: u8, len: usize) -> u32 {
+    (0..8).fold(proto as u32 + len as u32, |sum, i| {
+        sum + ((IP6_A >> (16 * i)) as u32 & 0xffff) + ((IP6_B >> (16 * i)) as u32 & 0xffff)
+    })
+}
+
+fn ipv6_of(pk: &Packet) -> &Ipv6 {
+    match pk.nw {
+        Nw::Ipv6(ref ip) => ip,
+        ref nw => panic!("expected IPv6, got {:?}", nw),
+    }
+}
+
+fn icmpv6_roundtrip(msg: Icmpv6Msg) -> Vec<u8> {
+    let mut ip = Ipv6::new(IP6_A, IP6_B, Tp::Icmpv6(Icmpv6::new(msg.clone())));
+    ip.hop_limit = 255;
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).ipv6(ip));
+    assert_eq!(&frame[12..14], &[0x86, 0xdd]);
+    assert_eq!((frame[20], frame[21]), (58, 255));
+    let len = frame.len() - 54;
+    assert_eq!(&frame[18..20], &[(len >> 8) as u8, len as u8]);
+    assert_eq!(ones_sum(pseudo_header_v6(58, len), &frame[54..]), 0xffff);
+    let parsed = Packet::parse(&frame).unwrap();
+    let ip = ipv6_of(&parsed);
+    assert_eq!((ip.src, ip.dst, ip.hop_limit), (IP6_A, IP6_B, 255));
+    match ip.tp {
+        Tp::Icmpv6(ref icmp) => {
+            assert_eq!(icmp.chksum_valid, Some(true));
+            assert_eq!(icmp.msg, msg);
+        }
+        ref tp => panic!("expected ICMPv6, got {:?}", tp),
+    }
+    frame
+}
+
+#[test]
+fn icmpv6_neighbor_solicit() {
+    let frame = icmpv6_roundtrip(Icmpv6Msg::NeighborSolicit(NeighborSolicit {
+        target: IP6_A + 1,
+        options: vec![NdpOption::SourceLinkLayerAddr(MAC_A)],
+    }));
+    assert_eq!(frame[54], 135);
+    assert_eq!(&frame[78..80], &[1, 1]);
+    assert_eq!(frame.len(), 54 + 24 + 8);
+}
+
+#[test]
+fn icmpv6_neighbor_advert() {
+    let frame = icmpv6_roundtrip(Icmpv6Msg::NeighborAdvert(NeighborAdvert {
+        router: false,
+        solicited: true,
+        override_flag: true,
+        target: IP6_A,
+        options: vec![NdpOption::TargetLinkLayerAddr(MAC_A)],
+    }));
+    assert_eq!(frame[54], 136);
+    assert_eq!(frame[58], 0x60);
+}
+
+#[test]
+fn icmpv6_router_advert() {
+    let mut rdnss = vec![0, 0, 0, 0, 0x0e, 0x10];
+    rdnss.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53]);
+    let frame = icmpv6_roundtrip(Icmpv6Msg::RouterAdvert(RouterAdvert {
+        hop_limit: 64,
+        managed: true,
+        other_config: false,
+        lifetime: 1800,
+        reachable_time: 0,
+        retrans_timer: 0,
+        options: vec![NdpOption::SourceLinkLayerAddr(MAC_A),
+                      NdpOption::Mtu(1500),
+                      NdpOption::PrefixInfo {
+                          prefix_len: 64,
+                          on_link: true,
+                          autonomous: true,
+                          valid_lifetime: 86400,
+                          preferred_lifetime: 14400,
+                          prefix: 0x2001_0db8_0000_0000_0000_0000_0000_0000,
+                      },
+                      NdpOption::Unparsable(25, rdnss)],
+    }));
+    assert_eq!(frame[54], 134);
+    assert_eq!(frame[59], 0x80);
+    icmpv6_roundtrip(Icmpv6Msg::Unparsable(128, 0, vec![0, 1, 0, 2, 0xaa]));
+}
+
+#[test]
+fn ipv6_extension_headers() {
+    let mut ip = Ipv6::new(IP6_A,
+                           IP6_B,
+                           Tp::Tcp(Tcp::new(80, 41000, 1, 2, TcpFlags::default(), vec![3; 5])));
+    ip.exts = vec![Ipv6Ext::HopByHop(vec![1, 4, 0, 0, 0, 0]),
+                   Ipv6Ext::Fragment {
+                       offset: 0,
+                       more_fragments: false,
+                       ident: 7,
+                   },
+                   Ipv6Ext::DestOpts(vec![1, 4, 0, 0, 0, 0])];
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).ipv6(ip.clone()));
+    assert_eq!(frame[20], 0);
+    assert_eq!((frame[54], frame[62], frame[70]), (44, 60, 6));
+    let parsed = Packet::parse(&frame).unwrap();
+    let parsed_ip = ipv6_of(&parsed);
+    assert_eq!(parsed_ip.exts, ip.exts);
+    match parsed_ip.tp {
+        Tp::Tcp(ref tcp) => {
+            assert_eq!(tcp.chksum_valid, Some(true));
+            assert_eq!(tcp.payload, vec![3; 5]);
+        }
+        ref tp => panic!("expected TCP, got {:?}", tp),
+    }
+    assert_eq!(Packet::marshal(&parsed), frame);
+
+    let mut truncated = frame[..60].to_vec();
+    truncated[19] = 6;
+    match Packet::parse(&truncated).unwrap().nw {
+        Nw::Unparsable(0x86dd, _) => (),
+        ref nw => panic!("expected an unparsable network header, got {:?}", nw),
+    }
+}
+
+#[test]
+fn ipv6_fragments() {
+    let mut ip = Ipv6::new(IP6_A, IP6_B, Tp::Udp(Udp::new(1, 2, vec![4; 16])));
+    ip.exts = vec![Ipv6Ext::Fragment {
+                       offset: 0,
+                       more_fragments: true,
+                       ident: 9,
+                   }];
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).ipv6(ip.clone()));
+    match ipv6_of(&Packet::parse(&frame).unwrap()).tp {
+        Tp::Udp(ref udp) => assert_eq!(udp.chksum_valid, None),
+        ref tp => panic!("expected UDP, got {:?}", tp),
+    }
+
+    ip.exts = vec![Ipv6Ext::Fragment {
+                       offset: 3,
+                       more_fragments: false,
+                       ident: 9,
+                   }];
+    ip.tp = Tp::Unparsable(17, vec![4; 8]);
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).ipv6(ip.clone()));
+    assert_eq!(Packet::parse(&frame).unwrap().nw, Nw::Ipv6(ip));
+}
+
+#[test]
+fn ipv6_udp() {
+    let ip = Ipv6::new(IP6_A, IP6_B, Tp::Udp(Udp::new(546, 547, vec![1, 2, 3])));
+    let frame = Packet::marshal(&PacketBuilder::new(MAC_A, MAC_B).vlan(3, 0).ipv6(ip));
+    assert_eq!(ones_sum(pseudo_header_v6(17, 11), &frame[58..]), 0xffff);
+    match ipv6_of(&Packet::parse(&frame).unwrap()).tp {
+        Tp::Udp(ref udp) => {
+            assert_eq!((udp.src, udp.dst), (546, 547));
+            assert_eq!(udp.chksum_valid, Some(true));
+        }
+        ref tp => panic!("expected UDP, got {:?}", tp),
+    }
+}
+
+proptest! {
+    #[test]
+    fn parse_does_not_panic(typ in prop_oneof![Just(0x0800u16),
+                                               Just(0x0806),
+                                               Just(0x86dd),
+                                               any::<u16>()],
+                            body in prop::collection::vec(any::<u8>(), 0..96)) {
+        let mut frame = vec![0; 12];
+        frame.push((typ >> 8) as u8);
+        frame.push(typ as u8);
+        frame.extend(body);
+        for len in 0..frame.len() + 1 {
+            if let Ok(pk) = Packet::parse(&frame[..len]) {
+                Packet::marshal(&pk);
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+#![cfg(feature = "tokio")]
+
+extern crate rust_ofp;
+extern crate tokio;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_ofp::ofp_controller::openflow0x01::{OF0x01Controller, Switches};
+use rust_ofp::ofp_controller::openflow0x01::tokio_runtime::AsyncRuntime;
+use rust_ofp::ofp_header::OfpHeader;
+use rust_ofp::ofp_message::OfpMessage;
+use rust_ofp::openflow0x01::{FragHandling, PacketIn, SwitchConfig, SwitchFeatures};
+use rust_ofp::openflow0x01::message::Message;
+
+/// Callbacks invoked on `Recorder`, in order.
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Controller recording the switches that connect and disconnect. Blocks in `switch_connected`
+/// until the switch answers a configuration request, which can only happen if the connection
+/// is still being served while the callback runs.
+struct Recorder;
+
+impl OF0x01Controller for Recorder {
+    fn new() -> Recorder {
+        Recorder
+    }
+
+    fn switch_connected(&mut self, sw: u64, _: SwitchFeatures, switches: &Switches) {
+        let reply = switches.send_request(sw, Message::GetConfigRequest).unwrap();
+        let event = match reply.wait_timeout(Duration::from_secs(5)) {
+            Ok(Message::GetConfigReply(config)) => {
+                format!("connected {} {}", sw, config.miss_send_len)
+            }
+            _ => format!("connected {} without config", sw),
+        };
+        EVENTS.lock().unwrap().push(event)
+    }
+
+    fn switch_disconnected(&mut self, sw: u64) {
+        EVENTS.lock().unwrap().push(format!("disconnected {}", sw))
+    }
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+}
+
+/// Controller allowing switches 50 milliseconds to complete their handshake.
+struct Impatient;
+
+impl OF0x01Controller for Impatient {
+    fn new() -> Impatient {
+        Impatient
+    }
+
+    fn switch_connected(&mut self, _: u64, _: SwitchFeatures, _: &Switches) {}
+
+    fn switch_disconnected(&mut self, _: u64) {}
+
+    fn packet_in(&mut self, _: u64, _: u32, _: PacketIn, _: &Switches) {}
+
+    fn handshake_timeout(&self) -> Duration {
+        Duration::from_millis(50)
+    }
+}
+
+/// Serve `rt` on a loopback listener from a new tokio runtime, returning the listener address.
+fn serve<Cntl: OF0x01Controller + Send + 'static>(rt: &AsyncRuntime<Cntl>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let serve = {
+        let _guard = runtime.enter();
+        rt.run(tokio::net::TcpListener::from_std(listener).unwrap())
+    };
+    thread::spawn(move || runtime.block_on(serve));
+    addr
+}
+
+/// Read the next message the controller sent on `stream`.
+fn read_msg(stream: &mut TcpStream) -> (u32, Message) {
+    let mut buf = [0; 8];
+    stream.read_exact(&mut buf).unwrap();
+    let header = OfpHeader::parse(buf);
+    let mut body = vec![0; header.length() - OfpHeader::size()];
+    stream.read_exact(&mut body).unwrap();
+    Message::parse(&header, &body).unwrap()
+}
+
+fn send_msg(stream: &mut TcpStream, xid: u32, msg: Message) {
+    stream.write_all(&Message::marshal(xid, msg).unwrap()).unwrap()
+}
+
+fn wait_until<F: Fn() -> bool>(cond: F) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+        thread::sleep(Duration::from_millis(10))
+    }
+}
+
+#[test]
+fn handshake_over_loopback() {
+    let rt = AsyncRuntime::<Recorder>::new();
+    let mut stream = TcpStream::connect(serve(&rt)).unwrap();
+    match read_msg(&mut stream) {
+        (_, Message::Hello) => (),
+        (_, msg) => panic!("expected hello, got {:?}", msg),
+    }
+    send_msg(&mut stream, 1, Message::Hello);
+    let (xid, msg) = read_msg(&mut stream);
+    match msg {
+        Message::FeaturesReq => (),
+        msg => panic!("expected features request, got {:?}", msg),
+    }
+    let mut features = vec![0x01, 6, 0, 32];
+    for shift in &[24, 16, 8, 0] {
+        features.push((xid >> shift) as u8)
+    }
+    features.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 7]);
+    features.extend_from_slice(&[0; 16]);
+    stream.write_all(&features).unwrap();
+
+    let (xid, msg) = read_msg(&mut stream);
+    match msg {
+        Message::GetConfigRequest => (),
+        msg => panic!("expected configuration request, got {:?}", msg),
+    }
+    send_msg(&mut stream, 3, Message::EchoRequest(vec![1, 2, 3]));
+    match read_msg(&mut stream) {
+        (3, Message::EchoReply(bytes)) => assert_eq!(bytes, vec![1, 2, 3]),
+        (_, msg) => panic!("expected echo reply, got {:?}", msg),
+    }
+    let config = SwitchConfig {
+        frag: FragHandling::Normal,
+        miss_send_len: 128,
+    };
+    send_msg(&mut stream, xid, Message::GetConfigReply(config));
+    wait_until(|| !EVENTS.lock().unwrap().is_empty());
+    assert!(rt.switches().is_connected(7));
+
+    drop(stream);
+    wait_until(|| !rt.switches().is_connected(7));
+    wait_until(|| EVENTS.lock().unwrap().len() == 2);
+    assert_eq!(*EVENTS.lock().unwrap(),
+               vec!["connected 7 128".to_string(), "disconnected 7".to_string()]);
+}
+
+#[test]
+fn silent_switch_times_out() {
+    let rt = AsyncRuntime::<Impatient>::new();
+    let mut stream = TcpStream::connect(serve(&rt)).unwrap();
+    match read_msg(&mut stream) {
+        (_, Message::Hello) => (),
+        (_, msg) => panic!("expected hello, got {:?}", msg),
+    }
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(stream.read(&mut [0; 8]).unwrap(), 0);
+}